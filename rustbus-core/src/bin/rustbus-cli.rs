@@ -0,0 +1,644 @@
+use rustbus_core::connection::{ConnectionParams, SerialPortParams, TcpParams};
+use rustbus_core::controller::{RetryOverride, RetryPolicy, Session, Transaction};
+use rustbus_core::data_type::{parse_bool, parse_u16, ByteOrder, DataType, DisplayFormat};
+use rustbus_core::project::Project;
+use rustbus_core::request::{request_data, response_data, RequestType};
+use rustbus_core::table::Table;
+use serde_json::{json, Value};
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio_modbus::prelude::{Request, Response};
+use tokio_serial::{DataBits, Parity, StopBits};
+
+const USAGE: &str = "\
+Usage:
+  rustbus-cli tcp <ip[:port]> [options] <command>
+  rustbus-cli rtu <serial port> [options] <command>
+  rustbus-cli project <file.json> [options] poll
+
+Commands:
+  read-coils <address> <quantity>
+  read-discrete <address> <quantity>
+  read-input <address> <quantity>
+  read-holding <address> <quantity>
+  write-coil <address> <0|1>
+  write-coils <address> <0|1>...
+  write-register <address> <value>
+  write-registers <address> <value>...
+  poll                       read the project's table every interval
+
+Options:
+  --unit <id>                slave id, defaults to 1 or the project's
+  --format <type>            u16 i16 u32 i32 f32 u64 i64 f64 ascii bitfield
+  --order <order>            abcd cdab badc dcba
+  --output <text|json|csv>   defaults to text
+  --timeout <ms>             per request, defaults to 1000 or the project's
+  --retries <n>              resends after a timeout, defaults to 0 or the project's
+  --interval <ms>            poll interval, defaults to the project's
+  --count <n>                polls before exiting, defaults to forever
+  --baud <rate>              serial only, defaults to 9600
+  --parity <none|odd|even>   serial only
+  --data-bits <5|6|7|8>      serial only
+  --stop-bits <1|2>          serial only
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone)]
+struct Options {
+    unit: Option<u8>,
+    data_type: DataType,
+    order: Option<ByteOrder>,
+    output: Output,
+    timeout: Option<Duration>,
+    retries: Option<u32>,
+    interval: Option<Duration>,
+    count: Option<u64>,
+    baudrate: u32,
+    parity: Parity,
+    data_bits: DataBits,
+    stop_bits: StopBits,
+}
+
+impl std::default::Default for Options {
+    fn default() -> Self {
+        Self {
+            unit: None,
+            data_type: DataType::U16,
+            order: None,
+            output: Output::Text,
+            timeout: None,
+            retries: None,
+            interval: None,
+            count: None,
+            baudrate: 9600,
+            parity: Parity::None,
+            data_bits: DataBits::Eight,
+            stop_bits: StopBits::One,
+        }
+    }
+}
+
+// One decoded value of a read, `number` is what goes into JSON when there is one.
+struct Reading {
+    address: u16,
+    text: String,
+    number: Option<f64>,
+}
+
+fn parse<T: std::str::FromStr>(name: &str, s: &str) -> Result<T, String> {
+    s.parse::<T>()
+        .map_err(|_e| format!("Invalid value for {}: {}", name, s))
+}
+
+fn parse_options(args: Vec<String>) -> Result<(Options, Vec<String>), String> {
+    let mut opts = Options::default();
+    let mut positional = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+        if arg == "--help" {
+            return Err(String::new());
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", arg))?;
+        match arg.as_str() {
+            "--unit" => opts.unit = Some(parse(&arg, &value)?),
+            "--format" => {
+                opts.data_type = *DataType::ALL
+                    .iter()
+                    .find(|x| x.to_string().eq_ignore_ascii_case(&value))
+                    .ok_or_else(|| format!("Unknown format {}", value))?
+            }
+            "--order" => {
+                opts.order = Some(
+                    *ByteOrder::ALL
+                        .iter()
+                        .find(|x| x.to_string().eq_ignore_ascii_case(&value))
+                        .ok_or_else(|| format!("Unknown byte order {}", value))?,
+                )
+            }
+            "--output" => {
+                opts.output = match value.as_str() {
+                    "text" => Output::Text,
+                    "json" => Output::Json,
+                    "csv" => Output::Csv,
+                    _ => return Err(format!("Unknown output {}", value)),
+                }
+            }
+            "--timeout" => opts.timeout = Some(Duration::from_millis(parse(&arg, &value)?)),
+            "--retries" => opts.retries = Some(parse(&arg, &value)?),
+            "--interval" => opts.interval = Some(Duration::from_millis(parse(&arg, &value)?)),
+            "--count" => opts.count = Some(parse(&arg, &value)?),
+            "--baud" => opts.baudrate = parse(&arg, &value)?,
+            "--parity" => {
+                opts.parity = match value.as_str() {
+                    "none" => Parity::None,
+                    "odd" => Parity::Odd,
+                    "even" => Parity::Even,
+                    _ => return Err(format!("Unknown parity {}", value)),
+                }
+            }
+            "--data-bits" => {
+                opts.data_bits = match value.as_str() {
+                    "5" => DataBits::Five,
+                    "6" => DataBits::Six,
+                    "7" => DataBits::Seven,
+                    "8" => DataBits::Eight,
+                    _ => return Err(format!("Unknown data bits {}", value)),
+                }
+            }
+            "--stop-bits" => {
+                opts.stop_bits = match value.as_str() {
+                    "1" => StopBits::One,
+                    "2" => StopBits::Two,
+                    _ => return Err(format!("Unknown stop bits {}", value)),
+                }
+            }
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    Ok((opts, positional))
+}
+
+fn tcp_params(target: &str, opts: &Options) -> Result<ConnectionParams, String> {
+    let (ip, port) = match target.split_once(':') {
+        Some((ip, port)) => (ip, parse("port", port)?),
+        None => (target, 502),
+    };
+    let ip: Ipv4Addr = parse("IP address", ip)?;
+    Ok(ConnectionParams::Tcp(TcpParams {
+        byte_order: opts.order.unwrap_or_default(),
+        ..TcpParams::new(ip.octets(), port, opts.unit.unwrap_or(1))
+    }))
+}
+
+fn serial_params(port: &str, opts: &Options) -> ConnectionParams {
+    ConnectionParams::Serial(SerialPortParams {
+        port: port.to_string(),
+        baudrate: opts.baudrate,
+        parity: opts.parity,
+        data_bits: opts.data_bits,
+        stop_bits: opts.stop_bits,
+        timeout: opts.timeout.unwrap_or(Duration::from_millis(1000)),
+        address: opts.unit.unwrap_or(1),
+        byte_order: opts.order.unwrap_or_default(),
+    })
+}
+
+fn address_and(args: &[String]) -> Result<(u16, &[String]), String> {
+    match args.split_first() {
+        Some((address, rest)) if !rest.is_empty() => Ok((parse("address", address)?, rest)),
+        _ => Err("Expected an address and a value".to_string()),
+    }
+}
+
+fn quantity(args: &[String]) -> Result<u16, String> {
+    match args {
+        [qty] => parse("quantity", qty),
+        _ => Err("Expected a single quantity".to_string()),
+    }
+}
+
+// Values of multi-register types are given as typed, e.g. `--format f32 1.5`.
+fn register_values(
+    values: &[String],
+    data_type: DataType,
+    order: ByteOrder,
+) -> Result<Vec<u16>, String> {
+    let mut words = Vec::new();
+    for value in values {
+        let encoded = match data_type {
+            DataType::U16 => parse_u16(value).map(|x| vec![x]),
+            _ => data_type.encode(value, 1, order, DisplayFormat::Decimal),
+        };
+        words.extend(
+            encoded.ok_or_else(|| format!("{} is not a valid {} value", value, data_type))?,
+        );
+    }
+    Ok(words)
+}
+
+fn coil_values(values: &[String]) -> Result<Vec<bool>, String> {
+    values
+        .iter()
+        .map(|x| parse_bool(x).ok_or_else(|| format!("{} is not a coil value", x)))
+        .collect()
+}
+
+fn build_request(
+    command: &str,
+    args: &[String],
+    opts: &Options,
+    order: ByteOrder,
+) -> Result<Request, String> {
+    let (address, rest) = address_and(args)?;
+    match command {
+        "read-coils" => Ok(Request::ReadCoils(address, quantity(rest)?)),
+        "read-discrete" => Ok(Request::ReadDiscreteInputs(address, quantity(rest)?)),
+        "read-input" => Ok(Request::ReadInputRegisters(address, quantity(rest)?)),
+        "read-holding" => Ok(Request::ReadHoldingRegisters(address, quantity(rest)?)),
+        "write-coil" => match coil_values(rest)?[..] {
+            [value] => Ok(Request::WriteSingleCoil(address, value)),
+            _ => Err("Expected a single coil value".to_string()),
+        },
+        "write-coils" => Ok(Request::WriteMultipleCoils(address, coil_values(rest)?)),
+        "write-register" => match register_values(rest, opts.data_type, order)?[..] {
+            [value] => Ok(Request::WriteSingleRegister(address, value)),
+            _ => Err("write-register takes a single 16 bit value, use write-registers".to_string()),
+        },
+        "write-registers" => Ok(Request::WriteMultipleRegisters(
+            address,
+            register_values(rest, opts.data_type, order)?,
+        )),
+        _ => Err(format!("Unknown command {}", command)),
+    }
+}
+
+fn readings(req: &Request, resp: &Response, data_type: DataType, order: ByteOrder) -> Vec<Reading> {
+    let bits = |start: u16, qty: u16, bits: &[bool]| -> Vec<Reading> {
+        (start..)
+            .zip(bits.iter().take(usize::from(qty)))
+            .map(|(address, x)| Reading {
+                address,
+                text: u8::from(*x).to_string(),
+                number: Some(f64::from(u8::from(*x))),
+            })
+            .collect()
+    };
+    match (req, resp) {
+        (Request::ReadCoils(start, qty), Response::ReadCoils(vals))
+        | (Request::ReadDiscreteInputs(start, qty), Response::ReadDiscreteInputs(vals)) => {
+            bits(*start, *qty, vals)
+        }
+        (Request::ReadInputRegisters(start, _), Response::ReadInputRegisters(words))
+        | (Request::ReadHoldingRegisters(start, _), Response::ReadHoldingRegisters(words)) => {
+            // A string takes the whole block.
+            let size = match data_type {
+                DataType::Ascii => words.len().max(1),
+                _ => usize::from(data_type.register_count(1)),
+            };
+            words
+                .chunks_exact(size)
+                .enumerate()
+                .map(|(idx, x)| Reading {
+                    address: start.wrapping_add((idx * size) as u16),
+                    text: data_type.decode(x, order),
+                    number: data_type.to_f64(x, order),
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn json_number(number: Option<f64>, text: &str) -> Value {
+    match number.and_then(serde_json::Number::from_f64) {
+        Some(x) => Value::Number(x),
+        None => Value::String(text.to_string()),
+    }
+}
+
+// Quotes a CSV field when it needs it.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn retry_policy(base: RetryPolicy, opts: &Options) -> RetryPolicy {
+    base.with_override(RetryOverride {
+        timeout: opts.timeout,
+        retries: opts.retries,
+    })
+}
+
+async fn one_shot(
+    params: ConnectionParams,
+    command: &str,
+    args: &[String],
+    opts: &Options,
+) -> Result<(), String> {
+    let order = params.byte_order();
+    let req = build_request(command, args, opts, order)?;
+    let retry = retry_policy(RetryPolicy::default(), opts);
+    let mut session = Session::open(params, retry)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (tx, _res) = session.transact(req, None).await;
+    let resp = tx.result.clone().map_err(|e| e.to_string())?;
+    let values = readings(&tx.request, &resp, opts.data_type, order);
+    let is_read = matches!(
+        tx.request,
+        Request::ReadCoils(..)
+            | Request::ReadDiscreteInputs(..)
+            | Request::ReadInputRegisters(..)
+            | Request::ReadHoldingRegisters(..)
+    );
+    match opts.output {
+        Output::Text if is_read => {
+            for x in values {
+                println!("{}\t{}", x.address, x.text);
+            }
+        }
+        Output::Text => println!("{}", response_data(&resp)),
+        Output::Json => {
+            let mut out = json!({
+                "request": RequestType::from(&tx.request).to_string(),
+                "data": request_data(&tx.request),
+                "timestamp": tx.timestamp.to_rfc3339(),
+                "rtt_ms": tx.rtt.as_secs_f64() * 1000.0,
+            });
+            if is_read {
+                out["values"] = values
+                    .iter()
+                    .map(|x| json!({"address": x.address, "value": json_number(x.number, &x.text)}))
+                    .collect();
+            } else {
+                out["response"] = Value::String(response_data(&resp));
+            }
+            println!("{}", out);
+        }
+        Output::Csv if is_read => {
+            println!("address,value");
+            for x in values {
+                println!("{},{}", x.address, csv_field(&x.text));
+            }
+        }
+        Output::Csv => {
+            println!("request,response");
+            println!(
+                "{},{}",
+                csv_field(&RequestType::from(&tx.request).to_string()),
+                csv_field(&response_data(&resp))
+            );
+        }
+    }
+    Ok(())
+}
+
+// Reads every row of the project's table each interval, the way the GUI polls,
+// and prints one line or record per cycle.
+async fn poll(project: Project, opts: &Options) -> Result<(), String> {
+    let mut params = project.connection.clone();
+    if let Some(order) = opts.order {
+        params = params.with_byte_order(order);
+    }
+    if let Some(unit) = opts.unit {
+        params = params.with_address(unit);
+    }
+    let order = params.byte_order();
+    let default_unit = params.address();
+    let mut table: Table = project.table;
+    if table.registers().is_empty() {
+        return Err("The project's table has no rows".to_string());
+    }
+    let interval = opts.interval.unwrap_or_else(|| table.poll_interval());
+    let retry = retry_policy(project.retry, opts);
+    let mut session = Session::open(params, retry)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let names: Vec<String> = table
+        .registers()
+        .iter()
+        .map(|x| x.name().to_string())
+        .collect();
+    if opts.output == Output::Csv {
+        let header: Vec<String> = names.iter().map(|x| csv_field(x)).collect();
+        println!("timestamp,{}", header.join(","));
+    }
+    let mut cycle = 0;
+    let mut ticker = tokio::time::interval(interval);
+    while opts.count.is_none_or(|count| cycle < count) {
+        ticker.tick().await;
+        cycle += 1;
+        let timestamp = chrono::Local::now();
+        for (req, unit, over) in table.poll_requests() {
+            match session.transact_with(req, unit, over).await {
+                (
+                    Transaction {
+                        request,
+                        slave,
+                        result: Ok(resp),
+                        ..
+                    },
+                    _,
+                ) => table.handle_response(slave, request, resp, default_unit),
+                (Transaction { result: Err(e), .. }, _) => eprintln!("{}", e),
+            }
+        }
+        let values: Vec<(String, Option<f64>)> = table
+            .registers()
+            .iter()
+            .map(|x| {
+                (
+                    x.value_text(order, DisplayFormat::Decimal),
+                    x.engineering_value(order),
+                )
+            })
+            .collect();
+        match opts.output {
+            Output::Text => {
+                let fields: Vec<String> = names
+                    .iter()
+                    .zip(&values)
+                    .map(|(name, (text, _))| format!("{}={}", name, text))
+                    .collect();
+                println!("{} {}", timestamp.format("%H:%M:%S%.3f"), fields.join(" "));
+            }
+            Output::Json => {
+                let mut fields = serde_json::Map::new();
+                for (name, (text, number)) in names.iter().zip(&values) {
+                    fields.insert(name.clone(), json_number(*number, text));
+                }
+                println!(
+                    "{}",
+                    json!({"timestamp": timestamp.to_rfc3339(), "values": fields})
+                );
+            }
+            Output::Csv => {
+                let fields: Vec<String> = values.iter().map(|(text, _)| csv_field(text)).collect();
+                println!("{},{}", timestamp.to_rfc3339(), fields.join(","));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run(args: Vec<String>) -> Result<(), String> {
+    let (opts, positional) = parse_options(args)?;
+    match &positional[..] {
+        [kind, project, command] if kind == "project" && command == "poll" => {
+            let project = Project::load_from_file(std::path::Path::new(project))
+                .map_err(|e| e.to_string())?;
+            poll(project, &opts).await
+        }
+        [kind, target, command, args @ ..] if kind == "tcp" => {
+            one_shot(tcp_params(target, &opts)?, command, args, &opts).await
+        }
+        [kind, port, command, args @ ..] if kind == "rtu" => {
+            one_shot(serial_params(port, &opts), command, args, &opts).await
+        }
+        _ => Err(String::new()),
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(args).await {
+        Ok(()) => (),
+        // An empty error asks for the usage text.
+        Err(e) if e.is_empty() => {
+            eprint!("{}", USAGE);
+            std::process::exit(2);
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn options_are_taken_out_of_the_positionals() {
+        let (opts, positional) = parse_options(args(
+            "tcp 10.0.0.2:1502 --unit 7 --format F32 --order cdab read-holding 0 4 --output csv",
+        ))
+        .unwrap();
+        assert_eq!(positional, args("tcp 10.0.0.2:1502 read-holding 0 4"));
+        assert_eq!(opts.unit, Some(7));
+        assert_eq!(opts.data_type, DataType::F32);
+        assert_eq!(opts.order, Some(ByteOrder::Cdab));
+        assert_eq!(opts.output, Output::Csv);
+
+        let (opts, _) = parse_options(args("--parity even --stop-bits 2 --timeout 250")).unwrap();
+        assert_eq!(opts.parity, Parity::Even);
+        assert_eq!(opts.stop_bits, StopBits::Two);
+        assert_eq!(opts.timeout, Some(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn bad_options_are_reported() {
+        assert_eq!(parse_options(args("--help")).unwrap_err(), "");
+        assert_eq!(
+            parse_options(args("--unit")).unwrap_err(),
+            "Missing value for --unit"
+        );
+        assert_eq!(
+            parse_options(args("--unit 300")).unwrap_err(),
+            "Invalid value for --unit: 300"
+        );
+        assert_eq!(
+            parse_options(args("--output xml")).unwrap_err(),
+            "Unknown output xml"
+        );
+        assert_eq!(
+            parse_options(args("--verbose 1")).unwrap_err(),
+            "Unknown option --verbose"
+        );
+    }
+
+    #[test]
+    fn tcp_target_defaults_to_port_502() {
+        let opts = Options::default();
+        let params = |target| match tcp_params(target, &opts) {
+            Ok(ConnectionParams::Tcp(p)) => (p.ip, p.port, p.address),
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(params("192.168.1.5"), ([192, 168, 1, 5], 502, 1));
+        assert_eq!(params("192.168.1.5:1502"), ([192, 168, 1, 5], 1502, 1));
+        assert!(tcp_params("plc.local", &opts).is_err());
+    }
+
+    #[test]
+    fn commands_build_requests() {
+        let opts = Options::default();
+        let build = |line: &str, opts: &Options| {
+            let line = args(line);
+            build_request(&line[0], &line[1..], opts, ByteOrder::Abcd)
+        };
+        assert_eq!(
+            build("read-holding 100 4", &opts),
+            Ok(Request::ReadHoldingRegisters(100, 4))
+        );
+        assert_eq!(
+            build("write-coils 0 1 0 1", &opts),
+            Ok(Request::WriteMultipleCoils(0, vec![true, false, true]))
+        );
+        assert_eq!(
+            build("write-register 3 0x10", &opts),
+            Ok(Request::WriteSingleRegister(3, 16))
+        );
+        assert!(build("read-holding 100", &opts).is_err());
+        assert!(build("write-coil 0 2", &opts).is_err());
+        assert!(build("erase 0 1", &opts).is_err());
+
+        let floats = Options {
+            data_type: DataType::F32,
+            ..Options::default()
+        };
+        assert_eq!(
+            build("write-registers 0 1.5", &floats),
+            Ok(Request::WriteMultipleRegisters(0, vec![0x3fc0, 0x0000]))
+        );
+        assert!(build("write-register 0 1.5", &floats).is_err());
+    }
+
+    #[test]
+    fn readings_follow_the_data_type() {
+        let req = Request::ReadHoldingRegisters(10, 4);
+        let resp = Response::ReadHoldingRegisters(vec![0x3fc0, 0, 0xbf80, 0]);
+        let values: Vec<(u16, String, Option<f64>)> =
+            readings(&req, &resp, DataType::F32, ByteOrder::Abcd)
+                .into_iter()
+                .map(|x| (x.address, x.text, x.number))
+                .collect();
+        assert_eq!(
+            values,
+            vec![
+                (10, "1.5".to_string(), Some(1.5)),
+                (12, "-1".to_string(), Some(-1.0))
+            ]
+        );
+
+        // Coil responses are padded to whole bytes.
+        let req = Request::ReadCoils(0, 2);
+        let resp = Response::ReadCoils(vec![true, false, false, false]);
+        let bits: Vec<String> = readings(&req, &resp, DataType::U16, ByteOrder::Abcd)
+            .into_iter()
+            .map(|x| x.text)
+            .collect();
+        assert_eq!(bits, vec!["1", "0"]);
+    }
+
+    #[test]
+    fn output_fields_are_quoted_and_numbers_kept() {
+        assert_eq!(csv_field("12"), "12");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(json_number(Some(1.5), "1.5"), json!(1.5));
+        assert_eq!(json_number(None, "AB"), json!("AB"));
+        assert_eq!(json_number(Some(f64::NAN), "NaN"), json!("NaN"));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::data_type::ByteOrder;
+use serde::de::{Deserializer, Error};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio_serial::{DataBits, Parity, StopBits};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ConnectionParams {
+    Serial(SerialPortParams),
+    Tcp(TcpParams),
+}
+
+impl Default for ConnectionParams {
+    fn default() -> Self {
+        ConnectionParams::new()
+    }
+}
+
+impl ConnectionParams {
+    pub fn new() -> Self {
+        ConnectionParams::Serial(SerialPortParams::default())
+    }
+
+    pub fn address(&self) -> u8 {
+        match self {
+            ConnectionParams::Serial(p) => p.address,
+            ConnectionParams::Tcp(p) => p.address,
+        }
+    }
+
+    pub fn with_address(&self, address: u8) -> Self {
+        match self {
+            ConnectionParams::Serial(p) => ConnectionParams::Serial(SerialPortParams {
+                address,
+                ..p.clone()
+            }),
+            ConnectionParams::Tcp(p) => ConnectionParams::Tcp(TcpParams { address, ..*p }),
+        }
+    }
+
+    // Default order for multi-register values, registers may override it.
+    pub fn byte_order(&self) -> ByteOrder {
+        match self {
+            ConnectionParams::Serial(p) => p.byte_order,
+            ConnectionParams::Tcp(p) => p.byte_order,
+        }
+    }
+
+    pub fn with_byte_order(&self, byte_order: ByteOrder) -> Self {
+        match self {
+            ConnectionParams::Serial(p) => ConnectionParams::Serial(SerialPortParams {
+                byte_order,
+                ..p.clone()
+            }),
+            ConnectionParams::Tcp(p) => ConnectionParams::Tcp(TcpParams { byte_order, ..*p }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+pub struct TcpParams {
+    #[serde(deserialize_with = "deserialize_ip")]
+    pub ip: [u8; 4],
+    pub port: u16,
+    pub address: u8,
+    #[serde(default)]
+    pub byte_order: ByteOrder,
+}
+
+impl Serialize for TcpParams {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("serial_port", 2)?;
+        let ip = Ipv4Addr::new(self.ip[0], self.ip[1], self.ip[2], self.ip[3]);
+        state.serialize_field("ip", &ip.to_string())?;
+        state.serialize_field("port", &self.port)?;
+        state.serialize_field("address", &self.address)?;
+        state.serialize_field("byte_order", &self.byte_order)?;
+        state.end()
+    }
+}
+
+fn deserialize_ip<'de, D>(deserializer: D) -> Result<[u8; 4], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    match s.parse::<Ipv4Addr>() {
+        Ok(ip) => Ok(ip.octets()),
+        Err(_e) => Err(D::Error::custom("Invalid IP")),
+    }
+}
+
+impl TcpParams {
+    pub fn new(ip: [u8; 4], port: u16, address: u8) -> Self {
+        Self {
+            ip,
+            port,
+            address,
+            byte_order: ByteOrder::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SerialPortParams {
+    pub port: String,
+    pub baudrate: u32,
+    #[serde(deserialize_with = "deserialize_parity")]
+    pub parity: Parity,
+    #[serde(deserialize_with = "deserialize_data_bits")]
+    pub data_bits: DataBits,
+    #[serde(deserialize_with = "deserialize_stop_bits")]
+    pub stop_bits: StopBits,
+    pub timeout: std::time::Duration,
+    pub address: u8,
+    #[serde(default)]
+    pub byte_order: ByteOrder,
+}
+
+// Names used in project files, the same ones the GUI shows.
+pub fn parity_name(parity: Parity) -> &'static str {
+    match parity {
+        Parity::None => "None",
+        Parity::Odd => "Odd",
+        Parity::Even => "Even",
+    }
+}
+
+pub fn data_bits_name(data_bits: DataBits) -> &'static str {
+    match data_bits {
+        DataBits::Five => "5",
+        DataBits::Six => "6",
+        DataBits::Seven => "7",
+        DataBits::Eight => "8",
+    }
+}
+
+pub fn stop_bits_name(stop_bits: StopBits) -> &'static str {
+    match stop_bits {
+        StopBits::One => "1",
+        StopBits::Two => "2",
+    }
+}
+
+fn deserialize_parity<'de, D>(deserializer: D) -> Result<Parity, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    match s.as_str() {
+        "Odd" => Ok(Parity::Odd),
+        "Even" => Ok(Parity::Even),
+        "None" => Ok(Parity::None),
+        _ => Err(D::Error::custom("Invalid Field")),
+    }
+}
+
+fn deserialize_data_bits<'de, D>(deserializer: D) -> Result<DataBits, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    match s.as_str() {
+        "5" => Ok(DataBits::Five),
+        "6" => Ok(DataBits::Six),
+        "7" => Ok(DataBits::Seven),
+        "8" => Ok(DataBits::Eight),
+        _ => Err(D::Error::custom("Invalid Field")),
+    }
+}
+
+fn deserialize_stop_bits<'de, D>(deserializer: D) -> Result<StopBits, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    match s.as_str() {
+        "1" => Ok(StopBits::One),
+        "2" => Ok(StopBits::Two),
+        _ => Err(D::Error::custom("Invalid Field")),
+    }
+}
+
+impl Serialize for SerialPortParams {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // 3 is the number of fields in the struct.
+        let mut state = serializer.serialize_struct("serial_port", 8)?;
+        state.serialize_field("port", &self.port)?;
+        state.serialize_field("baudrate", &self.baudrate)?;
+        state.serialize_field("parity", parity_name(self.parity))?;
+        state.serialize_field("data_bits", data_bits_name(self.data_bits))?;
+        state.serialize_field("stop_bits", stop_bits_name(self.stop_bits))?;
+        state.serialize_field("timeout", &self.timeout)?;
+        state.serialize_field("address", &self.address)?;
+        state.serialize_field("byte_order", &self.byte_order)?;
+        state.end()
+    }
+}
+
+impl Default for SerialPortParams {
+    fn default() -> Self {
+        Self {
+            port: "".to_string(),
+            baudrate: 115200,
+            parity: Parity::None,
+            data_bits: DataBits::Eight,
+            stop_bits: StopBits::One,
+            timeout: std::time::Duration::from_millis(1000),
+            address: 1,
+            byte_order: ByteOrder::default(),
+        }
+    }
+}
+
+impl SerialPortParams {
+    pub fn new() -> Self {
+        Self {
+            port: "".to_string(),
+            baudrate: 115200,
+            parity: Parity::None,
+            data_bits: DataBits::Eight,
+            stop_bits: StopBits::One,
+            timeout: Duration::from_secs(1),
+            address: 1,
+            byte_order: ByteOrder::default(),
+        }
+    }
+}
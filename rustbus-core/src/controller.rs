@@ -0,0 +1,753 @@
+use crate::connection::ConnectionParams;
+use crate::error::ModbusError;
+use crate::transport::{self, RawFrame, Tap, TapHandle};
+use chrono::{DateTime, Local};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio_modbus::client::{Client, Context};
+use tokio_modbus::prelude::{Request, Response, Slave, SlaveContext};
+use tokio_serial::SerialPortBuilderExt;
+
+pub enum WorkerState {
+    Disconnected,
+    Ready(UnboundedReceiver<Commands>),
+    Connecting(
+        UnboundedReceiver<Commands>,
+        ConnectionParams,
+        ReconnectPolicy,
+        RetryPolicy,
+    ),
+    Connected(UnboundedReceiver<Commands>, Session),
+    Reconnecting(
+        UnboundedReceiver<Commands>,
+        ConnectionParams,
+        ReconnectPolicy,
+        RetryPolicy,
+        u32,
+    ),
+    Error(UnboundedReceiver<Commands>),
+    // Every command sender is gone, nothing can happen any more.
+    Stopped,
+}
+
+pub struct Session {
+    ctx: Context,
+    slave: Slave,
+    tap: TapHandle,
+    params: ConnectionParams,
+    policy: ReconnectPolicy,
+    retry: RetryPolicy,
+    failures: u32,
+    pending: VecDeque<WorkerEvent>,
+    // Error of a request whose transaction was already reported, handled on
+    // the next step so it still counts towards a degraded link.
+    deferred: Option<std::io::Error>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconnectPolicy {
+    pub enabled: bool,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    // 0 keeps retrying forever
+    pub max_attempts: u32,
+    // Consecutive failed requests on a live link before it is reported as degraded
+    pub degraded_after: u32,
+}
+
+impl std::default::Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_attempts: 0,
+            degraded_after: 3,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+
+    fn exhausted(&self, attempt: u32) -> bool {
+        self.max_attempts != 0 && attempt >= self.max_attempts
+    }
+}
+
+// How long a transaction waits for its answer and how often it is sent again
+// when none comes. Exceptions and a lost link are never retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub timeout: Duration,
+    pub retries: u32,
+    // Wait before the first retry, doubled for every one after it
+    pub backoff: Duration,
+}
+
+impl std::default::Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(1000),
+            retries: 0,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.backoff.saturating_mul(factor)
+    }
+
+    // Longest a transaction can take, every retry and its wait included.
+    pub fn total_time(&self) -> Duration {
+        (1..=self.retries).fold(self.timeout, |acc, retry| {
+            acc.saturating_add(self.delay(retry))
+                .saturating_add(self.timeout)
+        })
+    }
+
+    pub fn with_override(&self, over: RetryOverride) -> RetryPolicy {
+        RetryPolicy {
+            timeout: over.timeout.unwrap_or(self.timeout),
+            retries: over.retries.unwrap_or(self.retries),
+            ..*self
+        }
+    }
+}
+
+// Table rows and saved actions can replace the connection's retry settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct RetryOverride {
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+    #[serde(default)]
+    pub retries: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Commands {
+    Nothing,
+    Disconnect,
+    Connect(ConnectionParams, ReconnectPolicy, RetryPolicy),
+    DeviceCommand(Request, Option<u8>, RetryOverride),
+    RequestCommand(Request, Option<u8>),
+    // A history entry sent again, its result only goes to the history.
+    RerunCommand(Request, Option<u8>),
+    // Tagged with the run id and step of the action it was sent for.
+    ActionCommand(u32, usize, Request, Option<u8>, RetryOverride),
+    DeviceInfoCommand(Request, Option<u8>),
+    DiagnosticCommand(Request, Option<u8>),
+    ScriptRequest(
+        Request,
+        Option<u8>,
+        UnboundedSender<Result<Response, String>>,
+    ),
+}
+
+// One request sent by any part of the front-end and what came back.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub request: Request,
+    pub unit: Option<u8>,
+    // The unit the request went to, `unit` or else the connection's.
+    pub slave: u8,
+    pub timestamp: DateTime<Local>,
+    pub rtt: Duration,
+    pub result: Result<Response, ModbusError>,
+}
+
+#[derive(Debug, Clone)]
+pub enum WorkerEvent {
+    WorkerHandle(UnboundedSender<Commands>),
+    // Table polls and writes. A failed one is followed by the `Error` it raised.
+    DeviceResponse(Transaction),
+    RequestResponse(Transaction),
+    RerunResponse(Transaction),
+    // Run id and step of the `ActionCommand` that was answered.
+    ActionResponse(u32, usize, Transaction),
+    DeviceInfoResponse(Transaction),
+    DiagnosticResponse(Transaction),
+    ScriptResponse(Transaction),
+    RawFrames(Vec<RawFrame>),
+    Connecting,
+    Connected,
+    Reconnecting(u32, Duration, String),
+    Degraded(String),
+    // A transaction got no answer, retries included.
+    Timeout(String),
+    Disconnected,
+    Idle,
+    Failed(String),
+    Error(ModbusError),
+}
+
+// Opens the transport and puts a client on it, recording into `tap`. A TCP
+// connect that takes longer than `timeout` fails like an unanswered request.
+async fn attach(
+    conn_params: &ConnectionParams,
+    tap: &TapHandle,
+    timeout: Duration,
+) -> Result<Context, std::io::Error> {
+    let slave = Slave(conn_params.address());
+    let ctx = match conn_params.clone() {
+        ConnectionParams::Serial(sp) => {
+            debug!("Opening Port: {:?}", sp.port);
+            let baudrate = sp.baudrate;
+            let port = tokio_serial::new(sp.port, sp.baudrate)
+                .data_bits(sp.data_bits)
+                .flow_control(tokio_serial::FlowControl::None)
+                .stop_bits(sp.stop_bits)
+                .parity(sp.parity)
+                .timeout(sp.timeout)
+                .open_native_async()?;
+            transport::rtu::attach_slave(Tap::new(port, tap.clone()), slave, baudrate)
+        }
+        ConnectionParams::Tcp(tcp_params) => {
+            debug!("Opening Port: {:?}", tcp_params);
+            let addr = Ipv4Addr::from(tcp_params.ip);
+            let socket_addr = SocketAddrV4::new(addr, tcp_params.port);
+            let connect = TcpStream::connect(socket_addr);
+            let stream = match tokio::time::timeout(timeout, connect).await {
+                Ok(stream) => stream?,
+                Err(_elapsed) => {
+                    return Err(ModbusError::Timeout(format!(
+                        "No connection to {} within {} ms",
+                        socket_addr,
+                        timeout.as_millis()
+                    ))
+                    .into())
+                }
+            };
+            transport::tcp::attach_slave(Tap::new(stream, tap.clone()), slave)
+        }
+    };
+    Ok(ctx)
+}
+
+async fn create_context(
+    conn_params: ConnectionParams,
+    policy: ReconnectPolicy,
+    retry: RetryPolicy,
+) -> Result<Session, std::io::Error> {
+    let tap = TapHandle::new();
+    let ctx = attach(&conn_params, &tap, retry.timeout).await?;
+    Ok(Session {
+        ctx,
+        slave: Slave(conn_params.address()),
+        tap,
+        params: conn_params,
+        policy,
+        retry,
+        failures: 0,
+        pending: VecDeque::new(),
+        deferred: None,
+    })
+}
+
+// Errors that mean the transport itself is gone (socket reset, peer closed)
+// rather than a single failed transaction. Other OS errors would come straight
+// back after a reconnect.
+fn is_connection_lost(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind::*;
+    matches!(
+        e.kind(),
+        BrokenPipe
+            | ConnectionReset
+            | ConnectionAborted
+            | ConnectionRefused
+            | NotConnected
+            | UnexpectedEof
+    )
+}
+
+fn connection_lost(
+    srx: UnboundedReceiver<Commands>,
+    params: ConnectionParams,
+    policy: ReconnectPolicy,
+    retry: RetryPolicy,
+    reason: String,
+) -> (Option<WorkerEvent>, WorkerState) {
+    if policy.enabled {
+        (
+            Some(WorkerEvent::Reconnecting(1, policy.delay(1), reason)),
+            WorkerState::Reconnecting(srx, params, policy, retry, 1),
+        )
+    } else {
+        (Some(WorkerEvent::Failed(reason)), WorkerState::Error(srx))
+    }
+}
+
+impl Session {
+    // Opens a connection driven directly rather than through the worker, for
+    // callers without a GUI.
+    pub async fn open(
+        params: ConnectionParams,
+        retry: RetryPolicy,
+    ) -> Result<Session, std::io::Error> {
+        create_context(params, ReconnectPolicy::default(), retry).await
+    }
+
+    fn slave_id(&self, unit: Option<u8>) -> u8 {
+        unit.unwrap_or(self.slave.into())
+    }
+
+    // A timeout can cancel a TCP read halfway through a frame, whose rest would
+    // then be taken for the next response. A fresh connection starts clean.
+    // The RTU client drops stray bytes before every request by itself.
+    async fn resync(&mut self) -> Result<(), std::io::Error> {
+        if let ConnectionParams::Tcp(_) = self.params {
+            debug!("Reopening {:?} after a timeout", self.params);
+            self.ctx = attach(&self.params, &self.tap, self.retry.timeout).await?;
+        }
+        Ok(())
+    }
+
+    async fn call(
+        &mut self,
+        cmd: Request,
+        unit: Option<u8>,
+        over: RetryOverride,
+    ) -> Result<Response, std::io::Error> {
+        let retry = self.retry.with_override(over);
+        let mut attempt = 0;
+        loop {
+            self.ctx.set_slave(Slave(self.slave_id(unit)));
+            let res = match tokio::time::timeout(retry.timeout, self.ctx.call(cmd.clone())).await {
+                Ok(res) => res,
+                Err(_elapsed) => {
+                    self.resync().await?;
+                    Err(ModbusError::Timeout(match attempt {
+                        0 => format!("No response within {} ms", retry.timeout.as_millis()),
+                        n => format!(
+                            "No response within {} ms after {} retries",
+                            retry.timeout.as_millis(),
+                            n
+                        ),
+                    })
+                    .into())
+                }
+            };
+            match res {
+                Err(e)
+                    if attempt < retry.retries
+                        && !is_connection_lost(&e)
+                        && !ModbusError::from(&e).is_exception() =>
+                {
+                    attempt += 1;
+                    debug!("Retry {} of {:?}: {}", attempt, cmd, e);
+                    tokio::time::sleep(retry.delay(attempt)).await;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    pub async fn transact(
+        &mut self,
+        cmd: Request,
+        unit: Option<u8>,
+    ) -> (Transaction, Result<(), std::io::Error>) {
+        self.transact_with(cmd, unit, RetryOverride::default())
+            .await
+    }
+
+    // `transact` with a row's or action's own retry settings.
+    pub async fn transact_with(
+        &mut self,
+        cmd: Request,
+        unit: Option<u8>,
+        over: RetryOverride,
+    ) -> (Transaction, Result<(), std::io::Error>) {
+        let timestamp = Local::now();
+        let started = Instant::now();
+        let res = self.call(cmd.clone(), unit, over).await;
+        let rtt = started.elapsed();
+        let (result, res) = match res {
+            Ok(resp) => (Ok(resp), Ok(())),
+            Err(e) => (Err(ModbusError::from(&e)), Err(e)),
+        };
+        let transaction = Transaction {
+            request: cmd,
+            unit,
+            slave: self.slave_id(unit),
+            timestamp,
+            rtt,
+            result,
+        };
+        (transaction, res)
+    }
+
+    // Transactions are always reported; a failed one is then handled like
+    // any other error on the next step.
+    fn report(
+        mut self,
+        srx: UnboundedReceiver<Commands>,
+        ev: WorkerEvent,
+        res: Result<(), std::io::Error>,
+    ) -> (Option<WorkerEvent>, WorkerState) {
+        match res {
+            Ok(()) => self.finish(srx, Ok(ev)),
+            Err(e) => {
+                self.deferred = Some(e);
+                (Some(ev), WorkerState::Connected(srx, self))
+            }
+        }
+    }
+
+    fn finish(
+        mut self,
+        srx: UnboundedReceiver<Commands>,
+        res: Result<WorkerEvent, std::io::Error>,
+    ) -> (Option<WorkerEvent>, WorkerState) {
+        match res {
+            Ok(ev) => {
+                if self.failures >= self.policy.degraded_after {
+                    self.pending.push_back(WorkerEvent::Connected);
+                }
+                self.failures = 0;
+                (Some(ev), WorkerState::Connected(srx, self))
+            }
+            Err(e) if is_connection_lost(&e) => {
+                connection_lost(srx, self.params, self.policy, self.retry, e.to_string())
+            }
+            // The device answered, the link is fine.
+            Err(e) if ModbusError::from(&e).is_exception() => (
+                Some(WorkerEvent::Error(ModbusError::from(&e))),
+                WorkerState::Connected(srx, self),
+            ),
+            // The failure itself is reported either way, the link turning
+            // degraded comes after it.
+            Err(e) => {
+                self.failures += 1;
+                if self.failures == self.policy.degraded_after {
+                    self.pending.push_back(WorkerEvent::Degraded(e.to_string()));
+                }
+                let event = match ModbusError::from(&e) {
+                    ModbusError::Timeout(reason) => WorkerEvent::Timeout(reason),
+                    err => WorkerEvent::Error(err),
+                };
+                (Some(event), WorkerState::Connected(srx, self))
+            }
+        }
+    }
+}
+
+// Runs the worker until it has something to report. Front-ends drive it by
+// feeding the returned state back in, starting from `Disconnected`.
+pub async fn step(state: WorkerState) -> (Option<WorkerEvent>, WorkerState) {
+    match state {
+        WorkerState::Disconnected => {
+            let (mtx, srx) = unbounded_channel::<Commands>();
+            (
+                Some(WorkerEvent::WorkerHandle(mtx)),
+                WorkerState::Ready(srx),
+            )
+        }
+        WorkerState::Ready(mut srx) => {
+            if let Some(command) = srx.recv().await {
+                match command {
+                    Commands::Connect(p, policy, retry) => (
+                        Some(WorkerEvent::Connecting),
+                        WorkerState::Connecting(srx, p, policy, retry),
+                    ),
+                    _ => (Some(WorkerEvent::Idle), WorkerState::Ready(srx)),
+                }
+            } else {
+                (None, WorkerState::Stopped)
+            }
+        }
+        WorkerState::Connecting(srx, p, policy, retry) => {
+            match create_context(p.clone(), policy, retry).await {
+                Ok(session) => (
+                    Some(WorkerEvent::Connected),
+                    WorkerState::Connected(srx, session),
+                ),
+                Err(e) if policy.enabled => connection_lost(srx, p, policy, retry, e.to_string()),
+                Err(e) => (
+                    Some(WorkerEvent::Error(ModbusError::from(&e))),
+                    WorkerState::Ready(srx),
+                ),
+            }
+        }
+        WorkerState::Connected(mut srx, mut session) => {
+            let frames = session.tap.take();
+            if !frames.is_empty() {
+                return (
+                    Some(WorkerEvent::RawFrames(frames)),
+                    WorkerState::Connected(srx, session),
+                );
+            }
+            if let Some(e) = session.deferred.take() {
+                return session.finish(srx, Err(e));
+            }
+            if let Some(ev) = session.pending.pop_front() {
+                return (Some(ev), WorkerState::Connected(srx, session));
+            }
+            if let Some(command) = srx.recv().await {
+                match command {
+                    Commands::Nothing => (None, WorkerState::Connected(srx, session)),
+                    Commands::Disconnect => {
+                        (Some(WorkerEvent::Disconnected), WorkerState::Ready(srx))
+                    }
+                    Commands::Connect(p, policy, retry) => (
+                        Some(WorkerEvent::Connecting),
+                        WorkerState::Connecting(srx, p, policy, retry),
+                    ),
+                    Commands::DeviceCommand(cmd, unit, over) => {
+                        let (tx, res) = session.transact_with(cmd, unit, over).await;
+                        session.report(srx, WorkerEvent::DeviceResponse(tx), res)
+                    }
+                    Commands::RequestCommand(cmd, unit) => {
+                        let (tx, res) = session.transact(cmd, unit).await;
+                        session.report(srx, WorkerEvent::RequestResponse(tx), res)
+                    }
+                    Commands::RerunCommand(cmd, unit) => {
+                        let (tx, res) = session.transact(cmd, unit).await;
+                        session.report(srx, WorkerEvent::RerunResponse(tx), res)
+                    }
+                    Commands::ActionCommand(id, step, cmd, unit, over) => {
+                        let (tx, res) = session.transact_with(cmd, unit, over).await;
+                        session.report(srx, WorkerEvent::ActionResponse(id, step, tx), res)
+                    }
+                    Commands::DeviceInfoCommand(cmd, unit) => {
+                        let (tx, res) = session.transact(cmd, unit).await;
+                        session.report(srx, WorkerEvent::DeviceInfoResponse(tx), res)
+                    }
+                    Commands::DiagnosticCommand(cmd, unit) => {
+                        let (tx, res) = session.transact(cmd, unit).await;
+                        session.report(srx, WorkerEvent::DiagnosticResponse(tx), res)
+                    }
+                    Commands::ScriptRequest(cmd, unit, reply) => {
+                        let (tx, res) = session.transact(cmd, unit).await;
+                        let _ = reply.send(tx.result.clone().map_err(|e| e.to_string()));
+                        session.report(srx, WorkerEvent::ScriptResponse(tx), res)
+                    }
+                }
+            } else {
+                (None, WorkerState::Stopped)
+            }
+        }
+        WorkerState::Reconnecting(mut srx, p, policy, retry, attempt) => {
+            let backoff = tokio::time::sleep(policy.delay(attempt));
+            tokio::pin!(backoff);
+            loop {
+                tokio::select! {
+                    _ = &mut backoff => break,
+                    command = srx.recv() => match command {
+                        Some(Commands::Disconnect) => {
+                            return (
+                                Some(WorkerEvent::Disconnected),
+                                WorkerState::Ready(srx),
+                            );
+                        }
+                        Some(Commands::Connect(p, policy, retry)) => {
+                            return (
+                                Some(WorkerEvent::Connecting),
+                                WorkerState::Connecting(srx, p, policy, retry),
+                            );
+                        }
+                        // Requests issued while the link is down are dropped so
+                        // polling picks up again once it is back.
+                        Some(_) => (),
+                        None => break,
+                    }
+                }
+            }
+            match create_context(p.clone(), policy, retry).await {
+                Ok(session) => (
+                    Some(WorkerEvent::Connected),
+                    WorkerState::Connected(srx, session),
+                ),
+                Err(e) if policy.exhausted(attempt) => (
+                    Some(WorkerEvent::Failed(format!(
+                        "Giving up after {} attempts: {}",
+                        attempt, e
+                    ))),
+                    WorkerState::Error(srx),
+                ),
+                Err(e) => (
+                    Some(WorkerEvent::Reconnecting(
+                        attempt + 1,
+                        policy.delay(attempt + 1),
+                        e.to_string(),
+                    )),
+                    WorkerState::Reconnecting(srx, p, policy, retry, attempt + 1),
+                ),
+            }
+        }
+        WorkerState::Error(mut srx) => {
+            if let Some(command) = srx.recv().await {
+                match command {
+                    Commands::Connect(p, policy, retry) => (
+                        Some(WorkerEvent::Connecting),
+                        WorkerState::Connecting(srx, p, policy, retry),
+                    ),
+                    Commands::Disconnect => {
+                        (Some(WorkerEvent::Disconnected), WorkerState::Ready(srx))
+                    }
+                    Commands::Nothing => (None, WorkerState::Error(srx)),
+                    _ => (
+                        Some(WorkerEvent::Error(ModbusError::Io("Not connected".to_string()))),
+                        WorkerState::Error(srx),
+                    ),
+                }
+            } else {
+                (None, WorkerState::Stopped)
+            }
+        }
+        WorkerState::Stopped => std::future::pending().await,
+    }
+}
+
+// Runs the worker on its own task for front-ends that are not driven by a
+// subscription. It stops once the command sender is dropped.
+pub fn spawn() -> (UnboundedSender<Commands>, UnboundedReceiver<WorkerEvent>) {
+    let (mtx, srx) = unbounded_channel::<Commands>();
+    let (etx, erx) = unbounded_channel::<WorkerEvent>();
+    tokio::spawn(async move {
+        let mut state = WorkerState::Ready(srx);
+        while !matches!(state, WorkerState::Stopped) {
+            let (ev, next) = step(state).await;
+            state = next;
+            if let Some(ev) = ev {
+                if etx.send(ev).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    (mtx, erx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::TcpParams;
+    use crate::data_type::ByteOrder;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // The first connection stalls halfway through its answer, the retry has
+    // to go out on a fresh one to get past the leftover bytes.
+    #[tokio::test]
+    async fn timeout_reopens_tcp_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut request = [0u8; 12];
+            let (mut stalled, _) = listener.accept().await.unwrap();
+            stalled.read_exact(&mut request).await.unwrap();
+            stalled.write_all(&request[..3]).await.unwrap();
+            let (mut fresh, _) = listener.accept().await.unwrap();
+            fresh.read_exact(&mut request).await.unwrap();
+            let transaction_id = u16::from_be_bytes([request[0], request[1]]);
+            let adu = transport::tcp::encode_adu(transaction_id, 1, &[0x03, 0x02, 0x12, 0x34]);
+            fresh.write_all(&adu).await.unwrap();
+            stalled
+        });
+        let params = ConnectionParams::Tcp(TcpParams {
+            ip: [127, 0, 0, 1],
+            port,
+            address: 1,
+            byte_order: ByteOrder::default(),
+        });
+        let retry = RetryPolicy {
+            timeout: Duration::from_millis(200),
+            retries: 1,
+            backoff: Duration::from_millis(10),
+        };
+        let mut session = Session::open(params, retry).await.unwrap();
+        let (tx, res) = session
+            .transact(Request::ReadHoldingRegisters(0, 1), None)
+            .await;
+        assert!(res.is_ok());
+        assert_eq!(tx.result, Ok(Response::ReadHoldingRegisters(vec![0x1234])));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn degraded_follows_the_failure_it_was_raised_by() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let params = ConnectionParams::Tcp(TcpParams {
+            ip: [127, 0, 0, 1],
+            port,
+            address: 1,
+            byte_order: ByteOrder::default(),
+        });
+        let policy = ReconnectPolicy {
+            degraded_after: 1,
+            ..ReconnectPolicy::default()
+        };
+        let session = create_context(params, policy, RetryPolicy::default())
+            .await
+            .unwrap();
+        let (_mtx, srx) = unbounded_channel();
+        let timeout = ModbusError::Timeout("No response".to_string()).into();
+        let (ev, state) = session.finish(srx, Err(timeout));
+        assert!(matches!(ev, Some(WorkerEvent::Timeout(_))));
+        let (ev, _state) = step(state).await;
+        assert!(matches!(ev, Some(WorkerEvent::Degraded(_))));
+    }
+
+    #[test]
+    fn only_a_dead_link_counts_as_lost() {
+        use std::io::{Error, ErrorKind};
+        assert!(is_connection_lost(&Error::from(ErrorKind::ConnectionReset)));
+        assert!(is_connection_lost(&Error::from(ErrorKind::UnexpectedEof)));
+        assert!(is_connection_lost(&Error::from_raw_os_error(32)));
+        assert!(!is_connection_lost(&Error::from_raw_os_error(22)));
+        assert!(!is_connection_lost(&Error::from_raw_os_error(13)));
+        assert!(!is_connection_lost(
+            &ModbusError::Timeout("No response".to_string()).into()
+        ));
+    }
+
+    #[test]
+    fn total_time_covers_every_retry() {
+        let retry = RetryPolicy {
+            timeout: Duration::from_millis(100),
+            retries: 2,
+            backoff: Duration::from_millis(10),
+        };
+        assert_eq!(retry.total_time(), Duration::from_millis(330));
+        let once = retry.with_override(RetryOverride {
+            timeout: None,
+            retries: Some(0),
+        });
+        assert_eq!(once.total_time(), Duration::from_millis(100));
+    }
+
+    // Nothing answers on the documentation range, the connect has to give up
+    // at the retry timeout instead of waiting for the OS to.
+    #[tokio::test]
+    async fn connect_gives_up_at_the_timeout() {
+        let params = ConnectionParams::Tcp(TcpParams::new([192, 0, 2, 1], 502, 1));
+        let retry = RetryPolicy {
+            timeout: Duration::from_millis(100),
+            ..RetryPolicy::default()
+        };
+        let started = std::time::Instant::now();
+        let err = create_context(params, ReconnectPolicy::default(), retry)
+            .await
+            .err()
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        if started.elapsed() >= retry.timeout {
+            assert!(matches!(ModbusError::from(&err), ModbusError::Timeout(_)));
+        }
+    }
+}
@@ -0,0 +1,620 @@
+use serde::{Deserialize, Serialize};
+
+// Longest string a row can hold, in registers. It still fits a single read.
+pub const MAX_ASCII_LENGTH: u8 = 125;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DataType {
+    #[default]
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    U64,
+    I64,
+    F64,
+    Ascii,
+    Bitfield,
+}
+
+impl std::fmt::Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DataType::U16 => "u16",
+                DataType::I16 => "i16",
+                DataType::U32 => "u32",
+                DataType::I32 => "i32",
+                DataType::F32 => "f32",
+                DataType::U64 => "u64",
+                DataType::I64 => "i64",
+                DataType::F64 => "f64",
+                DataType::Ascii => "ASCII",
+                DataType::Bitfield => "Bitfield",
+            }
+        )
+    }
+}
+
+// Order of the bytes of a value on the wire, A being the most significant byte
+// of a 32 bit value. Words swap as a whole, so CDAB on a 64 bit value sends the
+// least significant word first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ByteOrder {
+    #[default]
+    Abcd,
+    Cdab,
+    Badc,
+    Dcba,
+}
+
+impl std::fmt::Display for ByteOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ByteOrder::Abcd => "ABCD",
+                ByteOrder::Cdab => "CDAB",
+                ByteOrder::Badc => "BADC",
+                ByteOrder::Dcba => "DCBA",
+            }
+        )
+    }
+}
+
+impl ByteOrder {
+    pub const ALL: [ByteOrder; 4] = [
+        ByteOrder::Abcd,
+        ByteOrder::Cdab,
+        ByteOrder::Badc,
+        ByteOrder::Dcba,
+    ];
+
+    pub fn swaps_words(&self) -> bool {
+        matches!(self, ByteOrder::Cdab | ByteOrder::Dcba)
+    }
+
+    pub fn swaps_bytes(&self) -> bool {
+        matches!(self, ByteOrder::Badc | ByteOrder::Dcba)
+    }
+
+    // Converts between wire order and most significant word first. The
+    // conversion is its own inverse, so it is used in both directions.
+    pub fn apply(&self, words: &[u16]) -> Vec<u16> {
+        let mut words: Vec<u16> = if self.swaps_bytes() {
+            words.iter().map(|x| x.swap_bytes()).collect()
+        } else {
+            words.to_vec()
+        };
+        if self.swaps_words() {
+            words.reverse();
+        }
+        words
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DisplayFormat {
+    #[default]
+    Decimal,
+    Signed,
+    Hex,
+    Binary,
+    Led,
+    Float,
+    Ascii,
+}
+
+impl std::fmt::Display for DisplayFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DisplayFormat::Decimal => "Decimal",
+                DisplayFormat::Signed => "Signed",
+                DisplayFormat::Hex => "Hex",
+                DisplayFormat::Binary => "Binary",
+                DisplayFormat::Led => "LED",
+                DisplayFormat::Float => "Float",
+                DisplayFormat::Ascii => "ASCII",
+            }
+        )
+    }
+}
+
+impl DisplayFormat {
+    pub const ALL: [DisplayFormat; 7] = [
+        DisplayFormat::Decimal,
+        DisplayFormat::Signed,
+        DisplayFormat::Hex,
+        DisplayFormat::Binary,
+        DisplayFormat::Led,
+        DisplayFormat::Float,
+        DisplayFormat::Ascii,
+    ];
+}
+
+// Digits and radix of `0x` or `0b` prefixed text.
+fn prefixed_digits(s: &str) -> Option<(String, u32)> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => Some((hex.replace('_', ""), 16)),
+        None => s
+            .strip_prefix("0b")
+            .or_else(|| s.strip_prefix("0B"))
+            .map(|bin| (bin.replace(['_', ' '], ""), 2)),
+    }
+}
+
+// Parses a single register value written as decimal, negative decimal, `0x1F`
+// or `0b1010`.
+pub fn parse_u16(s: &str) -> Option<u16> {
+    let s = s.trim();
+    match prefixed_digits(s) {
+        Some((digits, radix)) => u16::from_str_radix(&digits, radix).ok(),
+        None if s.starts_with('-') => s.parse::<i16>().ok().map(|x| x as u16),
+        None => s.parse::<u16>().ok(),
+    }
+}
+
+// Addresses, quantities and lengths. Unlike `parse_u16` a negative number is
+// refused rather than wrapped to a huge count.
+pub fn parse_unsigned(s: &str) -> Option<u16> {
+    match s.trim().starts_with('-') {
+        true => None,
+        false => parse_u16(s),
+    }
+}
+
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim() {
+        "1" | "true" | "on" => Some(true),
+        "0" | "false" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+// Formats a single register for the editors. Formats that need more than one
+// register fall back to decimal.
+pub fn format_u16(x: u16, format: DisplayFormat) -> String {
+    match format {
+        DisplayFormat::Signed => (x as i16).to_string(),
+        DisplayFormat::Hex => format!("0x{:04X}", x),
+        DisplayFormat::Binary | DisplayFormat::Led => format!("0b{:016b}", x),
+        DisplayFormat::Decimal | DisplayFormat::Float | DisplayFormat::Ascii => x.to_string(),
+    }
+}
+
+// Parses `0x`/`0b` prefixed raw bits into `count` registers, most significant
+// word first.
+fn parse_bits(s: &str, count: usize) -> Option<Vec<u16>> {
+    let (digits, radix) = prefixed_digits(s.trim())?;
+    if count > 4 {
+        return None;
+    }
+    let value = u64::from_str_radix(&digits, radix).ok()?;
+    if count < 4 && value >> (16 * count) != 0 {
+        return None;
+    }
+    Some(split(value, count))
+}
+
+fn ascii_text(words: &[u16]) -> String {
+    words
+        .iter()
+        .flat_map(|x| x.to_be_bytes())
+        .map(|x| {
+            if x.is_ascii_graphic() || x == b' ' {
+                char::from(x)
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+fn ascii_words(s: &str, count: usize) -> Option<Vec<u16>> {
+    if !s.is_ascii() || s.len() > count * 2 {
+        return None;
+    }
+    let mut bytes = s.as_bytes().to_vec();
+    bytes.resize(count * 2, 0);
+    Some(
+        bytes
+            .chunks(2)
+            .map(|x| u16::from_be_bytes([x[0], x[1]]))
+            .collect(),
+    )
+}
+
+impl DataType {
+    pub const ALL: [DataType; 10] = [
+        DataType::U16,
+        DataType::I16,
+        DataType::U32,
+        DataType::I32,
+        DataType::F32,
+        DataType::U64,
+        DataType::I64,
+        DataType::F64,
+        DataType::Ascii,
+        DataType::Bitfield,
+    ];
+
+    // Number of registers a value occupies. Only strings use `length`, every
+    // other type has a fixed width.
+    pub fn register_count(&self, length: u8) -> u16 {
+        match self {
+            DataType::U16 | DataType::I16 | DataType::Bitfield => 1,
+            DataType::U32 | DataType::I32 | DataType::F32 => 2,
+            DataType::U64 | DataType::I64 | DataType::F64 => 4,
+            DataType::Ascii => u16::from(length.clamp(1, MAX_ASCII_LENGTH)),
+        }
+    }
+
+    // Strings are read in address order, only the bytes inside each register
+    // can be swapped.
+    pub fn arrange(&self, words: &[u16], order: ByteOrder) -> Vec<u16> {
+        match self {
+            DataType::Ascii if order.swaps_bytes() => ByteOrder::Badc.apply(words),
+            DataType::Ascii => words.to_vec(),
+            _ => order.apply(words),
+        }
+    }
+
+    pub fn decode(&self, words: &[u16], order: ByteOrder) -> String {
+        let words = &self.arrange(words, order)[..];
+        match self {
+            DataType::U16 => first(words).to_string(),
+            DataType::I16 => (first(words) as i16).to_string(),
+            DataType::U32 => (join(words, 2) as u32).to_string(),
+            DataType::I32 => (join(words, 2) as u32 as i32).to_string(),
+            DataType::F32 => f32::from_bits(join(words, 2) as u32).to_string(),
+            DataType::U64 => join(words, 4).to_string(),
+            DataType::I64 => (join(words, 4) as i64).to_string(),
+            DataType::F64 => f64::from_bits(join(words, 4)).to_string(),
+            DataType::Ascii => words
+                .iter()
+                .flat_map(|x| x.to_be_bytes())
+                .take_while(|x| *x != 0)
+                .map(char::from)
+                .collect(),
+            DataType::Bitfield => format!("{:016b}", first(words)),
+        }
+    }
+
+    // Text in the given format is tried first, then the type's own notation,
+    // signed integers and finally `0x`/`0b` raw bits, so any value the table
+    // shows can be typed back.
+    pub fn encode(
+        &self,
+        s: &str,
+        length: u8,
+        order: ByteOrder,
+        format: DisplayFormat,
+    ) -> Option<Vec<u16>> {
+        let count = usize::from(self.register_count(length));
+        let words = self
+            .encode_format(s, count, format)
+            .or_else(|| self.encode_words(s, length))
+            .or_else(|| self.encode_format(s, count, DisplayFormat::Signed))
+            .or_else(|| parse_bits(s, count))?;
+        Some(self.arrange(&words, order))
+    }
+
+    fn encode_format(&self, s: &str, count: usize, format: DisplayFormat) -> Option<Vec<u16>> {
+        let s = s.trim();
+        match (format, count) {
+            (DisplayFormat::Hex, _) if !s.starts_with("0x") => parse_bits(&format!("0x{}", s), count),
+            (DisplayFormat::Binary, _) | (DisplayFormat::Led, _) if !s.starts_with("0b") => {
+                parse_bits(&format!("0b{}", s), count)
+            }
+            (DisplayFormat::Signed, 1) => Some(vec![s.parse::<i16>().ok()? as u16]),
+            (DisplayFormat::Signed, 2) => Some(split(u64::from(s.parse::<i32>().ok()? as u32), 2)),
+            (DisplayFormat::Signed, 4) => Some(split(s.parse::<i64>().ok()? as u64, 4)),
+            (DisplayFormat::Float, 2) => Some(split(u64::from(s.parse::<f32>().ok()?.to_bits()), 2)),
+            (DisplayFormat::Float, 4) => Some(split(s.parse::<f64>().ok()?.to_bits(), 4)),
+            (DisplayFormat::Ascii, _) => ascii_words(s, count),
+            _ => None,
+        }
+    }
+
+    // Renders one value. Hex, binary, signed and float reinterpret the raw bits
+    // of the value, which only works for up to four registers.
+    pub fn format(&self, words: &[u16], order: ByteOrder, format: DisplayFormat) -> String {
+        let arranged = self.arrange(words, order);
+        let count = arranged.len();
+        match format {
+            DisplayFormat::Decimal => self.decode(words, order),
+            DisplayFormat::Ascii => ascii_text(&arranged),
+            _ if count > 4 || *self == DataType::Ascii => arranged
+                .iter()
+                .map(|x| format_u16(*x, format))
+                .collect::<Vec<String>>()
+                .join(" "),
+            DisplayFormat::Signed => match self {
+                DataType::F32 | DataType::F64 => self.decode(words, order),
+                _ => match count {
+                    1 => (join(&arranged, 1) as u16 as i16).to_string(),
+                    2 => (join(&arranged, 2) as u32 as i32).to_string(),
+                    _ => (join(&arranged, count) as i64).to_string(),
+                },
+            },
+            DisplayFormat::Hex => format!("0x{:0width$X}", join(&arranged, count), width = count * 4),
+            DisplayFormat::Binary | DisplayFormat::Led => {
+                format!("0b{:0width$b}", join(&arranged, count), width = count * 16)
+            }
+            DisplayFormat::Float => match count {
+                2 => f32::from_bits(join(&arranged, 2) as u32).to_string(),
+                4 => f64::from_bits(join(&arranged, 4)).to_string(),
+                _ => self.decode(words, order),
+            },
+        }
+    }
+
+    // Formats consecutive values from a block of registers. Strings take the
+    // whole block and single registers shown as floats are read in pairs.
+    pub fn format_all(&self, words: &[u16], order: ByteOrder, format: DisplayFormat) -> Vec<String> {
+        match (self, format) {
+            (DataType::Ascii, _) | (_, DisplayFormat::Ascii) => {
+                vec![DataType::Ascii.format(words, order, format)]
+            }
+            (DataType::U16 | DataType::I16 | DataType::Bitfield, DisplayFormat::Float) => words
+                .chunks(2)
+                .map(|x| DataType::F32.format(x, order, format))
+                .collect(),
+            _ => words
+                .chunks(usize::from(self.register_count(1)))
+                .map(|x| self.format(x, order, format))
+                .collect(),
+        }
+    }
+
+    // Numeric value for scaling, `None` for strings and bitfields.
+    pub fn to_f64(&self, words: &[u16], order: ByteOrder) -> Option<f64> {
+        let arranged = self.arrange(words, order);
+        let words = &arranged[..];
+        match self {
+            DataType::U16 => Some(f64::from(first(words))),
+            DataType::I16 => Some(f64::from(first(words) as i16)),
+            DataType::U32 => Some(f64::from(join(words, 2) as u32)),
+            DataType::I32 => Some(f64::from(join(words, 2) as u32 as i32)),
+            DataType::F32 => Some(f64::from(f32::from_bits(join(words, 2) as u32))),
+            DataType::U64 => Some(join(words, 4) as f64),
+            DataType::I64 => Some(join(words, 4) as i64 as f64),
+            DataType::F64 => Some(f64::from_bits(join(words, 4))),
+            DataType::Ascii | DataType::Bitfield => None,
+        }
+    }
+
+    // Converts a numeric value back to registers, rounding integers and
+    // rejecting anything the type can not hold.
+    pub fn from_f64(&self, value: f64, order: ByteOrder) -> Result<Vec<u16>, String> {
+        if !value.is_finite() {
+            return Err(format!("{} is not a number", value));
+        }
+        let (min, max) = match self {
+            DataType::U16 => (0.0, f64::from(u16::MAX)),
+            DataType::I16 => (f64::from(i16::MIN), f64::from(i16::MAX)),
+            DataType::U32 => (0.0, f64::from(u32::MAX)),
+            DataType::I32 => (f64::from(i32::MIN), f64::from(i32::MAX)),
+            DataType::F32 => (f64::from(f32::MIN), f64::from(f32::MAX)),
+            DataType::U64 => (0.0, u64::MAX as f64),
+            DataType::I64 => (i64::MIN as f64, i64::MAX as f64),
+            DataType::F64 => (f64::MIN, f64::MAX),
+            DataType::Ascii | DataType::Bitfield => {
+                return Err(format!("{} values can not be scaled", self))
+            }
+        };
+        let value = match self {
+            DataType::F32 | DataType::F64 => value,
+            _ => value.round(),
+        };
+        if value < min || value > max {
+            return Err(format!("{} is outside {} range {} to {}", value, self, min, max));
+        }
+        let words = match self {
+            DataType::U16 => vec![value as u16],
+            DataType::I16 => vec![value as i16 as u16],
+            DataType::U32 => split(u64::from(value as u32), 2),
+            DataType::I32 => split(u64::from(value as i32 as u32), 2),
+            DataType::F32 => split(u64::from((value as f32).to_bits()), 2),
+            DataType::U64 => split(value as u64, 4),
+            DataType::I64 => split(value as i64 as u64, 4),
+            _ => split(value.to_bits(), 4),
+        };
+        Ok(self.arrange(&words, order))
+    }
+
+    // Bits of one value for LED lamps, most significant bit first.
+    pub fn bits(&self, words: &[u16], order: ByteOrder) -> Vec<bool> {
+        self.arrange(words, order)
+            .iter()
+            .flat_map(|x| (0..16).rev().map(move |i| x & (1 << i) != 0))
+            .collect()
+    }
+
+    // Flips one bit as numbered by `bits`.
+    pub fn toggle_bit(&self, words: &[u16], order: ByteOrder, bit: usize) -> Vec<u16> {
+        let mut arranged = self.arrange(words, order);
+        if let Some(x) = arranged.get_mut(bit / 16) {
+            *x ^= 1 << (15 - bit % 16);
+        }
+        self.arrange(&arranged, order)
+    }
+
+    fn encode_words(&self, s: &str, length: u8) -> Option<Vec<u16>> {
+        let s = s.trim();
+        match self {
+            DataType::U16 => Some(vec![s.parse::<u16>().ok()?]),
+            DataType::I16 => Some(vec![s.parse::<i16>().ok()? as u16]),
+            DataType::U32 => Some(split(u64::from(s.parse::<u32>().ok()?), 2)),
+            DataType::I32 => Some(split(u64::from(s.parse::<i32>().ok()? as u32), 2)),
+            DataType::F32 => Some(split(u64::from(s.parse::<f32>().ok()?.to_bits()), 2)),
+            DataType::U64 => Some(split(s.parse::<u64>().ok()?, 4)),
+            DataType::I64 => Some(split(s.parse::<i64>().ok()? as u64, 4)),
+            DataType::F64 => Some(split(s.parse::<f64>().ok()?.to_bits(), 4)),
+            DataType::Ascii => ascii_words(s, usize::from(self.register_count(length))),
+            DataType::Bitfield => {
+                let digits: String = s.chars().filter(|x| *x != ' ' && *x != '_').collect();
+                Some(vec![u16::from_str_radix(&digits, 2).ok()?])
+            }
+        }
+    }
+}
+
+fn first(words: &[u16]) -> u16 {
+    words.first().copied().unwrap_or_default()
+}
+
+// Most significant word first, missing words read as zero.
+fn join(words: &[u16], count: usize) -> u64 {
+    (0..count).fold(0u64, |acc, i| {
+        (acc << 16) | u64::from(words.get(i).copied().unwrap_or_default())
+    })
+}
+
+fn split(value: u64, count: usize) -> Vec<u16> {
+    (0..count)
+        .rev()
+        .map(|i| (value >> (16 * i)) as u16)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUES: [(DataType, &str); 10] = [
+        (DataType::U16, "65535"),
+        (DataType::I16, "-2"),
+        (DataType::U32, "4000000000"),
+        (DataType::I32, "-123456"),
+        (DataType::F32, "1.5"),
+        (DataType::U64, "18000000000000000000"),
+        (DataType::I64, "-9000000000000"),
+        (DataType::F64, "-0.25"),
+        (DataType::Ascii, "ABC"),
+        (DataType::Bitfield, "0000000010000001"),
+    ];
+
+    #[test]
+    fn every_type_round_trips() {
+        for (data_type, text) in VALUES {
+            let words = data_type
+                .encode(text, 2, ByteOrder::Abcd, DisplayFormat::Decimal)
+                .unwrap_or_else(|| panic!("{} {}", data_type, text));
+            assert_eq!(words.len(), usize::from(data_type.register_count(2)));
+            assert_eq!(data_type.decode(&words, ByteOrder::Abcd), text, "{}", data_type);
+        }
+    }
+
+    #[test]
+    fn wide_values_are_most_significant_word_first() {
+        let words = DataType::I32.encode("-2", 1, ByteOrder::Abcd, DisplayFormat::Decimal);
+        assert_eq!(words, Some(vec![0xFFFF, 0xFFFE]));
+        let words = DataType::F32.encode("1.5", 1, ByteOrder::Abcd, DisplayFormat::Decimal);
+        assert_eq!(words, Some(vec![0x3FC0, 0x0000]));
+        assert_eq!(DataType::U64.decode(&[0, 0, 1, 2], ByteOrder::Abcd), "65538");
+    }
+
+    #[test]
+    fn strings_are_padded_and_capped() {
+        assert_eq!(
+            DataType::Ascii.encode("AB", 2, ByteOrder::Abcd, DisplayFormat::Decimal),
+            Some(vec![0x4142, 0x0000])
+        );
+        assert_eq!(DataType::Ascii.encode("ABCDE", 2, ByteOrder::Abcd, DisplayFormat::Decimal), None);
+        assert_eq!(DataType::Ascii.register_count(0), 1);
+        assert_eq!(DataType::Ascii.register_count(255), u16::from(MAX_ASCII_LENGTH));
+    }
+
+    #[test]
+    fn byte_orders_lay_out_a_u32() {
+        let cases = [
+            (ByteOrder::Abcd, [0x1234, 0x5678]),
+            (ByteOrder::Cdab, [0x5678, 0x1234]),
+            (ByteOrder::Badc, [0x3412, 0x7856]),
+            (ByteOrder::Dcba, [0x7856, 0x3412]),
+        ];
+        for (order, words) in cases {
+            let encoded = DataType::U32.encode("305419896", 1, order, DisplayFormat::Decimal);
+            assert_eq!(encoded, Some(words.to_vec()), "{}", order);
+            assert_eq!(DataType::U32.decode(&words, order), "305419896", "{}", order);
+        }
+    }
+
+    #[test]
+    fn word_swap_reverses_all_four_words() {
+        assert_eq!(
+            ByteOrder::Cdab.apply(&[0x0001, 0x0203, 0x0405, 0x0607]),
+            vec![0x0607, 0x0405, 0x0203, 0x0001]
+        );
+        assert_eq!(DataType::U64.decode(&[2, 1, 0, 0], ByteOrder::Cdab), "65538");
+    }
+
+    #[test]
+    fn every_type_round_trips_in_every_order() {
+        for order in ByteOrder::ALL {
+            for (data_type, text) in VALUES {
+                let words = data_type
+                    .encode(text, 2, order, DisplayFormat::Decimal)
+                    .unwrap_or_else(|| panic!("{} {} {}", data_type, text, order));
+                assert_eq!(data_type.decode(&words, order), text, "{} {}", data_type, order);
+            }
+        }
+    }
+
+    #[test]
+    fn strings_only_swap_bytes() {
+        assert_eq!(DataType::Ascii.decode(&[0x4142, 0x4300], ByteOrder::Cdab), "ABC");
+        assert_eq!(DataType::Ascii.decode(&[0x4241, 0x0043], ByteOrder::Dcba), "ABC");
+    }
+
+    #[test]
+    fn formats_reinterpret_the_raw_bits() {
+        let words = [0xFFFF, 0xFFFE];
+        let order = ByteOrder::Abcd;
+        assert_eq!(DataType::U32.format(&words, order, DisplayFormat::Decimal), "4294967294");
+        assert_eq!(DataType::U32.format(&words, order, DisplayFormat::Signed), "-2");
+        assert_eq!(DataType::U32.format(&words, order, DisplayFormat::Hex), "0xFFFFFFFE");
+        assert_eq!(
+            DataType::U16.format(&[5], order, DisplayFormat::Binary),
+            "0b0000000000000101"
+        );
+        assert_eq!(DataType::U32.format(&[0x3FC0, 0], order, DisplayFormat::Float), "1.5");
+        assert_eq!(DataType::U16.format(&[0x4101], order, DisplayFormat::Ascii), "A.");
+    }
+
+    #[test]
+    fn formatted_text_can_be_typed_back() {
+        let order = ByteOrder::Abcd;
+        // ASCII shows unprintable bytes as dots, there is no way back.
+        for format in DisplayFormat::ALL.into_iter().filter(|x| *x != DisplayFormat::Ascii) {
+            let text = DataType::I32.format(&[0x8000, 0x0001], order, format);
+            let words = DataType::I32.encode(&text, 1, order, format);
+            assert_eq!(words, Some(vec![0x8000, 0x0001]), "{} {}", format, text);
+        }
+    }
+
+    #[test]
+    fn encode_falls_back_to_raw_bits() {
+        let words = DataType::F32.encode("0x3FC00000", 1, ByteOrder::Abcd, DisplayFormat::Decimal);
+        assert_eq!(words, Some(vec![0x3FC0, 0x0000]));
+        assert_eq!(DataType::U16.encode("0x10000", 1, ByteOrder::Abcd, DisplayFormat::Decimal), None);
+    }
+
+    #[test]
+    fn unsigned_parser_refuses_negatives() {
+        assert_eq!(parse_u16("-1"), Some(0xFFFF));
+        assert_eq!(parse_unsigned("-1"), None);
+        assert_eq!(parse_unsigned(" 125 "), Some(125));
+        assert_eq!(parse_unsigned("0x7D"), Some(125));
+        assert_eq!(parse_unsigned("0b1_0000"), Some(16));
+        assert_eq!(parse_unsigned("65536"), None);
+    }
+}
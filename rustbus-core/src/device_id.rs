@@ -0,0 +1,234 @@
+use crate::transport::hex_string;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio_modbus::prelude::{Request, Response};
+
+// Encapsulated Interface Transport carrying MEI type 0x0E.
+pub const FUNCTION: u8 = 0x2B;
+pub const MEI_TYPE: u8 = 0x0E;
+// Devices split long answers, a broken one could keep saying more follows.
+const MAX_CONTINUATIONS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DeviceIdLevel {
+    #[default]
+    Basic,
+    Regular,
+    Extended,
+}
+
+impl DeviceIdLevel {
+    pub const ALL: [DeviceIdLevel; 3] = [
+        DeviceIdLevel::Basic,
+        DeviceIdLevel::Regular,
+        DeviceIdLevel::Extended,
+    ];
+
+    // Read Device ID code sent in the request.
+    fn code(&self) -> u8 {
+        match self {
+            DeviceIdLevel::Basic => 0x01,
+            DeviceIdLevel::Regular => 0x02,
+            DeviceIdLevel::Extended => 0x03,
+        }
+    }
+}
+
+impl std::fmt::Display for DeviceIdLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DeviceIdLevel::Basic => "Basic",
+                DeviceIdLevel::Regular => "Regular",
+                DeviceIdLevel::Extended => "Extended",
+            }
+        )
+    }
+}
+
+pub fn object_name(id: u8) -> String {
+    match id {
+        0x00 => "VendorName".to_string(),
+        0x01 => "ProductCode".to_string(),
+        0x02 => "MajorMinorRevision".to_string(),
+        0x03 => "VendorUrl".to_string(),
+        0x04 => "ProductName".to_string(),
+        0x05 => "ModelName".to_string(),
+        0x06 => "UserApplicationName".to_string(),
+        0x07..=0x7F => format!("Reserved 0x{:02X}", id),
+        _ => format!("Private 0x{:02X}", id),
+    }
+}
+
+// Objects are meant to be ASCII, anything else is shown as hex.
+pub fn object_text(value: &[u8]) -> String {
+    if value.iter().all(|x| x.is_ascii_graphic() || *x == b' ') {
+        String::from_utf8_lossy(value).to_string()
+    } else {
+        hex_string(value)
+    }
+}
+
+pub fn request(level: DeviceIdLevel, object_id: u8) -> Request {
+    Request::Custom(FUNCTION, vec![MEI_TYPE, level.code(), object_id])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceIdResponse {
+    pub conformity: u8,
+    // Id to ask for next when the device has more than fit in this answer.
+    pub next_object: Option<u8>,
+    pub objects: Vec<(u8, Vec<u8>)>,
+}
+
+pub fn parse_response(resp: &Response) -> Result<DeviceIdResponse, String> {
+    let data = match resp {
+        Response::Custom(FUNCTION, data) => data,
+        other => return Err(format!("Unexpected response {:?}", other)),
+    };
+    if data.len() < 6 || data[0] != MEI_TYPE {
+        return Err(format!(
+            "Malformed device identification: {}",
+            hex_string(data)
+        ));
+    }
+    let conformity = data[2];
+    let next_object = if data[3] == 0xFF { Some(data[4]) } else { None };
+    let count = usize::from(data[5]);
+    let mut objects = Vec::with_capacity(count);
+    let mut rest = &data[6..];
+    for _ in 0..count {
+        match rest {
+            [id, len, tail @ ..] if tail.len() >= usize::from(*len) => {
+                let (value, tail) = tail.split_at(usize::from(*len));
+                objects.push((*id, value.to_vec()));
+                rest = tail;
+            }
+            _ => return Err("Device identification object list is truncated".to_string()),
+        }
+    }
+    Ok(DeviceIdResponse {
+        conformity,
+        next_object,
+        objects,
+    })
+}
+
+// Everything read so far for one level, across as many requests as the device
+// needed to send it.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceIdentity {
+    pub level: DeviceIdLevel,
+    pub conformity: Option<u8>,
+    pub objects: BTreeMap<u8, Vec<u8>>,
+    requests: usize,
+}
+
+impl DeviceIdentity {
+    pub fn new(level: DeviceIdLevel) -> Self {
+        Self {
+            level,
+            ..Self::default()
+        }
+    }
+
+    // Stream access starts at object 0, the device sends every object of the
+    // level from there on.
+    pub fn first_request(&self) -> Request {
+        request(self.level, 0x00)
+    }
+
+    // Adds a response, giving the request that continues the stream if the
+    // device said more follows.
+    pub fn apply(&mut self, resp: &Response) -> Result<Option<Request>, String> {
+        let parsed = parse_response(resp)?;
+        self.requests += 1;
+        self.conformity = Some(parsed.conformity);
+        for (id, value) in parsed.objects {
+            self.objects.insert(id, value);
+        }
+        match parsed.next_object {
+            Some(_) if self.requests >= MAX_CONTINUATIONS => Err(format!(
+                "Device still had more objects after {} requests",
+                self.requests
+            )),
+            Some(next) => Ok(Some(request(self.level, next))),
+            None => Ok(None),
+        }
+    }
+}
+
+pub fn conformity_text(conformity: u8) -> String {
+    let level = match conformity & 0x7F {
+        0x01 => "basic",
+        0x02 => "regular",
+        0x03 => "extended",
+        _ => "unknown",
+    };
+    if conformity & 0x80 != 0 {
+        format!("0x{:02X} ({}, individual access)", conformity, level)
+    } else {
+        format!("0x{:02X} ({}, stream access only)", conformity, level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_objects_and_continuation() {
+        let resp = Response::Custom(
+            FUNCTION,
+            vec![
+                MEI_TYPE, 0x01, 0x81, 0xFF, 0x02, 0x02, 0x00, 0x03, b'A', b'C', b'M', 0x01, 0x02,
+                b'X', b'1',
+            ],
+        );
+        assert_eq!(
+            parse_response(&resp),
+            Ok(DeviceIdResponse {
+                conformity: 0x81,
+                next_object: Some(0x02),
+                objects: vec![(0x00, b"ACM".to_vec()), (0x01, b"X1".to_vec())],
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_responses() {
+        let truncated = Response::Custom(
+            FUNCTION,
+            vec![MEI_TYPE, 0x01, 0x01, 0x00, 0x00, 0x01, 0x00, 0x05, b'A'],
+        );
+        assert!(parse_response(&truncated).is_err());
+        let short = Response::Custom(FUNCTION, vec![MEI_TYPE, 0x01]);
+        assert!(parse_response(&short).is_err());
+        assert!(parse_response(&Response::ReadHoldingRegisters(vec![1])).is_err());
+    }
+
+    #[test]
+    fn identity_follows_the_stream() {
+        let mut identity = DeviceIdentity::new(DeviceIdLevel::Regular);
+        assert_eq!(
+            identity.first_request(),
+            Request::Custom(FUNCTION, vec![MEI_TYPE, 0x02, 0x00])
+        );
+        let first = Response::Custom(
+            FUNCTION,
+            vec![MEI_TYPE, 0x02, 0x02, 0xFF, 0x01, 0x01, 0x00, 0x01, b'A'],
+        );
+        assert_eq!(
+            identity.apply(&first),
+            Ok(Some(Request::Custom(FUNCTION, vec![MEI_TYPE, 0x02, 0x01])))
+        );
+        let last = Response::Custom(
+            FUNCTION,
+            vec![MEI_TYPE, 0x02, 0x02, 0x00, 0x00, 0x01, 0x01, 0x01, b'B'],
+        );
+        assert_eq!(identity.apply(&last), Ok(None));
+        assert_eq!(identity.objects.len(), 2);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::transport::hex_string;
+use serde::{Deserialize, Serialize};
+use tokio_modbus::prelude::{Request, Response};
+
+pub const READ_EXCEPTION_STATUS: u8 = 0x07;
+pub const DIAGNOSTICS: u8 = 0x08;
+
+// FC 0x08 sub-functions, plus FC 0x07 which serial devices answer the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Diagnostic {
+    ReturnQueryData,
+    RestartCommunications,
+    RestartClearLog,
+    ReturnDiagnosticRegister,
+    ClearCounters,
+    BusMessageCount,
+    BusCommunicationErrorCount,
+    BusExceptionErrorCount,
+    ServerMessageCount,
+    ServerNoResponseCount,
+    ServerNakCount,
+    ServerBusyCount,
+    BusCharacterOverrunCount,
+    ReadExceptionStatus,
+}
+
+impl Diagnostic {
+    pub const ALL: [Diagnostic; 14] = [
+        Diagnostic::ReturnQueryData,
+        Diagnostic::RestartCommunications,
+        Diagnostic::RestartClearLog,
+        Diagnostic::ReturnDiagnosticRegister,
+        Diagnostic::ClearCounters,
+        Diagnostic::BusMessageCount,
+        Diagnostic::BusCommunicationErrorCount,
+        Diagnostic::BusExceptionErrorCount,
+        Diagnostic::ServerMessageCount,
+        Diagnostic::ServerNoResponseCount,
+        Diagnostic::ServerNakCount,
+        Diagnostic::ServerBusyCount,
+        Diagnostic::BusCharacterOverrunCount,
+        Diagnostic::ReadExceptionStatus,
+    ];
+
+    // The ones a device keeps count of, read together to get a picture of the line.
+    pub const COUNTERS: [Diagnostic; 8] = [
+        Diagnostic::BusMessageCount,
+        Diagnostic::BusCommunicationErrorCount,
+        Diagnostic::BusExceptionErrorCount,
+        Diagnostic::ServerMessageCount,
+        Diagnostic::ServerNoResponseCount,
+        Diagnostic::ServerNakCount,
+        Diagnostic::ServerBusyCount,
+        Diagnostic::BusCharacterOverrunCount,
+    ];
+
+    pub fn sub_function(&self) -> Option<u16> {
+        match self {
+            Diagnostic::ReturnQueryData => Some(0x00),
+            Diagnostic::RestartCommunications | Diagnostic::RestartClearLog => Some(0x01),
+            Diagnostic::ReturnDiagnosticRegister => Some(0x02),
+            Diagnostic::ClearCounters => Some(0x0A),
+            Diagnostic::BusMessageCount => Some(0x0B),
+            Diagnostic::BusCommunicationErrorCount => Some(0x0C),
+            Diagnostic::BusExceptionErrorCount => Some(0x0D),
+            Diagnostic::ServerMessageCount => Some(0x0E),
+            Diagnostic::ServerNoResponseCount => Some(0x0F),
+            Diagnostic::ServerNakCount => Some(0x10),
+            Diagnostic::ServerBusyCount => Some(0x11),
+            Diagnostic::BusCharacterOverrunCount => Some(0x12),
+            Diagnostic::ReadExceptionStatus => None,
+        }
+    }
+
+    // `echo` is only sent by Return Query Data, everything else has fixed data.
+    pub fn request(&self, echo: &[u8]) -> Request {
+        let sub_function = match self.sub_function() {
+            Some(x) => x,
+            None => return Request::Custom(READ_EXCEPTION_STATUS, Vec::new()),
+        };
+        let mut data = sub_function.to_be_bytes().to_vec();
+        match self {
+            Diagnostic::ReturnQueryData => data.extend_from_slice(echo),
+            Diagnostic::RestartClearLog => data.extend_from_slice(&[0xFF, 0x00]),
+            _ => data.extend_from_slice(&[0x00, 0x00]),
+        }
+        Request::Custom(DIAGNOSTICS, data)
+    }
+
+    // What came back, as text for the results table.
+    pub fn parse_response(&self, req: &Request, resp: &Response) -> Result<String, String> {
+        let (sent, data) = match (req, resp) {
+            (
+                Request::Custom(READ_EXCEPTION_STATUS, _),
+                Response::Custom(READ_EXCEPTION_STATUS, data),
+            ) => {
+                return match data.first() {
+                    Some(status) => Ok(format!("0x{:02X} ({:08b})", status, status)),
+                    None => Err("Empty exception status".to_string()),
+                };
+            }
+            (Request::Custom(DIAGNOSTICS, sent), Response::Custom(DIAGNOSTICS, data)) => {
+                (sent, data)
+            }
+            (_, other) => return Err(format!("Unexpected response {:?}", other)),
+        };
+        if data.len() < 2 || data[..2] != sent[..2] {
+            return Err(format!("Sub-function not echoed: {}", hex_string(data)));
+        }
+        let value = &data[2..];
+        let word = || match value {
+            [hi, lo] => Ok(u16::from_be_bytes([*hi, *lo])),
+            _ => Err(format!("Expected 2 data bytes, got {}", hex_string(value))),
+        };
+        match self {
+            Diagnostic::ReturnQueryData if value == &sent[2..] => {
+                Ok(format!("Echoed {}", hex_string(value)))
+            }
+            Diagnostic::ReturnQueryData => Err(format!(
+                "Echo mismatch: sent {}, got {}",
+                hex_string(&sent[2..]),
+                hex_string(value)
+            )),
+            Diagnostic::RestartCommunications | Diagnostic::RestartClearLog => {
+                Ok("Restarted".to_string())
+            }
+            Diagnostic::ClearCounters => Ok("Cleared".to_string()),
+            Diagnostic::ReturnDiagnosticRegister => {
+                word().map(|x| format!("0x{:04X} ({:016b})", x, x))
+            }
+            _ => word().map(|x| x.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Diagnostic::ReturnQueryData => "Return Query Data (08/00)",
+                Diagnostic::RestartCommunications => "Restart Communications (08/01)",
+                Diagnostic::RestartClearLog => "Restart, Clear Log (08/01)",
+                Diagnostic::ReturnDiagnosticRegister => "Diagnostic Register (08/02)",
+                Diagnostic::ClearCounters => "Clear Counters (08/0A)",
+                Diagnostic::BusMessageCount => "Bus Messages (08/0B)",
+                Diagnostic::BusCommunicationErrorCount => "Bus CRC Errors (08/0C)",
+                Diagnostic::BusExceptionErrorCount => "Bus Exceptions (08/0D)",
+                Diagnostic::ServerMessageCount => "Server Messages (08/0E)",
+                Diagnostic::ServerNoResponseCount => "Server No Response (08/0F)",
+                Diagnostic::ServerNakCount => "Server NAKs (08/10)",
+                Diagnostic::ServerBusyCount => "Server Busy (08/11)",
+                Diagnostic::BusCharacterOverrunCount => "Character Overruns (08/12)",
+                Diagnostic::ReadExceptionStatus => "Read Exception Status (07)",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_carry_sub_function_and_data() {
+        assert_eq!(
+            Diagnostic::ReturnQueryData.request(&[0xA5, 0x37]),
+            Request::Custom(DIAGNOSTICS, vec![0x00, 0x00, 0xA5, 0x37])
+        );
+        assert_eq!(
+            Diagnostic::RestartClearLog.request(&[]),
+            Request::Custom(DIAGNOSTICS, vec![0x00, 0x01, 0xFF, 0x00])
+        );
+        assert_eq!(
+            Diagnostic::BusMessageCount.request(&[0xA5]),
+            Request::Custom(DIAGNOSTICS, vec![0x00, 0x0B, 0x00, 0x00])
+        );
+        assert_eq!(
+            Diagnostic::ReadExceptionStatus.request(&[]),
+            Request::Custom(READ_EXCEPTION_STATUS, Vec::new())
+        );
+    }
+
+    #[test]
+    fn responses_are_shown_per_sub_function() {
+        let parse = |diag: Diagnostic, echo: &[u8], data: Vec<u8>| {
+            let function = match diag {
+                Diagnostic::ReadExceptionStatus => READ_EXCEPTION_STATUS,
+                _ => DIAGNOSTICS,
+            };
+            diag.parse_response(&diag.request(echo), &Response::Custom(function, data))
+        };
+        assert_eq!(
+            parse(
+                Diagnostic::ReturnQueryData,
+                &[0xA5, 0x37],
+                vec![0x00, 0x00, 0xA5, 0x37]
+            ),
+            Ok("Echoed A5 37".to_string())
+        );
+        assert_eq!(
+            parse(
+                Diagnostic::BusMessageCount,
+                &[],
+                vec![0x00, 0x0B, 0x01, 0x2C]
+            ),
+            Ok("300".to_string())
+        );
+        assert_eq!(
+            parse(
+                Diagnostic::ReturnDiagnosticRegister,
+                &[],
+                vec![0x00, 0x02, 0x00, 0x81]
+            ),
+            Ok("0x0081 (0000000010000001)".to_string())
+        );
+        assert_eq!(
+            parse(Diagnostic::ClearCounters, &[], vec![0x00, 0x0A, 0x00, 0x00]),
+            Ok("Cleared".to_string())
+        );
+        assert_eq!(
+            parse(Diagnostic::ReadExceptionStatus, &[], vec![0x05]),
+            Ok("0x05 (00000101)".to_string())
+        );
+    }
+
+    #[test]
+    fn bad_responses_are_errors() {
+        let count = Diagnostic::ServerBusyCount;
+        let req = count.request(&[]);
+        let bad = |data: Vec<u8>| count.parse_response(&req, &Response::Custom(DIAGNOSTICS, data));
+        assert_eq!(
+            bad(vec![0x00, 0x0B, 0x00, 0x01]),
+            Err("Sub-function not echoed: 00 0B 00 01".to_string())
+        );
+        assert_eq!(
+            bad(vec![0x00, 0x11, 0x01]),
+            Err("Expected 2 data bytes, got 01".to_string())
+        );
+        assert!(count
+            .parse_response(&req, &Response::ReadCoils(vec![true]))
+            .is_err());
+
+        let echo = Diagnostic::ReturnQueryData;
+        assert_eq!(
+            echo.parse_response(
+                &echo.request(&[0x12, 0x34]),
+                &Response::Custom(DIAGNOSTICS, vec![0x00, 0x00, 0x12, 0x35])
+            ),
+            Err("Echo mismatch: sent 12 34, got 12 35".to_string())
+        );
+        assert_eq!(
+            Diagnostic::ReadExceptionStatus.parse_response(
+                &Diagnostic::ReadExceptionStatus.request(&[]),
+                &Response::Custom(READ_EXCEPTION_STATUS, Vec::new())
+            ),
+            Err("Empty exception status".to_string())
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::{Error, ErrorKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceptionCode {
+    IllegalFunction,
+    IllegalDataAddress,
+    IllegalDataValue,
+    ServerDeviceFailure,
+    Acknowledge,
+    ServerDeviceBusy,
+    NegativeAcknowledge,
+    MemoryParityError,
+    GatewayPathUnavailable,
+    GatewayTargetDeviceFailedToRespond,
+    Unknown(u8),
+}
+
+impl ExceptionCode {
+    pub fn code(&self) -> u8 {
+        match self {
+            ExceptionCode::IllegalFunction => 0x01,
+            ExceptionCode::IllegalDataAddress => 0x02,
+            ExceptionCode::IllegalDataValue => 0x03,
+            ExceptionCode::ServerDeviceFailure => 0x04,
+            ExceptionCode::Acknowledge => 0x05,
+            ExceptionCode::ServerDeviceBusy => 0x06,
+            ExceptionCode::NegativeAcknowledge => 0x07,
+            ExceptionCode::MemoryParityError => 0x08,
+            ExceptionCode::GatewayPathUnavailable => 0x0A,
+            ExceptionCode::GatewayTargetDeviceFailedToRespond => 0x0B,
+            ExceptionCode::Unknown(code) => *code,
+        }
+    }
+}
+
+impl From<u8> for ExceptionCode {
+    fn from(code: u8) -> Self {
+        match code {
+            0x01 => ExceptionCode::IllegalFunction,
+            0x02 => ExceptionCode::IllegalDataAddress,
+            0x03 => ExceptionCode::IllegalDataValue,
+            0x04 => ExceptionCode::ServerDeviceFailure,
+            0x05 => ExceptionCode::Acknowledge,
+            0x06 => ExceptionCode::ServerDeviceBusy,
+            0x07 => ExceptionCode::NegativeAcknowledge,
+            0x08 => ExceptionCode::MemoryParityError,
+            0x0A => ExceptionCode::GatewayPathUnavailable,
+            0x0B => ExceptionCode::GatewayTargetDeviceFailedToRespond,
+            x => ExceptionCode::Unknown(x),
+        }
+    }
+}
+
+// Names as the spec writes them.
+impl std::fmt::Display for ExceptionCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ExceptionCode::IllegalFunction => "IllegalFunction",
+                ExceptionCode::IllegalDataAddress => "IllegalDataAddress",
+                ExceptionCode::IllegalDataValue => "IllegalDataValue",
+                ExceptionCode::ServerDeviceFailure => "ServerDeviceFailure",
+                ExceptionCode::Acknowledge => "Acknowledge",
+                ExceptionCode::ServerDeviceBusy => "ServerDeviceBusy",
+                ExceptionCode::NegativeAcknowledge => "NegativeAcknowledge",
+                ExceptionCode::MemoryParityError => "MemoryParityError",
+                ExceptionCode::GatewayPathUnavailable => "GatewayPathUnavailable",
+                ExceptionCode::GatewayTargetDeviceFailedToRespond => {
+                    "GatewayTargetDeviceFailedToRespond"
+                }
+                ExceptionCode::Unknown(_) => "Unknown",
+            }
+        )
+    }
+}
+
+// Why a transaction failed. Transports hand it over inside an `io::Error`
+// since that is what tokio-modbus clients return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModbusError {
+    Io(String),
+    Timeout(String),
+    // CRC mismatches, bad headers and anything else that did not frame
+    Framing(String),
+    Exception { function: u8, code: ExceptionCode },
+}
+
+impl ModbusError {
+    pub fn is_exception(&self) -> bool {
+        matches!(self, ModbusError::Exception { .. })
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ModbusError::Io(_) => "I/O",
+            ModbusError::Timeout(_) => "Timeout",
+            ModbusError::Framing(_) => "Framing",
+            ModbusError::Exception { .. } => "Exception",
+        }
+    }
+}
+
+impl std::fmt::Display for ModbusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModbusError::Io(e) => write!(f, "I/O error: {}", e),
+            ModbusError::Timeout(e) => write!(f, "Timeout: {}", e),
+            ModbusError::Framing(e) => write!(f, "Framing error: {}", e),
+            ModbusError::Exception { function, code } => write!(
+                f,
+                "Exception 0x{:02X} {} (function 0x{:02X})",
+                code.code(),
+                code,
+                function
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ModbusError {}
+
+impl From<&Error> for ModbusError {
+    fn from(e: &Error) -> Self {
+        if let Some(inner) = e.get_ref().and_then(|x| x.downcast_ref::<ModbusError>()) {
+            return inner.clone();
+        }
+        match e.kind() {
+            ErrorKind::TimedOut => ModbusError::Timeout(e.to_string()),
+            ErrorKind::InvalidData => ModbusError::Framing(e.to_string()),
+            _ => ModbusError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<ModbusError> for Error {
+    fn from(e: ModbusError) -> Self {
+        let kind = match e {
+            ModbusError::Io(_) | ModbusError::Exception { .. } => ErrorKind::Other,
+            ModbusError::Timeout(_) => ErrorKind::TimedOut,
+            ModbusError::Framing(_) => ErrorKind::InvalidData,
+        };
+        Error::new(kind, e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exception_codes_round_trip() {
+        for code in 0..=0xFF {
+            assert_eq!(ExceptionCode::from(code).code(), code);
+        }
+        assert_eq!(
+            ExceptionCode::from(0x04),
+            ExceptionCode::ServerDeviceFailure
+        );
+        assert_eq!(
+            ExceptionCode::from(0x0B),
+            ExceptionCode::GatewayTargetDeviceFailedToRespond
+        );
+        assert_eq!(ExceptionCode::from(0x09), ExceptionCode::Unknown(0x09));
+    }
+
+    #[test]
+    fn modbus_error_survives_io_error() {
+        let err = ModbusError::Exception {
+            function: 0x10,
+            code: ExceptionCode::IllegalDataValue,
+        };
+        let io = Error::from(err.clone());
+        assert_eq!(io.kind(), ErrorKind::Other);
+        assert_eq!(ModbusError::from(&io), err);
+        assert!(ModbusError::from(&io).is_exception());
+        assert_eq!(
+            err.to_string(),
+            "Exception 0x03 IllegalDataValue (function 0x10)"
+        );
+    }
+
+    #[test]
+    fn io_error_kinds_map_to_modbus_errors() {
+        let timeout = Error::new(ErrorKind::TimedOut, "no answer");
+        assert_eq!(ModbusError::from(&timeout).kind(), "Timeout");
+        let framing = Error::new(ErrorKind::InvalidData, "bad crc");
+        assert_eq!(ModbusError::from(&framing).kind(), "Framing");
+        let io = Error::from(ErrorKind::BrokenPipe);
+        assert_eq!(ModbusError::from(&io).kind(), "I/O");
+    }
+}
@@ -0,0 +1,230 @@
+use crate::transport::hex_string;
+use bytes::Bytes;
+use tokio_modbus::prelude::{Request, Response};
+
+pub const READ_FILE_RECORD: u8 = 0x14;
+pub const WRITE_FILE_RECORD: u8 = 0x15;
+pub const READ_FIFO_QUEUE: u8 = 0x18;
+// Every sub-request carries this reference type.
+const REFERENCE_TYPE: u8 = 0x06;
+pub const MAX_RECORD: u16 = 0x270F;
+pub const MAX_FIFO_COUNT: u16 = 31;
+// Most registers a single group can read or write within one PDU.
+pub const MAX_RECORD_LENGTH: u16 = 121;
+
+// One group of records in a file. The request builder sends a single group
+// per request, `values` is only used when writing.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileRecord {
+    pub file: u16,
+    pub record: u16,
+    pub length: u16,
+    pub values: Vec<u16>,
+}
+
+impl FileRecord {
+    pub fn read_request(&self) -> Request {
+        let mut data = vec![7, REFERENCE_TYPE];
+        data.extend_from_slice(&self.file.to_be_bytes());
+        data.extend_from_slice(&self.record.to_be_bytes());
+        data.extend_from_slice(&self.length.to_be_bytes());
+        Request::Custom(READ_FILE_RECORD, data)
+    }
+
+    // The length written is the number of values.
+    pub fn write_request(&self) -> Request {
+        let mut data = vec![0, REFERENCE_TYPE];
+        data.extend_from_slice(&self.file.to_be_bytes());
+        data.extend_from_slice(&self.record.to_be_bytes());
+        data.extend_from_slice(&(self.values.len() as u16).to_be_bytes());
+        for x in &self.values {
+            data.extend_from_slice(&x.to_be_bytes());
+        }
+        data[0] = (data.len() - 1) as u8;
+        Request::Custom(WRITE_FILE_RECORD, data)
+    }
+
+    // The first group of a read or write request.
+    pub fn from_request(req: &Request) -> Option<FileRecord> {
+        let (function, data) = match req {
+            Request::Custom(function @ (READ_FILE_RECORD | WRITE_FILE_RECORD), data) => {
+                (*function, data)
+            }
+            _ => return None,
+        };
+        let (group, rest) = match data.as_slice() {
+            [_byte_count, REFERENCE_TYPE, f0, f1, r0, r1, l0, l1, rest @ ..] => (
+                FileRecord {
+                    file: u16::from_be_bytes([*f0, *f1]),
+                    record: u16::from_be_bytes([*r0, *r1]),
+                    length: u16::from_be_bytes([*l0, *l1]),
+                    values: Vec::new(),
+                },
+                rest,
+            ),
+            _ => return None,
+        };
+        if function == READ_FILE_RECORD {
+            return Some(group);
+        }
+        let values: Vec<u16> = rest
+            .chunks_exact(2)
+            .take(usize::from(group.length))
+            .map(|x| u16::from_be_bytes([x[0], x[1]]))
+            .collect();
+        if values.len() != usize::from(group.length) {
+            return None;
+        }
+        Some(FileRecord { values, ..group })
+    }
+}
+
+// Record data of every group in a read response, in request order.
+pub fn parse_read_response(resp: &Response) -> Result<Vec<Vec<u16>>, String> {
+    let data = match resp {
+        Response::Custom(READ_FILE_RECORD, data) => data,
+        other => return Err(format!("Unexpected response {:?}", other)),
+    };
+    let malformed = || format!("Malformed file record response: {}", hex_string(data));
+    let length = usize::from(*data.first().ok_or_else(malformed)?);
+    let mut rest = data.get(1..1 + length).ok_or_else(malformed)?;
+    let mut groups = Vec::new();
+    while !rest.is_empty() {
+        match rest {
+            [len, REFERENCE_TYPE, tail @ ..]
+                if *len >= 1 && tail.len() >= usize::from(*len) - 1 =>
+            {
+                let (words, tail) = tail.split_at(usize::from(*len) - 1);
+                if words.len() % 2 != 0 {
+                    return Err(malformed());
+                }
+                groups.push(
+                    words
+                        .chunks_exact(2)
+                        .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                        .collect(),
+                );
+                rest = tail;
+            }
+            _ => return Err(malformed()),
+        }
+    }
+    Ok(groups)
+}
+
+pub fn fifo_request(address: u16) -> Request {
+    Request::Custom(READ_FIFO_QUEUE, address.to_be_bytes().to_vec())
+}
+
+pub fn fifo_address(req: &Request) -> Option<u16> {
+    match req {
+        Request::Custom(READ_FIFO_QUEUE, data) => match data.as_slice() {
+            [hi, lo] => Some(u16::from_be_bytes([*hi, *lo])),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Queued values, oldest first.
+pub fn parse_fifo_response(resp: &Response) -> Result<Vec<u16>, String> {
+    let data = match resp {
+        Response::Custom(READ_FIFO_QUEUE, data) => data,
+        other => return Err(format!("Unexpected response {:?}", other)),
+    };
+    let (count, words) = match data.as_slice() {
+        [_b0, _b1, c0, c1, words @ ..] => (usize::from(u16::from_be_bytes([*c0, *c1])), words),
+        _ => return Err(format!("Malformed FIFO response: {}", hex_string(data))),
+    };
+    if words.len() != count * 2 {
+        return Err(format!(
+            "FIFO count is {} but {} bytes of values followed",
+            count,
+            words.len()
+        ));
+    }
+    Ok(words
+        .chunks_exact(2)
+        .map(|x| u16::from_be_bytes([x[0], x[1]]))
+        .collect())
+}
+
+// What goes into an exported file: the record or queue data when the response
+// parses, the whole PDU otherwise.
+pub fn raw_bytes(resp: &Response) -> Vec<u8> {
+    let words = match resp {
+        Response::Custom(READ_FILE_RECORD, _) => parse_read_response(resp).map(|x| x.concat()),
+        Response::Custom(READ_FIFO_QUEUE, _) => parse_fifo_response(resp),
+        _ => Err(String::new()),
+    };
+    match words {
+        Ok(words) => words.iter().flat_map(|x| x.to_be_bytes()).collect(),
+        Err(_e) => Bytes::from(resp.clone()).to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_group() {
+        let resp = Response::Custom(
+            READ_FILE_RECORD,
+            vec![
+                0x0C,
+                0x05,
+                REFERENCE_TYPE,
+                0x0D,
+                0xFE,
+                0x00,
+                0x20,
+                0x05,
+                REFERENCE_TYPE,
+                0x33,
+                0xCD,
+                0x00,
+                0x40,
+            ],
+        );
+        assert_eq!(
+            parse_read_response(&resp),
+            Ok(vec![vec![0x0DFE, 0x0020], vec![0x33CD, 0x0040]])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_responses() {
+        let odd = Response::Custom(
+            READ_FILE_RECORD,
+            vec![0x05, 0x04, REFERENCE_TYPE, 0x0D, 0xFE, 0x00],
+        );
+        assert!(parse_read_response(&odd).is_err());
+        let truncated = Response::Custom(READ_FILE_RECORD, vec![0x08, 0x05, REFERENCE_TYPE, 0x0D]);
+        assert!(parse_read_response(&truncated).is_err());
+        let reference = Response::Custom(READ_FILE_RECORD, vec![0x03, 0x03, 0x07, 0x00]);
+        assert!(parse_read_response(&reference).is_err());
+        assert!(parse_read_response(&Response::ReadCoils(vec![true])).is_err());
+    }
+
+    #[test]
+    fn requests_round_trip() {
+        let record = FileRecord {
+            file: 4,
+            record: 7,
+            length: 2,
+            values: vec![0x06AF, 0x04BE],
+        };
+        assert_eq!(
+            FileRecord::from_request(&record.write_request()),
+            Some(record.clone())
+        );
+        assert_eq!(
+            FileRecord::from_request(&record.read_request()),
+            Some(FileRecord {
+                values: Vec::new(),
+                ..record
+            })
+        );
+    }
+}
@@ -0,0 +1,12 @@
+pub mod connection;
+pub mod controller;
+pub mod data_type;
+pub mod device_id;
+pub mod diagnostics;
+pub mod error;
+pub mod file_record;
+pub mod logger;
+pub mod project;
+pub mod request;
+pub mod table;
+pub mod transport;
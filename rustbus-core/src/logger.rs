@@ -0,0 +1,344 @@
+use crate::data_type::{ByteOrder, DisplayFormat};
+use crate::table::Table;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LogValues {
+    Raw,
+    #[default]
+    Scaled,
+}
+
+impl LogValues {
+    pub const ALL: [LogValues; 2] = [LogValues::Raw, LogValues::Scaled];
+}
+
+impl std::fmt::Display for LogValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LogValues::Raw => "Raw",
+                LogValues::Scaled => "Scaled",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Rotation {
+    #[default]
+    Never,
+    Size,
+    Time,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 3] = [Rotation::Never, Rotation::Size, Rotation::Time];
+}
+
+impl std::fmt::Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Rotation::Never => "No Rotation",
+                Rotation::Size => "Rotate by Size",
+                Rotation::Time => "Rotate by Time",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogConfig {
+    pub directory: PathBuf,
+    pub prefix: String,
+    #[serde(default)]
+    pub values: LogValues,
+    #[serde(default)]
+    pub rotation: Rotation,
+    // Only the limit matching `rotation` is used.
+    #[serde(default = "default_max_size_kb")]
+    pub max_size_kb: u64,
+    #[serde(default = "default_max_age")]
+    pub max_age: Duration,
+}
+
+fn default_max_size_kb() -> u64 {
+    1024
+}
+
+fn default_max_age() -> Duration {
+    Duration::from_secs(3600)
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("."),
+            prefix: String::from("rustbus"),
+            values: LogValues::default(),
+            rotation: Rotation::default(),
+            max_size_kb: default_max_size_kb(),
+            max_age: default_max_age(),
+        }
+    }
+}
+
+// Writes one timestamped row per call with a column for every named register.
+// Columns are fixed when logging starts so every file of a run lines up.
+pub struct DataLogger {
+    config: LogConfig,
+    columns: Vec<String>,
+    file: BufWriter<File>,
+    path: PathBuf,
+    bytes: u64,
+    opened: Instant,
+    rows: u64,
+}
+
+impl std::fmt::Debug for DataLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataLogger")
+            .field("path", &self.path)
+            .field("columns", &self.columns)
+            .field("rows", &self.rows)
+            .finish()
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// A file per start or rotation, named after the time it was opened.
+fn new_path(config: &LogConfig) -> PathBuf {
+    let stamp = Local::now().format("%Y%m%d_%H%M%S");
+    let mut path = config
+        .directory
+        .join(format!("{}_{}.csv", config.prefix, stamp));
+    let mut n = 1;
+    while path.exists() {
+        path = config
+            .directory
+            .join(format!("{}_{}_{}.csv", config.prefix, stamp, n));
+        n += 1;
+    }
+    path
+}
+
+fn open(config: &LogConfig, columns: &[String]) -> Result<(BufWriter<File>, PathBuf, u64), Error> {
+    let path = new_path(config);
+    let mut file = BufWriter::new(File::create(&path)?);
+    let header: Vec<String> = columns.iter().map(|x| csv_field(x)).collect();
+    let line = format!("timestamp,{}\n", header.join(","));
+    file.write_all(line.as_bytes())?;
+    file.flush()?;
+    Ok((file, path, line.len() as u64))
+}
+
+impl DataLogger {
+    pub fn start(config: LogConfig, table: &Table) -> Result<Self, Error> {
+        let mut columns: Vec<String> = Vec::new();
+        for reg in table.registers() {
+            let name = reg.name().trim();
+            if !name.is_empty() && !columns.iter().any(|x| x == name) {
+                columns.push(name.to_string());
+            }
+        }
+        if columns.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The table has no named rows to log",
+            ));
+        }
+        let (file, path, bytes) = open(&config, &columns)?;
+        Ok(Self {
+            config,
+            columns,
+            file,
+            path,
+            bytes,
+            opened: Instant::now(),
+            rows: 0,
+        })
+    }
+
+    fn should_rotate(&self) -> bool {
+        match self.config.rotation {
+            Rotation::Never => false,
+            Rotation::Size => self.bytes >= self.config.max_size_kb.max(1) * 1024,
+            Rotation::Time => self.opened.elapsed() >= self.config.max_age,
+        }
+    }
+
+    // Rows renamed or deleted since starting leave their column empty.
+    pub fn log(&mut self, table: &Table, default_order: ByteOrder) -> Result<(), Error> {
+        if self.should_rotate() {
+            let (file, path, bytes) = open(&self.config, &self.columns)?;
+            self.file = file;
+            self.path = path;
+            self.bytes = bytes;
+            self.opened = Instant::now();
+        }
+        let fields: Vec<String> = self
+            .columns
+            .iter()
+            .map(
+                |name| match table.registers().iter().find(|x| x.name().trim() == name) {
+                    Some(reg) => match self.config.values {
+                        LogValues::Raw => csv_field(&reg.raw_text(default_order)),
+                        LogValues::Scaled => {
+                            csv_field(&reg.value_text(default_order, DisplayFormat::Decimal))
+                        }
+                    },
+                    None => String::new(),
+                },
+            )
+            .collect();
+        let line = format!(
+            "{},{}\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            fields.join(",")
+        );
+        // Flushed every row so the file can be followed while logging.
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        self.bytes += line.len() as u64;
+        self.rows += 1;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::{RegisterType, TableCommand};
+
+    // A scaled "Temp" row, an unnamed row, a second "Temp" and a coil whose
+    // name needs quoting.
+    fn table() -> Table {
+        let mut table = Table::default();
+        let order = ByteOrder::default();
+        for _ in 0..4 {
+            table.handle_commmand(TableCommand::AddRegister(None), order);
+        }
+        let commands = [
+            TableCommand::SetType(0, RegisterType::HoldingRegister),
+            TableCommand::SetName(0, "Temp".to_string()),
+            TableCommand::SetValue(0, "653".to_string()),
+            TableCommand::SetScale(0, "0.1".to_string()),
+            TableCommand::SetOffset(0, "-40".to_string()),
+            TableCommand::SetDecimals(0, Some(1)),
+            TableCommand::SetName(2, " Temp ".to_string()),
+            TableCommand::SetName(3, "Pump, main".to_string()),
+        ];
+        for tc in commands {
+            table.handle_commmand(tc, order);
+        }
+        table
+    }
+
+    fn config(test: &str) -> LogConfig {
+        let directory =
+            std::env::temp_dir().join(format!("rustbus-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        LogConfig {
+            directory,
+            ..LogConfig::default()
+        }
+    }
+
+    fn lines(path: &Path) -> Vec<String> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn start_needs_a_named_row() {
+        let config = config("unnamed");
+        let mut table = Table::default();
+        table.handle_commmand(TableCommand::AddRegister(None), ByteOrder::default());
+        let err = DataLogger::start(config.clone(), &table).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        std::fs::remove_dir_all(config.directory).unwrap();
+    }
+
+    #[test]
+    fn rows_have_a_column_per_name() {
+        let config = config("columns");
+        let mut table = table();
+        let mut logger = DataLogger::start(config.clone(), &table).unwrap();
+        logger.log(&table, ByteOrder::default()).unwrap();
+        // A renamed row is no longer found under its column.
+        table.handle_commmand(
+            TableCommand::SetName(3, "Pump".to_string()),
+            ByteOrder::default(),
+        );
+        logger.log(&table, ByteOrder::default()).unwrap();
+        assert_eq!(logger.rows(), 2);
+
+        let lines = lines(logger.path());
+        assert_eq!(lines[0], "timestamp,Temp,\"Pump, main\"");
+        assert!(lines[1].ends_with(",25.3,0"), "{}", lines[1]);
+        assert!(lines[2].ends_with(",25.3,"), "{}", lines[2]);
+        std::fs::remove_dir_all(config.directory).unwrap();
+    }
+
+    #[test]
+    fn raw_values_skip_the_scaling() {
+        let config = LogConfig {
+            values: LogValues::Raw,
+            ..config("raw")
+        };
+        let table = table();
+        let mut logger = DataLogger::start(config.clone(), &table).unwrap();
+        logger.log(&table, ByteOrder::default()).unwrap();
+        assert!(lines(logger.path())[1].ends_with(",653,0"));
+        std::fs::remove_dir_all(config.directory).unwrap();
+    }
+
+    #[test]
+    fn size_rotation_starts_a_new_file() {
+        let config = LogConfig {
+            rotation: Rotation::Size,
+            max_size_kb: 1,
+            ..config("rotation")
+        };
+        let table = table();
+        let mut logger = DataLogger::start(config.clone(), &table).unwrap();
+        let first = logger.path().to_path_buf();
+        while logger.path() == first {
+            logger.log(&table, ByteOrder::default()).unwrap();
+        }
+        assert!(std::fs::metadata(&first).unwrap().len() >= 1024);
+        let second = lines(logger.path());
+        assert_eq!(second.len(), 2);
+        assert_eq!(second[0], "timestamp,Temp,\"Pump, main\"");
+        std::fs::remove_dir_all(config.directory).unwrap();
+    }
+}
@@ -0,0 +1,26 @@
+use crate::connection::ConnectionParams;
+use crate::controller::{ReconnectPolicy, RetryPolicy};
+use crate::table::Table;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+
+// The parts of a saved project any front-end can use. Fields a front-end adds
+// of its own are skipped when loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub connection: ConnectionParams,
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    pub table: Table,
+}
+
+impl Project {
+    pub fn load_from_file(path: &std::path::Path) -> Result<Project, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
@@ -0,0 +1,352 @@
+use crate::file_record::{self, FileRecord};
+use crate::transport::hex_string;
+use bytes::Bytes;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tokio_modbus::prelude::{Request, Response};
+
+// Largest quantities a single PDU can carry.
+pub const MAX_READ_BITS: u16 = 2000;
+pub const MAX_READ_REGISTERS: u16 = 125;
+pub const MAX_WRITE_COILS: u16 = 1968;
+pub const MAX_WRITE_REGISTERS: u16 = 123;
+// Read/Write Multiple Registers has less room left for the registers it writes.
+pub const MAX_READ_WRITE_REGISTERS: u16 = 121;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RequestType {
+    ReadCoils,
+    ReadDiscreteInputs,
+    WriteSingleCoil,
+    WriteMultipleCoils,
+    ReadInputRegisters,
+    ReadHoldingRegisters,
+    WriteSingleRegister,
+    WriteMultipleRegisters,
+    MaskWriteRegister,
+    ReadWriteMultipleRegisters,
+    ReadFileRecord,
+    WriteFileRecord,
+    ReadFifoQueue,
+    Custom
+}
+
+impl RequestType {
+    pub fn new_request(&self) -> Request {
+        match self {
+            RequestType::ReadCoils => Request::ReadCoils(0, 0),
+            RequestType::ReadDiscreteInputs => Request::ReadDiscreteInputs(0, 0),
+            RequestType::WriteSingleCoil => Request::WriteSingleCoil(0, false),
+            RequestType::WriteMultipleCoils => Request::WriteMultipleCoils(0, Vec::new()),
+            RequestType::ReadInputRegisters => Request::ReadInputRegisters(0, 0),
+            RequestType::ReadHoldingRegisters => Request::ReadHoldingRegisters(0, 0),
+            RequestType::WriteSingleRegister => Request::WriteSingleRegister(0, 0),
+            RequestType::WriteMultipleRegisters => Request::WriteMultipleRegisters(0, Vec::new()),
+            RequestType::MaskWriteRegister => Request::MaskWriteRegister(0, 0xFFFF, 0),
+            RequestType::ReadWriteMultipleRegisters => {
+                Request::ReadWriteMultipleRegisters(0, 0, 0, Vec::new())
+            }
+            RequestType::ReadFileRecord => FileRecord {
+                file: 1,
+                length: 1,
+                ..FileRecord::default()
+            }
+            .read_request(),
+            RequestType::WriteFileRecord => FileRecord {
+                file: 1,
+                values: vec![0],
+                ..FileRecord::default()
+            }
+            .write_request(),
+            RequestType::ReadFifoQueue => file_record::fifo_request(0),
+            RequestType::Custom => Request::Custom(0x41, Vec::new()),
+        }
+    }
+}
+
+impl std::fmt::Display for RequestType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                RequestType::ReadCoils => "Read Coils (0x01)",
+                RequestType::ReadDiscreteInputs => "Read Discrete Inputs (0x02)",
+                RequestType::WriteSingleCoil => "Write Single Coil (0x05)",
+                RequestType::WriteMultipleCoils => "Write Multiple Coils (0x0F)",
+                RequestType::ReadInputRegisters => "Read Input Registers (0x04)",
+                RequestType::ReadHoldingRegisters => "Read Holding Registers (0x03)",
+                RequestType::WriteSingleRegister => "Write Single Register (0x06)",
+                RequestType::WriteMultipleRegisters => "Write Multiple Registers (0x10)",
+                RequestType::MaskWriteRegister => "Mask Write Register (0x16)",
+                RequestType::ReadWriteMultipleRegisters => "Read/Write Multiple Registers (0x17)",
+                RequestType::ReadFileRecord => "Read File Record (0x14)",
+                RequestType::WriteFileRecord => "Write File Record (0x15)",
+                RequestType::ReadFifoQueue => "Read FIFO Queue (0x18)",
+                RequestType::Custom => "Custom Function Code",
+            }
+        )
+    }
+}
+
+impl From<&Request> for RequestType {
+    fn from(req: &Request) -> Self {
+        match req {
+            Request::ReadCoils(..) => RequestType::ReadCoils,
+            Request::ReadDiscreteInputs(..) => RequestType::ReadDiscreteInputs,
+            Request::WriteSingleCoil(..) => RequestType::WriteSingleCoil,
+            Request::WriteMultipleCoils(..) => RequestType::WriteMultipleCoils,
+            Request::ReadInputRegisters(..) => RequestType::ReadInputRegisters,
+            Request::ReadHoldingRegisters(..) => RequestType::ReadHoldingRegisters,
+            Request::WriteSingleRegister(..) => RequestType::WriteSingleRegister,
+            Request::WriteMultipleRegisters(..) => RequestType::WriteMultipleRegisters,
+            Request::MaskWriteRegister(..) => RequestType::MaskWriteRegister,
+            Request::ReadWriteMultipleRegisters(..) => RequestType::ReadWriteMultipleRegisters,
+            // Only what the builder can edit gets its own mode back.
+            Request::Custom(file_record::READ_FILE_RECORD, _)
+                if FileRecord::from_request(req).is_some() =>
+            {
+                RequestType::ReadFileRecord
+            }
+            Request::Custom(file_record::WRITE_FILE_RECORD, _)
+                if FileRecord::from_request(req).is_some() =>
+            {
+                RequestType::WriteFileRecord
+            }
+            Request::Custom(file_record::READ_FIFO_QUEUE, _)
+                if file_record::fifo_address(req).is_some() =>
+            {
+                RequestType::ReadFifoQueue
+            }
+            _ => RequestType::Custom,
+        }
+    }
+}
+
+impl RequestType {
+    pub const ALL: [RequestType; 14] = [
+        RequestType::ReadCoils,
+        RequestType::ReadDiscreteInputs,
+        RequestType::WriteSingleCoil,
+        RequestType::WriteMultipleCoils,
+        RequestType::ReadInputRegisters,
+        RequestType::ReadHoldingRegisters,
+        RequestType::WriteSingleRegister,
+        RequestType::WriteMultipleRegisters,
+        RequestType::MaskWriteRegister,
+        RequestType::ReadWriteMultipleRegisters,
+        RequestType::ReadFileRecord,
+        RequestType::WriteFileRecord,
+        RequestType::ReadFifoQueue,
+        RequestType::Custom,
+    ];
+}
+
+pub fn get_address(req: &Request) -> u16 {
+    match req {
+        Request::ReadCoils(addr, _val) => *addr,
+        Request::ReadDiscreteInputs(addr, _val) => *addr,
+        Request::WriteSingleCoil(addr, _val) => *addr,
+        Request::WriteMultipleCoils(addr, _val) => *addr,
+        Request::ReadInputRegisters(addr, _val) => *addr,
+        Request::ReadHoldingRegisters(addr, _val) => *addr,
+        Request::WriteSingleRegister(addr, _val) => *addr,
+        Request::WriteMultipleRegisters(addr, _val) => *addr,
+        Request::MaskWriteRegister(addr, _and_mask, _or_mask) => *addr,
+        Request::ReadWriteMultipleRegisters(addr, _qty, _write_addr, _vals) => *addr,
+        Request::Custom(_function, _data) => file_record::fifo_address(req).unwrap_or(0),
+        _ => unreachable!(),
+    }
+}
+
+pub fn request_data(req: &Request) -> String {
+    match req {
+        Request::ReadCoils(_addr, val) => val.to_string(),
+        Request::ReadDiscreteInputs(_addr, val) => val.to_string(),
+        Request::WriteSingleCoil(_addr, val) => u8::from(*val).to_string(),
+        Request::WriteMultipleCoils(_addr, vals) => bool_list(vals),
+        Request::ReadInputRegisters(_addr, val) => val.to_string(),
+        Request::ReadHoldingRegisters(_addr, val) => val.to_string(),
+        Request::WriteSingleRegister(_addr, val) => val.to_string(),
+        Request::WriteMultipleRegisters(_addr, vals) => word_list(vals),
+        Request::MaskWriteRegister(_addr, and_mask, or_mask) => {
+            format!("AND 0x{:04X} OR 0x{:04X}", and_mask, or_mask)
+        }
+        Request::ReadWriteMultipleRegisters(_addr, qty, write_addr, vals) => {
+            format!("R {} / W @{}: {}", qty, write_addr, word_list(vals))
+        }
+        Request::Custom(function, data) => match FileRecord::from_request(req) {
+            Some(rec) if *function == file_record::READ_FILE_RECORD => {
+                format!("File {} Record {} x{}", rec.file, rec.record, rec.length)
+            }
+            Some(rec) => format!(
+                "File {} Record {}: {}",
+                rec.file,
+                rec.record,
+                word_list(&rec.values)
+            ),
+            None => format!("FC 0x{:02X}: {}", function, hex_string(data)),
+        },
+        _ => String::new(),
+    }
+}
+
+pub fn response_data(resp: &Response) -> String {
+    match resp {
+        Response::ReadCoils(vals) => bool_list(vals),
+        Response::ReadDiscreteInputs(vals) => bool_list(vals),
+        Response::WriteSingleCoil(addr, val) => format!("{} = {}", addr, u8::from(*val)),
+        Response::WriteMultipleCoils(addr, qty) => format!("{} x{}", addr, qty),
+        Response::ReadInputRegisters(vals) => word_list(vals),
+        Response::ReadHoldingRegisters(vals) => word_list(vals),
+        Response::WriteSingleRegister(addr, val) => format!("{} = {}", addr, val),
+        Response::WriteMultipleRegisters(addr, qty) => format!("{} x{}", addr, qty),
+        Response::MaskWriteRegister(addr, and_mask, or_mask) => {
+            format!("{} AND 0x{:04X} OR 0x{:04X}", addr, and_mask, or_mask)
+        }
+        Response::ReadWriteMultipleRegisters(vals) => word_list(vals),
+        Response::Custom(file_record::READ_FILE_RECORD, _) => {
+            match file_record::parse_read_response(resp) {
+                Ok(groups) => word_list(&groups.concat()),
+                Err(_e) => hex_string(&Bytes::from(resp.clone())),
+            }
+        }
+        Response::Custom(file_record::READ_FIFO_QUEUE, _) => {
+            match file_record::parse_fifo_response(resp) {
+                Ok(vals) => word_list(&vals),
+                Err(_e) => hex_string(&Bytes::from(resp.clone())),
+            }
+        }
+        other => hex_string(&Bytes::from(other.clone())),
+    }
+}
+
+fn bool_list(vals: &[bool]) -> String {
+    vals.iter()
+        .map(|x| u8::from(*x).to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn word_list(vals: &[u16]) -> String {
+    vals.iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Requests are saved in projects through a mirror of tokio-modbus' enum.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum SavedRequest {
+    ReadCoils { address: u16, quantity: u16 },
+    ReadDiscreteInputs { address: u16, quantity: u16 },
+    WriteSingleCoil { address: u16, value: bool },
+    WriteMultipleCoils { address: u16, values: Vec<bool> },
+    ReadInputRegisters { address: u16, quantity: u16 },
+    ReadHoldingRegisters { address: u16, quantity: u16 },
+    WriteSingleRegister { address: u16, value: u16 },
+    WriteMultipleRegisters { address: u16, values: Vec<u16> },
+    MaskWriteRegister { address: u16, and_mask: u16, or_mask: u16 },
+    ReadWriteMultipleRegisters {
+        read_address: u16,
+        quantity: u16,
+        write_address: u16,
+        values: Vec<u16>,
+    },
+    Custom { function: u8, data: Vec<u8> },
+}
+
+impl From<&Request> for SavedRequest {
+    fn from(req: &Request) -> Self {
+        match req.clone() {
+            Request::ReadCoils(address, quantity) => SavedRequest::ReadCoils { address, quantity },
+            Request::ReadDiscreteInputs(address, quantity) => {
+                SavedRequest::ReadDiscreteInputs { address, quantity }
+            }
+            Request::WriteSingleCoil(address, value) => {
+                SavedRequest::WriteSingleCoil { address, value }
+            }
+            Request::WriteMultipleCoils(address, values) => {
+                SavedRequest::WriteMultipleCoils { address, values }
+            }
+            Request::ReadInputRegisters(address, quantity) => {
+                SavedRequest::ReadInputRegisters { address, quantity }
+            }
+            Request::ReadHoldingRegisters(address, quantity) => {
+                SavedRequest::ReadHoldingRegisters { address, quantity }
+            }
+            Request::WriteSingleRegister(address, value) => {
+                SavedRequest::WriteSingleRegister { address, value }
+            }
+            Request::WriteMultipleRegisters(address, values) => {
+                SavedRequest::WriteMultipleRegisters { address, values }
+            }
+            Request::MaskWriteRegister(address, and_mask, or_mask) => {
+                SavedRequest::MaskWriteRegister { address, and_mask, or_mask }
+            }
+            Request::ReadWriteMultipleRegisters(read_address, quantity, write_address, values) => {
+                SavedRequest::ReadWriteMultipleRegisters {
+                    read_address,
+                    quantity,
+                    write_address,
+                    values,
+                }
+            }
+            Request::Custom(function, data) => SavedRequest::Custom { function, data },
+            Request::Disconnect => unreachable!(),
+        }
+    }
+}
+
+impl From<SavedRequest> for Request {
+    fn from(req: SavedRequest) -> Self {
+        match req {
+            SavedRequest::ReadCoils { address, quantity } => Request::ReadCoils(address, quantity),
+            SavedRequest::ReadDiscreteInputs { address, quantity } => {
+                Request::ReadDiscreteInputs(address, quantity)
+            }
+            SavedRequest::WriteSingleCoil { address, value } => {
+                Request::WriteSingleCoil(address, value)
+            }
+            SavedRequest::WriteMultipleCoils { address, values } => {
+                Request::WriteMultipleCoils(address, values)
+            }
+            SavedRequest::ReadInputRegisters { address, quantity } => {
+                Request::ReadInputRegisters(address, quantity)
+            }
+            SavedRequest::ReadHoldingRegisters { address, quantity } => {
+                Request::ReadHoldingRegisters(address, quantity)
+            }
+            SavedRequest::WriteSingleRegister { address, value } => {
+                Request::WriteSingleRegister(address, value)
+            }
+            SavedRequest::WriteMultipleRegisters { address, values } => {
+                Request::WriteMultipleRegisters(address, values)
+            }
+            SavedRequest::MaskWriteRegister { address, and_mask, or_mask } => {
+                Request::MaskWriteRegister(address, and_mask, or_mask)
+            }
+            SavedRequest::ReadWriteMultipleRegisters {
+                read_address,
+                quantity,
+                write_address,
+                values,
+            } => Request::ReadWriteMultipleRegisters(read_address, quantity, write_address, values),
+            SavedRequest::Custom { function, data } => Request::Custom(function, data),
+        }
+    }
+}
+
+pub fn serialize_request<S>(req: &Request, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    SavedRequest::from(req).serialize(serializer)
+}
+
+pub fn deserialize_request<'de, D>(deserializer: D) -> Result<Request, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Request::from(SavedRequest::deserialize(deserializer)?))
+}
@@ -0,0 +1,1092 @@
+use crate::controller::RetryOverride;
+use crate::data_type::{parse_bool, ByteOrder, DataType, DisplayFormat, MAX_ASCII_LENGTH};
+use crate::error::ModbusError;
+use crate::request::{MAX_READ_BITS, MAX_READ_REGISTERS};
+use log::{debug, warn};
+use tokio_modbus::prelude::{Request, Response};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json;
+use std::fs::File;
+use std::io::BufReader;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum TableCommand {
+    AddRegister(Option<usize>),
+    SetAddress(usize, u16),
+    SetType(usize, RegisterType),
+    SetName(usize, String),
+    SetDescription(usize, String),
+    SetValue(usize, String),
+    SetDataType(usize, DataType),
+    SetLength(usize, u8),
+    SetByteOrder(usize, Option<ByteOrder>),
+    SetValueFormat(DisplayFormat),
+    ToggleBit(usize, usize),
+    SetScale(usize, String),
+    SetOffset(usize, String),
+    SetEngUnit(usize, String),
+    SetDecimals(usize, Option<u8>),
+    SetPolling(bool),
+    SetPollInterval(Duration),
+    SetPollGap(u16),
+    SetUnit(usize, Option<u8>),
+    SetTimeout(usize, Option<Duration>),
+    SetRetries(usize, Option<u32>),
+    Delete(usize),
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Table {
+    registers: Vec<Register>,
+    #[serde(default)]
+    value_format: DisplayFormat,
+    #[serde(default = "default_poll_interval")]
+    poll_interval: Duration,
+    // Unused addresses a poll may read over to merge two rows into one request.
+    #[serde(default)]
+    poll_gap: u16,
+    #[serde(skip)]
+    polling: bool,
+}
+
+fn default_poll_interval() -> Duration {
+    Duration::from_millis(1000)
+}
+
+#[derive(Debug, Clone)]
+pub enum RegisterRequest {
+    Read(Register),
+    Write(Register),
+}
+
+impl From<RegisterRequest> for Request {
+    fn from(register: RegisterRequest) -> Request {
+        match register {
+            RegisterRequest::Read(reg) => reg
+                .register_type
+                .read_request(reg.address, reg.register_count()),
+            RegisterRequest::Write(reg) => match reg.register_type {
+                RegisterType::Coil => Request::WriteSingleCoil(reg.address, reg.value[0] != 0),
+                RegisterType::InputRegister if reg.register_count() == 1 => {
+                    Request::WriteSingleRegister(reg.address, reg.value[0])
+                }
+                RegisterType::InputRegister | RegisterType::HoldingRegister => {
+                    Request::WriteMultipleRegisters(reg.address, reg.value)
+                }
+                RegisterType::DiscreteInputs => unreachable!(),
+            },
+        }
+        // Request::MaskWriteRegister(u16, u16, u16),
+        // Request::ReadWriteMultipleRegisters(u16, u16, u16, Vec<u16>),
+        // Request::Custom(u8, Vec<u8>),
+    }
+}
+
+impl Table {
+    pub fn handle_commmand(&mut self, tc: TableCommand, default_order: ByteOrder) {
+        match tc {
+            TableCommand::AddRegister(idx) => {
+                if let Some(index) = idx {
+                    self.registers.insert(index+1, Register::default());
+                } else {
+                    self.registers.push(Register::default());
+                }
+            }
+            TableCommand::SetAddress(idx, addr) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.address = addr;
+                }
+            }
+            TableCommand::SetType(idx, rtype) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.register_type = rtype;
+                    x.fit_value();
+                }
+            }
+            TableCommand::SetName(idx, name) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.name = name;
+                }
+            }
+            TableCommand::SetDescription(idx, desc) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.description = desc;
+                }
+            }
+            TableCommand::SetValue(idx, val) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    if let Ok(value) = x.parse_value(&val, default_order, self.value_format) {
+                        x.value = value;
+                    }
+                    x.edit = Some(val);
+                }
+            }
+            TableCommand::SetDataType(idx, data_type) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.data_type = data_type;
+                    x.fit_value();
+                }
+            }
+            TableCommand::SetLength(idx, length) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.length = length.clamp(1, MAX_ASCII_LENGTH);
+                    x.fit_value();
+                }
+            }
+            TableCommand::SetByteOrder(idx, byte_order) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.byte_order = byte_order;
+                    x.edit = None;
+                }
+            }
+            TableCommand::SetValueFormat(format) => {
+                self.value_format = format;
+                self.registers.iter_mut().for_each(|x| x.edit = None);
+            }
+            TableCommand::ToggleBit(idx, bit) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.toggle_bit(bit, default_order);
+                }
+            }
+            TableCommand::SetScale(idx, scale) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.scale = parse_factor(&scale, x.scale);
+                    x.scale_text = Some(scale);
+                    x.edit = None;
+                }
+            }
+            TableCommand::SetOffset(idx, offset) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.offset = parse_factor(&offset, x.offset);
+                    x.offset_text = Some(offset);
+                    x.edit = None;
+                }
+            }
+            TableCommand::SetEngUnit(idx, eng_unit) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.eng_unit = eng_unit;
+                }
+            }
+            TableCommand::SetDecimals(idx, decimals) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.decimals = decimals;
+                    x.edit = None;
+                }
+            }
+            TableCommand::SetPolling(polling) => {
+                self.polling = polling;
+            }
+            TableCommand::SetPollInterval(interval) => {
+                self.poll_interval = interval.max(Duration::from_millis(10));
+            }
+            TableCommand::SetPollGap(gap) => {
+                self.poll_gap = gap;
+            }
+            TableCommand::SetUnit(idx, unit) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.unit = unit;
+                }
+            }
+            TableCommand::SetTimeout(idx, timeout) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.retry.timeout = timeout;
+                }
+            }
+            TableCommand::SetRetries(idx, retries) => {
+                if let Some(x) = self.registers.get_mut(idx) {
+                    x.retry.retries = retries;
+                }
+            }
+            TableCommand::Delete(idx) => {
+                self.registers.remove(idx);
+            }
+            TableCommand::None => (),
+        }
+    }
+
+    pub fn registers(&self) -> &[Register] {
+        &self.registers
+    }
+
+    pub fn is_polling(&self) -> bool {
+        self.polling
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    pub fn poll_gap(&self) -> u16 {
+        self.poll_gap
+    }
+
+    pub fn value_format(&self) -> DisplayFormat {
+        self.value_format
+    }
+
+    // Reads covering every row. Rows of the same type, unit and retry settings
+    // are merged into one request when at most `poll_gap` unused addresses lie
+    // between them and the result still fits in a single PDU.
+    pub fn poll_requests(&self) -> Vec<(Request, Option<u8>, RetryOverride)> {
+        let mut spans: Vec<(RegisterType, Option<u8>, RetryOverride, u16, u16)> = self
+            .registers
+            .iter()
+            .map(|x| {
+                (
+                    x.register_type,
+                    x.unit,
+                    x.retry,
+                    x.address,
+                    x.register_count(),
+                )
+            })
+            .collect();
+        spans.sort();
+        let mut blocks: Vec<(RegisterType, Option<u8>, RetryOverride, u16, u32)> = Vec::new();
+        for (rtype, unit, retry, addr, count) in spans {
+            let end = u32::from(addr) + u32::from(count);
+            match blocks.last_mut() {
+                Some((block_type, block_unit, block_retry, start, block_end))
+                    if *block_type == rtype
+                        && *block_unit == unit
+                        && *block_retry == retry
+                        && u32::from(addr) <= *block_end + u32::from(self.poll_gap)
+                        && end.max(*block_end) - u32::from(*start)
+                            <= u32::from(rtype.max_read()) =>
+                {
+                    *block_end = end.max(*block_end);
+                }
+                _ => blocks.push((rtype, unit, retry, addr, end)),
+            }
+        }
+        blocks
+            .into_iter()
+            .map(|(rtype, unit, retry, start, end)| {
+                (
+                    rtype.read_request(start, (end - u32::from(start)) as u16),
+                    unit,
+                    retry,
+                )
+            })
+            .collect()
+    }
+
+    // Rows on `unit`, a row without its own unit being on the connection's.
+    fn on_unit(&mut self, unit: u8, default_unit: u8) -> impl Iterator<Item = &mut Register> {
+        self.registers
+            .iter_mut()
+            .filter(move |x| x.unit.unwrap_or(default_unit) == unit)
+    }
+
+    // Hands every register inside a block read its slice of the response.
+    fn apply_words(
+        &mut self,
+        (unit, default_unit): (u8, u8),
+        rtype: RegisterType,
+        start: u16,
+        words: &[u16],
+    ) {
+        self.on_unit(unit, default_unit)
+            .filter(|x| x.register_type == rtype && x.address >= start)
+            .for_each(|x| {
+                let offset = usize::from(x.address - start);
+                let count = usize::from(x.register_count());
+                if offset + count <= words.len() {
+                    x.set_words(&words[offset..offset + count]);
+                }
+            });
+    }
+
+    fn apply_bits(
+        &mut self,
+        (unit, default_unit): (u8, u8),
+        rtype: RegisterType,
+        start: u16,
+        quantity: u16,
+        bits: &[bool],
+    ) {
+        // Bit responses are padded to whole bytes, only the requested ones count.
+        let bits = &bits[..bits.len().min(usize::from(quantity))];
+        self.on_unit(unit, default_unit)
+            .filter(|x| x.register_type == rtype && x.address >= start)
+            .for_each(|x| {
+                if let Some(bit) = bits.get(usize::from(x.address - start)) {
+                    x.set_words(&[u16::from(*bit)]);
+                }
+            });
+    }
+
+    // Applies a confirmed write to every register of `rtype` it overlaps, a
+    // register only partly covered keeps its other words.
+    fn apply_written_words(
+        &mut self,
+        (unit, default_unit): (u8, u8),
+        rtype: RegisterType,
+        start: u16,
+        words: &[u16],
+    ) {
+        let start = u32::from(start);
+        let end = start + words.len() as u32;
+        self.on_unit(unit, default_unit)
+            .filter(|x| x.register_type == rtype)
+            .for_each(|x| {
+                let first = u32::from(x.address);
+                let last = first + u32::from(x.register_count());
+                if first >= end || last <= start {
+                    return;
+                }
+                let mut value = x.value.clone();
+                value.resize(usize::from(x.register_count()), 0);
+                for address in first.max(start)..last.min(end) {
+                    value[(address - first) as usize] = words[(address - start) as usize];
+                }
+                x.set_words(&value);
+            });
+    }
+
+    fn apply_mask(
+        &mut self,
+        (unit, default_unit): (u8, u8),
+        address: u16,
+        and_mask: u16,
+        or_mask: u16,
+    ) {
+        self.on_unit(unit, default_unit)
+            .filter(|x| {
+                x.register_type == RegisterType::HoldingRegister
+                    && x.address <= address
+                    && u32::from(address) < u32::from(x.address) + u32::from(x.register_count())
+            })
+            .for_each(|x| {
+                let mut value = x.value.clone();
+                value.resize(usize::from(x.register_count()), 0);
+                let word = &mut value[usize::from(address - x.address)];
+                *word = (*word & and_mask) | (or_mask & !and_mask);
+                x.set_words(&value);
+            });
+    }
+
+    // Routes a response to every register of the addressed type and unit that
+    // lies in the range the request covered, whichever part of the GUI sent it.
+    // `unit` is the one that answered, rows without their own unit are on
+    // `default_unit`.
+    pub fn handle_response(&mut self, unit: u8, req: Request, resp: Response, default_unit: u8) {
+        let on = (unit, default_unit);
+        match (req, resp) {
+            (Request::ReadCoils(start, qty), Response::ReadCoils(bits)) => {
+                self.apply_bits(on, RegisterType::Coil, start, qty, &bits)
+            }
+            (Request::ReadDiscreteInputs(start, qty), Response::ReadDiscreteInputs(bits)) => {
+                self.apply_bits(on, RegisterType::DiscreteInputs, start, qty, &bits)
+            }
+            (Request::ReadInputRegisters(start, _qty), Response::ReadInputRegisters(words)) => {
+                self.apply_words(on, RegisterType::InputRegister, start, &words)
+            }
+            (Request::ReadHoldingRegisters(start, _qty), Response::ReadHoldingRegisters(words)) => {
+                self.apply_words(on, RegisterType::HoldingRegister, start, &words)
+            }
+            (Request::WriteSingleCoil(_, _), Response::WriteSingleCoil(addr, val)) => {
+                self.apply_bits(on, RegisterType::Coil, addr, 1, &[val])
+            }
+            (Request::WriteMultipleCoils(start, bits), Response::WriteMultipleCoils(_, qty)) => {
+                self.apply_bits(on, RegisterType::Coil, start, qty, &bits)
+            }
+            (Request::WriteSingleRegister(_, _), Response::WriteSingleRegister(addr, val)) => {
+                self.apply_written_words(on, RegisterType::HoldingRegister, addr, &[val])
+            }
+            (
+                Request::WriteMultipleRegisters(start, words),
+                Response::WriteMultipleRegisters(_, _qty),
+            ) => self.apply_written_words(on, RegisterType::HoldingRegister, start, &words),
+            (
+                Request::MaskWriteRegister(_, _, _),
+                Response::MaskWriteRegister(addr, and_mask, or_mask),
+            ) => self.apply_mask(on, addr, and_mask, or_mask),
+            (
+                Request::ReadWriteMultipleRegisters(read_start, _qty, write_start, written),
+                Response::ReadWriteMultipleRegisters(words),
+            ) => {
+                // The device writes before it reads.
+                self.apply_written_words(on, RegisterType::HoldingRegister, write_start, &written);
+                self.apply_words(on, RegisterType::HoldingRegister, read_start, &words)
+            }
+            (req, resp) => debug!("Response not routed to table: {:?} {:?}", req, resp),
+        }
+    }
+
+    // Marks every register on `unit` the failed request covered.
+    pub fn handle_error(&mut self, unit: u8, req: &Request, err: &ModbusError, default_unit: u8) {
+        let (rtype, start, quantity) = match req {
+            Request::ReadCoils(start, qty) => (RegisterType::Coil, *start, *qty),
+            Request::ReadDiscreteInputs(start, qty) => (RegisterType::DiscreteInputs, *start, *qty),
+            Request::ReadInputRegisters(start, qty) => (RegisterType::InputRegister, *start, *qty),
+            Request::ReadHoldingRegisters(start, qty) => {
+                (RegisterType::HoldingRegister, *start, *qty)
+            }
+            Request::WriteSingleCoil(addr, _) => (RegisterType::Coil, *addr, 1),
+            Request::WriteMultipleCoils(start, bits) => {
+                (RegisterType::Coil, *start, bits.len() as u16)
+            }
+            Request::WriteSingleRegister(addr, _) | Request::MaskWriteRegister(addr, _, _) => {
+                (RegisterType::HoldingRegister, *addr, 1)
+            }
+            Request::WriteMultipleRegisters(start, words) => {
+                (RegisterType::HoldingRegister, *start, words.len() as u16)
+            }
+            Request::ReadWriteMultipleRegisters(start, qty, _, _) => {
+                (RegisterType::HoldingRegister, *start, *qty)
+            }
+            _ => return,
+        };
+        let start = u32::from(start);
+        let end = start + u32::from(quantity);
+        self.on_unit(unit, default_unit)
+            .filter(|x| x.register_type == rtype)
+            .filter(|x| {
+                let first = u32::from(x.address);
+                first < end && first + u32::from(x.register_count()) > start
+            })
+            .for_each(|x| x.error = Some(err.to_string()));
+    }
+
+    pub fn load_from_file(path: &std::path::Path) -> Result<Table, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let u = serde_json::from_reader(reader)?;
+        Ok(u)
+    }
+}
+
+impl std::default::Default for Table {
+    fn default() -> Self {
+        Self {
+            registers: Vec::new(),
+            value_format: DisplayFormat::default(),
+            poll_interval: default_poll_interval(),
+            poll_gap: 0,
+            polling: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RegisterType {
+    Coil,
+    DiscreteInputs,
+    InputRegister,
+    HoldingRegister,
+}
+
+impl std::fmt::Display for RegisterType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                RegisterType::Coil => "COIL",
+                RegisterType::DiscreteInputs => "DISCRETE",
+                RegisterType::InputRegister => "INPUT",
+                RegisterType::HoldingRegister => "HOLDING",
+            }
+        )
+    }
+}
+
+impl RegisterType {
+    pub const ALL: [RegisterType; 4] = [
+        RegisterType::Coil,
+        RegisterType::DiscreteInputs,
+        RegisterType::InputRegister,
+        RegisterType::HoldingRegister,
+    ];
+
+    fn max_read(&self) -> u16 {
+        match self {
+            RegisterType::Coil | RegisterType::DiscreteInputs => MAX_READ_BITS,
+            RegisterType::InputRegister | RegisterType::HoldingRegister => MAX_READ_REGISTERS,
+        }
+    }
+
+    fn read_request(&self, address: u16, quantity: u16) -> Request {
+        match self {
+            RegisterType::Coil => Request::ReadCoils(address, quantity),
+            RegisterType::DiscreteInputs => Request::ReadDiscreteInputs(address, quantity),
+            RegisterType::InputRegister => Request::ReadInputRegisters(address, quantity),
+            RegisterType::HoldingRegister => Request::ReadHoldingRegisters(address, quantity),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Register {
+    register_type: RegisterType,
+    address: u16,
+    length: u8,
+    name: String,
+    // Raw register words, most significant first. Older project files store a
+    // single number here.
+    #[serde(deserialize_with = "deserialize_value")]
+    value: Vec<u16>,
+    description: String,
+    #[serde(default)]
+    unit: Option<u8>,
+    #[serde(default)]
+    data_type: DataType,
+    // `None` follows the connection's byte order.
+    #[serde(default)]
+    byte_order: Option<ByteOrder>,
+    // Engineering value = raw * scale + offset, shown with `decimals` places.
+    #[serde(default)]
+    scale: Option<f64>,
+    #[serde(default)]
+    offset: Option<f64>,
+    #[serde(default)]
+    eng_unit: String,
+    #[serde(default)]
+    decimals: Option<u8>,
+    // Unset fields follow the connection's retry policy.
+    #[serde(default)]
+    retry: RetryOverride,
+    #[serde(skip)]
+    edit: Option<String>,
+    #[serde(skip)]
+    scale_text: Option<String>,
+    #[serde(skip)]
+    offset_text: Option<String>,
+    // Why the last request touching this register failed, until one succeeds.
+    #[serde(skip)]
+    error: Option<String>,
+}
+
+// Empty clears the factor, unparsable text keeps the previous one while typing.
+fn parse_factor(s: &str, current: Option<f64>) -> Option<f64> {
+    if s.trim().is_empty() {
+        return None;
+    }
+    s.trim().parse::<f64>().ok().or(current)
+}
+
+fn deserialize_value<'de, D>(deserializer: D) -> Result<Vec<u16>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Single(u16),
+        Words(Vec<u16>),
+    }
+    match Value::deserialize(deserializer)? {
+        Value::Single(x) => Ok(vec![x]),
+        Value::Words(x) if x.is_empty() => Ok(vec![0]),
+        Value::Words(x) => Ok(x),
+    }
+}
+
+impl Register {
+    // Coils and discrete inputs are single bits whatever the data type says.
+    pub fn register_count(&self) -> u16 {
+        match self.register_type {
+            RegisterType::Coil | RegisterType::DiscreteInputs => 1,
+            RegisterType::InputRegister | RegisterType::HoldingRegister => {
+                self.data_type.register_count(self.length)
+            }
+        }
+    }
+
+    // Scaling only applies to numeric registers shown as numbers, hex and bit
+    // views always show the raw value.
+    fn shows_scaled(&self, format: DisplayFormat) -> bool {
+        let numeric = matches!(
+            format,
+            DisplayFormat::Decimal | DisplayFormat::Signed | DisplayFormat::Float
+        );
+        let words = matches!(
+            self.register_type,
+            RegisterType::InputRegister | RegisterType::HoldingRegister
+        );
+        numeric && words && (self.scale.is_some() || self.offset.is_some())
+    }
+
+    pub fn engineering_value(&self, default_order: ByteOrder) -> Option<f64> {
+        let raw = match self.register_type {
+            RegisterType::Coil | RegisterType::DiscreteInputs => f64::from(self.value[0]),
+            RegisterType::InputRegister | RegisterType::HoldingRegister => self
+                .data_type
+                .to_f64(&self.value, self.byte_order.unwrap_or(default_order))?,
+        };
+        Some(raw * self.scale.unwrap_or(1.0) + self.offset.unwrap_or(0.0))
+    }
+
+    pub fn value_text(&self, default_order: ByteOrder, format: DisplayFormat) -> String {
+        if self.shows_scaled(format) {
+            if let Some(value) = self.engineering_value(default_order) {
+                return match self.decimals {
+                    Some(decimals) => format!("{:.*}", usize::from(decimals), value),
+                    None => value.to_string(),
+                };
+            }
+        }
+        match self.register_type {
+            RegisterType::Coil | RegisterType::DiscreteInputs => self.value[0].to_string(),
+            RegisterType::InputRegister | RegisterType::HoldingRegister => self.data_type.format(
+                &self.value,
+                self.byte_order.unwrap_or(default_order),
+                format,
+            ),
+        }
+    }
+
+    // The value as read from the device, ignoring scale and offset.
+    pub fn raw_text(&self, default_order: ByteOrder) -> String {
+        match self.register_type {
+            RegisterType::Coil | RegisterType::DiscreteInputs => self.value[0].to_string(),
+            RegisterType::InputRegister | RegisterType::HoldingRegister => self.data_type.format(
+                &self.value,
+                self.byte_order.unwrap_or(default_order),
+                DisplayFormat::Decimal,
+            ),
+        }
+    }
+
+    pub fn bits(&self, default_order: ByteOrder) -> Vec<bool> {
+        match self.register_type {
+            RegisterType::Coil | RegisterType::DiscreteInputs => vec![self.value[0] != 0],
+            RegisterType::InputRegister | RegisterType::HoldingRegister => self
+                .data_type
+                .bits(&self.value, self.byte_order.unwrap_or(default_order)),
+        }
+    }
+
+    fn toggle_bit(&mut self, bit: usize, default_order: ByteOrder) {
+        self.value = match self.register_type {
+            RegisterType::Coil | RegisterType::DiscreteInputs => vec![u16::from(self.value[0] == 0)],
+            RegisterType::InputRegister | RegisterType::HoldingRegister => self.data_type.toggle_bit(
+                &self.value,
+                self.byte_order.unwrap_or(default_order),
+                bit,
+            ),
+        };
+        self.edit = None;
+    }
+
+    // Converts typed text to raw registers. Engineering values are scaled back
+    // and range checked against the data type.
+    fn parse_value(
+        &self,
+        s: &str,
+        default_order: ByteOrder,
+        format: DisplayFormat,
+    ) -> Result<Vec<u16>, String> {
+        let order = self.byte_order.unwrap_or(default_order);
+        if self.shows_scaled(format) && self.data_type.to_f64(&self.value, order).is_some() {
+            let value = s
+                .trim()
+                .parse::<f64>()
+                .map_err(|_e| format!("{} is not a number", s))?;
+            let scale = self.scale.unwrap_or(1.0);
+            if scale == 0.0 {
+                return Err("Scale is zero".to_string());
+            }
+            return self
+                .data_type
+                .from_f64((value - self.offset.unwrap_or(0.0)) / scale, order);
+        }
+        match self.register_type {
+            RegisterType::Coil | RegisterType::DiscreteInputs => {
+                parse_bool(s).map(|x| vec![u16::from(x)])
+            }
+            RegisterType::InputRegister | RegisterType::HoldingRegister => {
+                self.data_type.encode(s, self.length, order, format)
+            }
+        }
+        .ok_or_else(|| format!("{} is not a valid {} value", s, self.data_type))
+    }
+
+    // Why the value being typed can not be written, if it can not.
+    pub fn edit_error(&self, default_order: ByteOrder, format: DisplayFormat) -> Option<String> {
+        self.parse_value(self.edit.as_ref()?, default_order, format)
+            .err()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    // Text being typed into the value field, or the value itself.
+    pub fn value_edit(&self, default_order: ByteOrder, format: DisplayFormat) -> String {
+        self.edit
+            .clone()
+            .unwrap_or_else(|| self.value_text(default_order, format))
+    }
+
+    pub fn scale_text(&self) -> String {
+        self.scale_text
+            .clone()
+            .unwrap_or_else(|| self.scale.map(|x| x.to_string()).unwrap_or_default())
+    }
+
+    pub fn offset_text(&self) -> String {
+        self.offset_text
+            .clone()
+            .unwrap_or_else(|| self.offset.map(|x| x.to_string()).unwrap_or_default())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn data_type(&self) -> DataType {
+        self.data_type
+    }
+
+    pub fn length(&self) -> u8 {
+        self.length.clamp(1, MAX_ASCII_LENGTH)
+    }
+
+    pub fn byte_order(&self) -> Option<ByteOrder> {
+        self.byte_order
+    }
+
+    pub fn eng_unit(&self) -> &str {
+        &self.eng_unit
+    }
+
+    pub fn decimals(&self) -> Option<u8> {
+        self.decimals
+    }
+
+    pub fn register_type(&self) -> RegisterType {
+        self.register_type
+    }
+
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    // Raw words as sent on the wire, a single 0/1 word for bits.
+    pub fn words(&self) -> &[u16] {
+        &self.value
+    }
+
+    pub fn unit(&self) -> Option<u8> {
+        self.unit
+    }
+
+    pub fn retry(&self) -> RetryOverride {
+        self.retry
+    }
+
+    pub fn read_request(&self) -> Request {
+        RegisterRequest::Read(self.clone()).into()
+    }
+
+    // Copy of the register holding the values of a read response.
+    pub fn with_response(&self, resp: Response) -> Result<Register, String> {
+        let mut reg = self.clone();
+        match resp {
+            Response::ReadCoils(bits) | Response::ReadDiscreteInputs(bits) if !bits.is_empty() => {
+                reg.value = vec![u16::from(bits[0])];
+            }
+            Response::ReadInputRegisters(words) | Response::ReadHoldingRegisters(words)
+                if words.len() >= usize::from(reg.register_count()) =>
+            {
+                reg.set_words(&words);
+            }
+            other => return Err(format!("Unexpected response {:?}", other)),
+        }
+        Ok(reg)
+    }
+
+    // Request writing an engineering value, scaled back to raw registers.
+    pub fn write_request(&self, value: f64, default_order: ByteOrder) -> Result<Request, String> {
+        let mut reg = self.clone();
+        reg.value = match self.register_type {
+            RegisterType::Coil => vec![u16::from(value != 0.0)],
+            RegisterType::DiscreteInputs => {
+                return Err(format!("{} is a discrete input", self.name))
+            }
+            RegisterType::InputRegister | RegisterType::HoldingRegister => {
+                let scale = self.scale.unwrap_or(1.0);
+                if scale == 0.0 {
+                    return Err("Scale is zero".to_string());
+                }
+                self.data_type.from_f64(
+                    (value - self.offset.unwrap_or(0.0)) / scale,
+                    self.byte_order.unwrap_or(default_order),
+                )?
+            }
+        };
+        Ok(RegisterRequest::Write(reg).into())
+    }
+
+    fn fit_value(&mut self) {
+        self.value.resize(usize::from(self.register_count()), 0);
+        self.edit = None;
+    }
+
+    fn set_words(&mut self, words: &[u16]) {
+        let count = usize::from(self.register_count());
+        if words.len() >= count {
+            self.value = words[..count].to_vec();
+            self.edit = None;
+            self.error = None;
+        } else {
+            warn!("Short response: {} of {} registers", words.len(), count);
+        }
+    }
+}
+
+impl std::default::Default for Register {
+    fn default() -> Self {
+        Self {
+            register_type: RegisterType::Coil,
+            address: 0,
+            length: 1,
+            name: "".to_string(),
+            value: vec![0],
+            description: "".to_string(),
+            unit: None,
+            data_type: DataType::default(),
+            byte_order: None,
+            scale: None,
+            offset: None,
+            eng_unit: "".to_string(),
+            decimals: None,
+            edit: None,
+            scale_text: None,
+            offset_text: None,
+            retry: RetryOverride::default(),
+            error: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register(register_type: RegisterType, address: u16, unit: Option<u8>) -> Register {
+        Register {
+            register_type,
+            address,
+            unit,
+            ..Register::default()
+        }
+    }
+
+    fn scaled(value: u16) -> Register {
+        Register {
+            value: vec![value],
+            scale: Some(0.1),
+            offset: Some(-40.0),
+            decimals: Some(1),
+            ..register(RegisterType::HoldingRegister, 0, None)
+        }
+    }
+
+    #[test]
+    fn numbers_show_scaled_and_bits_show_raw() {
+        let reg = scaled(653);
+        let order = ByteOrder::Abcd;
+        assert_eq!(reg.value_text(order, DisplayFormat::Decimal), "25.3");
+        assert_eq!(reg.value_text(order, DisplayFormat::Hex), "0x028D");
+        assert_eq!(reg.raw_text(order), "653");
+        let value = reg.engineering_value(order).unwrap();
+        assert!((value - 25.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn typed_values_are_scaled_back_and_range_checked() {
+        let reg = scaled(0);
+        let order = ByteOrder::Abcd;
+        assert_eq!(reg.parse_value("25.3", order, DisplayFormat::Decimal), Ok(vec![653]));
+        assert!(reg.parse_value("-50", order, DisplayFormat::Decimal).is_err());
+        assert_eq!(
+            reg.write_request(25.3, order),
+            Ok(Request::WriteMultipleRegisters(0, vec![653]))
+        );
+        let zero = Register {
+            scale: Some(0.0),
+            ..scaled(0)
+        };
+        assert!(zero.write_request(1.0, order).is_err());
+    }
+
+    fn table(registers: Vec<Register>) -> Table {
+        Table {
+            registers,
+            ..Table::default()
+        }
+    }
+
+    fn requests(table: &Table) -> Vec<(Request, Option<u8>)> {
+        table
+            .poll_requests()
+            .into_iter()
+            .map(|(req, unit, _retry)| (req, unit))
+            .collect()
+    }
+
+    #[test]
+    fn poll_merges_neighbours_of_the_same_kind() {
+        let float = Register {
+            data_type: DataType::F32,
+            ..register(RegisterType::HoldingRegister, 2, None)
+        };
+        let mut table = table(vec![
+            register(RegisterType::HoldingRegister, 0, None),
+            float,
+            register(RegisterType::HoldingRegister, 6, None),
+            register(RegisterType::HoldingRegister, 1, Some(2)),
+            register(RegisterType::Coil, 5, None),
+            register(RegisterType::Coil, 4, None),
+        ]);
+        assert_eq!(
+            requests(&table),
+            vec![
+                (Request::ReadCoils(4, 2), None),
+                (Request::ReadHoldingRegisters(0, 1), None),
+                (Request::ReadHoldingRegisters(2, 2), None),
+                (Request::ReadHoldingRegisters(6, 1), None),
+                (Request::ReadHoldingRegisters(1, 1), Some(2)),
+            ]
+        );
+        table.poll_gap = 2;
+        assert_eq!(
+            requests(&table),
+            vec![
+                (Request::ReadCoils(4, 2), None),
+                (Request::ReadHoldingRegisters(0, 7), None),
+                (Request::ReadHoldingRegisters(1, 1), Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn poll_keeps_rows_with_their_own_retries_apart() {
+        let patient = Register {
+            retry: RetryOverride {
+                timeout: Some(Duration::from_secs(5)),
+                retries: None,
+            },
+            ..register(RegisterType::InputRegister, 1, None)
+        };
+        let table = table(vec![register(RegisterType::InputRegister, 0, None), patient]);
+        assert_eq!(table.poll_requests().len(), 2);
+    }
+
+    #[test]
+    fn poll_splits_blocks_at_the_pdu_limit() {
+        let mut table = table(vec![
+            register(RegisterType::InputRegister, 0, None),
+            register(RegisterType::InputRegister, 124, None),
+            register(RegisterType::InputRegister, 125, None),
+            register(RegisterType::DiscreteInputs, 0, None),
+            register(RegisterType::DiscreteInputs, 1999, None),
+            register(RegisterType::DiscreteInputs, 2000, None),
+        ]);
+        table.poll_gap = 2000;
+        assert_eq!(
+            requests(&table),
+            vec![
+                (Request::ReadDiscreteInputs(0, 2000), None),
+                (Request::ReadDiscreteInputs(2000, 1), None),
+                (Request::ReadInputRegisters(0, 125), None),
+                (Request::ReadInputRegisters(125, 1), None),
+            ]
+        );
+    }
+
+    fn words(table: &Table) -> Vec<&[u16]> {
+        table.registers().iter().map(|x| x.words()).collect()
+    }
+
+    #[test]
+    fn responses_hand_each_row_its_slice() {
+        let wide = Register {
+            data_type: DataType::U32,
+            ..register(RegisterType::HoldingRegister, 11, None)
+        };
+        let mut table = table(vec![
+            register(RegisterType::HoldingRegister, 10, None),
+            wide,
+            register(RegisterType::HoldingRegister, 14, None),
+            register(RegisterType::InputRegister, 10, None),
+        ]);
+        table.handle_response(
+            1,
+            Request::ReadHoldingRegisters(10, 4),
+            Response::ReadHoldingRegisters(vec![1, 2, 3, 4]),
+            1,
+        );
+        assert_eq!(words(&table), vec![&[1][..], &[2, 3][..], &[0][..], &[0][..]]);
+    }
+
+    #[test]
+    fn bit_padding_is_ignored() {
+        let mut table = table(vec![
+            register(RegisterType::Coil, 0, None),
+            register(RegisterType::Coil, 2, None),
+        ]);
+        table.handle_response(
+            1,
+            Request::ReadCoils(0, 2),
+            Response::ReadCoils(vec![true, false, true, false, false, false, false, false]),
+            1,
+        );
+        assert_eq!(words(&table), vec![&[1][..], &[0][..]]);
+    }
+
+    #[test]
+    fn responses_only_reach_rows_of_the_unit_that_answered() {
+        let mut table = table(vec![
+            register(RegisterType::Coil, 0, None),
+            register(RegisterType::Coil, 0, Some(2)),
+            register(RegisterType::Coil, 1, Some(1)),
+        ]);
+        table.handle_response(
+            1,
+            Request::ReadCoils(0, 2),
+            Response::ReadCoils(vec![true, true, false, false, false, false, false, false]),
+            1,
+        );
+        assert_eq!(words(&table), vec![&[1][..], &[0][..], &[1][..]]);
+
+        let err = ModbusError::Timeout("no answer".to_string());
+        table.handle_error(2, &Request::ReadCoils(0, 2), &err, 1);
+        let errors: Vec<bool> = table.registers().iter().map(|x| x.error().is_some()).collect();
+        assert_eq!(errors, vec![false, true, false]);
+    }
+
+    #[test]
+    fn writes_update_overlapped_words_only() {
+        let wide = Register {
+            data_type: DataType::U32,
+            value: vec![7, 8],
+            ..register(RegisterType::HoldingRegister, 0, None)
+        };
+        let mut table = table(vec![wide]);
+        table.handle_response(
+            1,
+            Request::WriteSingleRegister(1, 9),
+            Response::WriteSingleRegister(1, 9),
+            1,
+        );
+        assert_eq!(table.registers()[0].words(), &[7, 9]);
+        table.handle_response(
+            1,
+            Request::MaskWriteRegister(0, 0x00F2, 0x0025),
+            Response::MaskWriteRegister(0, 0x00F2, 0x0025),
+            1,
+        );
+        assert_eq!(table.registers()[0].words(), &[0x0007, 9]);
+    }
+}
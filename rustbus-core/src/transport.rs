@@ -0,0 +1,251 @@
+use crate::error::{ExceptionCode, ModbusError};
+use bytes::Bytes;
+use chrono::{DateTime, Local};
+use std::io::{Error, ErrorKind};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_modbus::prelude::Response;
+
+pub mod rtu;
+pub mod tcp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Tx,
+    Rx,
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Direction::Tx => "Tx",
+                Direction::Rx => "Rx",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RawFrame {
+    pub timestamp: DateTime<Local>,
+    pub direction: Direction,
+    pub data: Vec<u8>,
+}
+
+impl RawFrame {
+    pub fn hex(&self) -> String {
+        hex_string(&self.data)
+    }
+}
+
+pub fn hex_string(data: &[u8]) -> String {
+    data.iter()
+        .map(|x| format!("{:02X}", x))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Parses "01 0A ff" or "010aff" into bytes, `None` on odd digit counts or
+// anything that is not hex.
+pub fn parse_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    let digits: String = s.chars().filter(|x| !x.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) || !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn decode_response_pdu(pdu: Vec<u8>) -> Result<Response, Error> {
+    match pdu.first() {
+        Some(function) if function & 0x80 != 0 => {
+            let code = pdu.get(1).copied().unwrap_or_default();
+            Err(ModbusError::Exception {
+                function: function & 0x7F,
+                code: ExceptionCode::from(code),
+            }
+            .into())
+        }
+        Some(_) => Response::try_from(Bytes::from(pdu)),
+        None => Err(Error::new(ErrorKind::InvalidData, "Empty response")),
+    }
+}
+
+#[derive(Debug, Default)]
+struct TapBuffer {
+    tx: Vec<u8>,
+    tx_started: Option<DateTime<Local>>,
+    rx: Vec<u8>,
+    rx_started: Option<DateTime<Local>>,
+    frames: Vec<RawFrame>,
+}
+
+impl TapBuffer {
+    fn finish_tx(&mut self) {
+        if let Some(timestamp) = self.tx_started.take() {
+            self.frames.push(RawFrame {
+                timestamp,
+                direction: Direction::Tx,
+                data: std::mem::take(&mut self.tx),
+            });
+        }
+    }
+
+    fn finish_rx(&mut self) {
+        if let Some(timestamp) = self.rx_started.take() {
+            self.frames.push(RawFrame {
+                timestamp,
+                direction: Direction::Rx,
+                data: std::mem::take(&mut self.rx),
+            });
+        }
+    }
+}
+
+// Shared view of everything that crossed a tapped transport. The worker keeps
+// one handle and drains it after each transaction while the transport owns the
+// other.
+#[derive(Debug, Clone, Default)]
+pub struct TapHandle(Arc<Mutex<TapBuffer>>);
+
+impl TapHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn take(&self) -> Vec<RawFrame> {
+        let mut buffer = self.0.lock().unwrap();
+        buffer.finish_tx();
+        buffer.finish_rx();
+        std::mem::take(&mut buffer.frames)
+    }
+
+    fn record_tx(&self, data: &[u8]) {
+        let mut buffer = self.0.lock().unwrap();
+        buffer.finish_rx();
+        if buffer.tx_started.is_none() {
+            buffer.tx_started = Some(Local::now());
+        }
+        buffer.tx.extend_from_slice(data);
+    }
+
+    fn record_rx(&self, data: &[u8]) {
+        let mut buffer = self.0.lock().unwrap();
+        buffer.finish_tx();
+        if buffer.rx_started.is_none() {
+            buffer.rx_started = Some(Local::now());
+        }
+        buffer.rx.extend_from_slice(data);
+    }
+
+    fn flush_tx(&self) {
+        self.0.lock().unwrap().finish_tx();
+    }
+}
+
+// Transport wrapper that copies every byte written to or read from `inner`
+// into a `TapHandle`. A write followed by a flush is recorded as one Tx frame,
+// consecutive reads are gathered into one Rx frame.
+#[derive(Debug)]
+pub struct Tap<T> {
+    inner: T,
+    handle: TapHandle,
+}
+
+impl<T> Tap<T> {
+    pub fn new(inner: T, handle: TapHandle) -> Self {
+        Self { inner, handle }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Tap<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let res = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = res {
+            let read = &buf.filled()[before..];
+            if !read.is_empty() {
+                self.handle.record_rx(read);
+            }
+        }
+        res
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Tap<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = res {
+            self.handle.record_tx(&buf[..written]);
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let res = Pin::new(&mut self.inner).poll_flush(cx);
+        if let Poll::Ready(Ok(())) = res {
+            self.handle.flush_tx();
+        }
+        res
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_pdu_decodes() {
+        assert_eq!(
+            decode_response_pdu(vec![0x06, 0x00, 0x01, 0x00, 0x03]).unwrap(),
+            Response::WriteSingleRegister(1, 3)
+        );
+        assert!(decode_response_pdu(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn parse_hex_bytes_accepts_spaced_and_packed() {
+        assert_eq!(parse_hex_bytes("01 0A ff"), Some(vec![0x01, 0x0A, 0xFF]));
+        assert_eq!(parse_hex_bytes("010aff"), Some(vec![0x01, 0x0A, 0xFF]));
+        assert_eq!(parse_hex_bytes(" "), Some(vec![]));
+    }
+
+    #[test]
+    fn parse_hex_bytes_rejects_odd_and_invalid() {
+        assert_eq!(parse_hex_bytes("010"), None);
+        assert_eq!(parse_hex_bytes("0 1 0"), None);
+        assert_eq!(parse_hex_bytes("zz"), None);
+        assert_eq!(parse_hex_bytes("+1"), None);
+    }
+
+    #[test]
+    fn exception_pdu_becomes_modbus_error() {
+        let err = decode_response_pdu(vec![0x83, 0x02]).unwrap_err();
+        assert_eq!(
+            ModbusError::from(&err),
+            ModbusError::Exception {
+                function: 0x03,
+                code: ExceptionCode::IllegalDataAddress,
+            }
+        );
+    }
+}
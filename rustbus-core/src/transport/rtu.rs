@@ -0,0 +1,254 @@
+use crate::transport::decode_response_pdu;
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_modbus::client::{Client, Context};
+use tokio_modbus::prelude::{Request, Response, Slave, SlaveContext};
+
+// A USB serial adapter may hand a single frame over in several chunks, so the
+// silence used to detect the end of a frame of unknown length is kept well
+// above the 3.5 character times the spec asks for.
+const MIN_FRAME_GAP: Duration = Duration::from_millis(20);
+
+// Modbus RTU client that frames responses itself. Responses whose length can
+// not be derived from the function code (vendor specific codes, diagnostics
+// echoes, device identification) are delimited by line silence, which the
+// tokio-modbus codec does not support.
+#[derive(Debug)]
+pub struct RtuClient<T> {
+    transport: T,
+    slave_id: u8,
+    frame_gap: Duration,
+}
+
+pub fn attach_slave<T>(transport: T, slave: Slave, baudrate: u32) -> Context
+where
+    T: AsyncRead + AsyncWrite + std::fmt::Debug + Unpin + Send + 'static,
+{
+    let client: Box<dyn Client> = Box::new(RtuClient {
+        transport,
+        slave_id: slave.into(),
+        frame_gap: frame_gap(baudrate),
+    });
+    Context::from(client)
+}
+
+// 3.5 character times of 11 bits, fixed at 1.75 ms above 19200 baud.
+pub fn frame_gap(baudrate: u32) -> Duration {
+    let t35 = if baudrate > 19200 {
+        Duration::from_micros(1750)
+    } else {
+        Duration::from_micros(38_500_000 / u64::from(baudrate.max(1)))
+    };
+    t35.max(MIN_FRAME_GAP)
+}
+
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for x in data {
+        crc ^= u16::from(*x);
+        for _ in 0..8 {
+            if crc & 0x0001 != 0 {
+                crc = (crc >> 1) ^ 0xA001;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    crc
+}
+
+pub fn encode_adu(slave_id: u8, pdu: &[u8]) -> Vec<u8> {
+    let mut adu = Vec::with_capacity(pdu.len() + 3);
+    adu.push(slave_id);
+    adu.extend_from_slice(pdu);
+    let crc = crc16(&adu);
+    adu.extend_from_slice(&crc.to_le_bytes());
+    adu
+}
+
+// Splits a complete ADU into (slave id, pdu) after checking its CRC.
+pub fn decode_adu(adu: &[u8]) -> Result<(u8, Vec<u8>), Error> {
+    if adu.len() < 4 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Frame too short: {:02X?}", adu),
+        ));
+    }
+    let (data, crc) = adu.split_at(adu.len() - 2);
+    let expected = crc16(data);
+    let actual = u16::from_le_bytes([crc[0], crc[1]]);
+    if expected != actual {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "CRC error: expected 0x{:04X}, received 0x{:04X}",
+                expected, actual
+            ),
+        ));
+    }
+    Ok((data[0], data[1..].to_vec()))
+}
+
+// Length of the response ADU in `buf` if it can be told from the bytes received
+// so far, `None` if the frame has to be delimited by silence instead.
+fn expected_response_len(buf: &[u8]) -> Option<usize> {
+    let function = *buf.get(1)?;
+    let pdu_len = match function {
+        0x01..=0x04 | 0x0C | 0x11 | 0x14 | 0x15 | 0x17 => 2 + usize::from(*buf.get(2)?),
+        0x05 | 0x06 | 0x0B | 0x0F | 0x10 => 5,
+        0x07 => 2,
+        0x16 => 7,
+        0x18 => 3 + usize::from(u16::from_be_bytes([*buf.get(2)?, *buf.get(3)?])),
+        x if x & 0x80 != 0 => 2,
+        _ => return None,
+    };
+    Some(pdu_len + 3)
+}
+
+impl<T> RtuClient<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    async fn read_frame(&mut self) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 256];
+        loop {
+            let read = if buf.is_empty() {
+                self.transport.read(&mut chunk).await?
+            } else {
+                match tokio::time::timeout(self.frame_gap, self.transport.read(&mut chunk)).await {
+                    Ok(read) => read?,
+                    Err(_silence) => return Ok(buf),
+                }
+            };
+            if read == 0 {
+                return Err(Error::from(ErrorKind::UnexpectedEof));
+            }
+            buf.extend_from_slice(&chunk[..read]);
+            if let Some(len) = expected_response_len(&buf) {
+                if buf.len() >= len {
+                    buf.truncate(len);
+                    return Ok(buf);
+                }
+            }
+        }
+    }
+
+    // Throws away whatever is already buffered, e.g. the late answer to a
+    // request that timed out, so it is not taken for the next response.
+    async fn discard_pending(&mut self) -> Result<(), Error> {
+        let mut chunk = [0u8; 256];
+        loop {
+            match tokio::time::timeout(Duration::ZERO, self.transport.read(&mut chunk)).await {
+                Ok(Ok(0)) => return Err(Error::from(ErrorKind::UnexpectedEof)),
+                Ok(Ok(_read)) => (),
+                Ok(Err(e)) => return Err(e),
+                Err(_empty) => return Ok(()),
+            }
+        }
+    }
+
+    async fn transact(&mut self, req: Request) -> Result<Response, Error> {
+        if req == Request::Disconnect {
+            self.transport.shutdown().await?;
+            return Err(Error::from(ErrorKind::NotConnected));
+        }
+        self.discard_pending().await?;
+        let pdu: Bytes = req.into();
+        let adu = encode_adu(self.slave_id, &pdu);
+        self.transport.write_all(&adu).await?;
+        self.transport.flush().await?;
+        let frame = self.read_frame().await?;
+        let (slave_id, rsp_pdu) = decode_adu(&frame)?;
+        if slave_id != self.slave_id {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid response slave: expected {}, actual {}",
+                    self.slave_id, slave_id
+                ),
+            ));
+        }
+        decode_response_pdu(rsp_pdu)
+    }
+}
+
+impl<T> SlaveContext for RtuClient<T> {
+    fn set_slave(&mut self, slave: Slave) {
+        self.slave_id = slave.into();
+    }
+}
+
+#[async_trait]
+impl<T> Client for RtuClient<T>
+where
+    T: AsyncRead + AsyncWrite + std::fmt::Debug + Unpin + Send,
+{
+    async fn call(&mut self, req: Request) -> Result<Response, Error> {
+        self.transact(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    #[test]
+    fn crc16_matches_spec_example() {
+        assert_eq!(crc16(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x0A]), 0xCDC5);
+        assert_eq!(
+            encode_adu(0x01, &[0x03, 0x00, 0x00, 0x00, 0x0A]),
+            vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x0A, 0xC5, 0xCD]
+        );
+    }
+
+    #[test]
+    fn decode_adu_splits_slave_and_pdu() {
+        let adu = encode_adu(0x11, &[0x03, 0x02, 0x12, 0x34]);
+        let (slave_id, pdu) = decode_adu(&adu).unwrap();
+        assert_eq!(slave_id, 0x11);
+        assert_eq!(pdu, vec![0x03, 0x02, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn decode_adu_rejects_bad_frames() {
+        let mut adu = encode_adu(0x01, &[0x03, 0x00, 0x00, 0x00, 0x0A]);
+        adu[2] ^= 0xFF;
+        assert_eq!(decode_adu(&adu).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            decode_adu(&[0x01, 0x03, 0x00]).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn response_length_from_header() {
+        assert_eq!(expected_response_len(&[0x01, 0x03, 0x04]), Some(9));
+        assert_eq!(expected_response_len(&[0x01, 0x06]), Some(8));
+        assert_eq!(expected_response_len(&[0x01, 0x83]), Some(5));
+        assert_eq!(expected_response_len(&[0x01, 0x03]), None);
+        assert_eq!(expected_response_len(&[0x01, 0x2B, 0x0E]), None);
+    }
+
+    // Vendor function codes carry no length, the answer ends with the line
+    // going quiet.
+    #[tokio::test]
+    async fn custom_response_ends_at_silence() {
+        let (client, mut device) = tokio::io::duplex(64);
+        let mut ctx = attach_slave(client, Slave(0x01), 115200);
+        let answer = tokio::spawn(async move {
+            let mut request = [0u8; 6];
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, encode_adu(0x01, &[0x41, 0xAA, 0x55])[..]);
+            device.write_all(&encode_adu(0x01, &[0x41, 0x01, 0x02, 0x03])).await.unwrap();
+            device
+        });
+        let resp = ctx.call(Request::Custom(0x41, vec![0xAA, 0x55])).await.unwrap();
+        assert_eq!(resp, Response::Custom(0x41, vec![0x01, 0x02, 0x03]));
+        answer.await.unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use crate::transport::decode_response_pdu;
+use async_trait::async_trait;
+use bytes::Bytes;
+use log::debug;
+use std::io::{Error, ErrorKind};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_modbus::client::{Client, Context};
+use tokio_modbus::prelude::{Request, Response, Slave, SlaveContext};
+
+const MBAP_HEADER_LEN: usize = 7;
+const PROTOCOL_ID: u16 = 0;
+
+// Modbus TCP client that frames requests itself so it can run over any
+// transport, in particular a `Tap`. tokio-modbus only offers TCP clients that
+// open their own `TcpStream`.
+#[derive(Debug)]
+pub struct TcpClient<T> {
+    transport: T,
+    unit_id: u8,
+    transaction_id: u16,
+}
+
+pub fn attach_slave<T>(transport: T, slave: Slave) -> Context
+where
+    T: AsyncRead + AsyncWrite + std::fmt::Debug + Unpin + Send + 'static,
+{
+    let client: Box<dyn Client> = Box::new(TcpClient {
+        transport,
+        unit_id: slave.into(),
+        transaction_id: 0,
+    });
+    Context::from(client)
+}
+
+pub fn encode_adu(transaction_id: u16, unit_id: u8, pdu: &[u8]) -> Vec<u8> {
+    let mut adu = Vec::with_capacity(MBAP_HEADER_LEN + pdu.len());
+    adu.extend_from_slice(&transaction_id.to_be_bytes());
+    adu.extend_from_slice(&PROTOCOL_ID.to_be_bytes());
+    adu.extend_from_slice(&(pdu.len() as u16 + 1).to_be_bytes());
+    adu.push(unit_id);
+    adu.extend_from_slice(pdu);
+    adu
+}
+
+// Reads one ADU and returns its (transaction id, unit id, pdu).
+pub async fn read_adu<T>(transport: &mut T) -> Result<(u16, u8, Vec<u8>), Error>
+where
+    T: AsyncRead + Unpin,
+{
+    let mut header = [0u8; MBAP_HEADER_LEN];
+    transport.read_exact(&mut header).await?;
+    let transaction_id = u16::from_be_bytes([header[0], header[1]]);
+    let protocol_id = u16::from_be_bytes([header[2], header[3]]);
+    let len = u16::from_be_bytes([header[4], header[5]]);
+    if protocol_id != PROTOCOL_ID || len < 2 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid MBAP header: {:02X?}", header),
+        ));
+    }
+    let mut pdu = vec![0u8; usize::from(len) - 1];
+    transport.read_exact(&mut pdu).await?;
+    Ok((transaction_id, header[6], pdu))
+}
+
+impl<T> TcpClient<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    async fn transact(&mut self, req: Request) -> Result<Response, Error> {
+        if req == Request::Disconnect {
+            self.transport.shutdown().await?;
+            return Err(Error::from(ErrorKind::NotConnected));
+        }
+        let transaction_id = self.transaction_id;
+        self.transaction_id = self.transaction_id.wrapping_add(1);
+        let pdu: Bytes = req.into();
+        let adu = encode_adu(transaction_id, self.unit_id, &pdu);
+        self.transport.write_all(&adu).await?;
+        self.transport.flush().await?;
+        let (rsp_transaction_id, rsp_unit_id, rsp_pdu) = loop {
+            let (rsp_transaction_id, rsp_unit_id, rsp_pdu) = read_adu(&mut self.transport).await?;
+            // A late answer to a request that already timed out.
+            let behind = transaction_id.wrapping_sub(rsp_transaction_id);
+            if behind > 0 && behind < 0x8000 {
+                debug!("Dropping stale response {}", rsp_transaction_id);
+                continue;
+            }
+            break (rsp_transaction_id, rsp_unit_id, rsp_pdu);
+        };
+        if rsp_transaction_id != transaction_id || rsp_unit_id != self.unit_id {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid response header: expected ({}, {}), actual ({}, {})",
+                    transaction_id, self.unit_id, rsp_transaction_id, rsp_unit_id
+                ),
+            ));
+        }
+        decode_response_pdu(rsp_pdu)
+    }
+}
+
+impl<T> SlaveContext for TcpClient<T> {
+    fn set_slave(&mut self, slave: Slave) {
+        self.unit_id = slave.into();
+    }
+}
+
+#[async_trait]
+impl<T> Client for TcpClient<T>
+where
+    T: AsyncRead + AsyncWrite + std::fmt::Debug + Unpin + Send,
+{
+    async fn call(&mut self, req: Request) -> Result<Response, Error> {
+        self.transact(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Direction, Tap, TapHandle};
+
+    #[test]
+    fn encode_adu_writes_mbap_header() {
+        assert_eq!(
+            encode_adu(0x0102, 0x11, &[0x03, 0x00, 0x6B, 0x00, 0x03]),
+            vec![0x01, 0x02, 0x00, 0x00, 0x00, 0x06, 0x11, 0x03, 0x00, 0x6B, 0x00, 0x03]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_adu_round_trips() {
+        let adu = encode_adu(7, 0x11, &[0x03, 0x02, 0x12, 0x34]);
+        let mut rest = &adu[..];
+        let (transaction_id, unit_id, pdu) = read_adu(&mut rest).await.unwrap();
+        assert_eq!((transaction_id, unit_id), (7, 0x11));
+        assert_eq!(pdu, vec![0x03, 0x02, 0x12, 0x34]);
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_adu_rejects_bad_headers() {
+        let mut adu = encode_adu(7, 0x11, &[0x03, 0x02, 0x12, 0x34]);
+        adu[3] = 1;
+        let err = read_adu(&mut &adu[..]).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let short = [0x00, 0x07, 0x00, 0x00, 0x00, 0x01, 0x11];
+        let err = read_adu(&mut &short[..]).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    // The tap sees both directions of every transaction in order.
+    #[tokio::test]
+    async fn tap_records_both_directions() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let tap = TapHandle::new();
+        let mut ctx = attach_slave(Tap::new(client, tap.clone()), Slave(0x11));
+        let device = tokio::spawn(async move {
+            let (transaction_id, _unit_id, _pdu) = read_adu(&mut server).await.unwrap();
+            let adu = encode_adu(transaction_id, 0x11, &[0x06, 0x00, 0x01, 0x00, 0x03]);
+            server.write_all(&adu).await.unwrap();
+        });
+        let resp = ctx.call(Request::WriteSingleRegister(1, 3)).await.unwrap();
+        assert_eq!(resp, Response::WriteSingleRegister(1, 3));
+        device.await.unwrap();
+        let frames = tap.take();
+        let directions: Vec<Direction> = frames.iter().map(|x| x.direction).collect();
+        assert_eq!(directions, vec![Direction::Tx, Direction::Rx]);
+        assert_eq!(frames[0].hex(), "00 00 00 00 00 06 11 06 00 01 00 03");
+    }
+}
@@ -3,10 +3,11 @@ use iced::time::Duration;
 use iced::widget::{button, column, text, Container};
 use iced::{executor, Alignment};
 use iced::{Application, Element};
-use iced::{Color, Command, Length, Settings, Subscription};
+use iced::{subscription, Color, Command, Length, Settings, Subscription};
 use iced_aw::native::Modal;
 use rfd::AsyncFileDialog;
 use serde_json;
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufWriter;
@@ -14,12 +15,38 @@ use tokio::sync::mpsc::UnboundedSender;
 
 use serde::{Deserialize, Serialize};
 
-use crate::controller::{connect, Commands, WorkerEvent};
-use crate::gui::components::connection::ConnectionParams;
-use crate::gui::components::request::RequestParams;
-use crate::gui::components::table::Table;
+use crate::controller::{self, Commands, ReconnectPolicy, RetryPolicy, WorkerEvent, WorkerState};
+use crate::gui::components::action::{ActionCommand, ActionList};
+use crate::gui::components::connection::{ConnectionParams, LinkState};
+use crate::gui::components::device_info::{DeviceInfo, DeviceInfoCommand};
+use crate::gui::components::diagnostics::{Diagnostics, DiagnosticsCommand};
+use crate::gui::components::history::{HistoryCommand, RequestHistory};
+use crate::gui::components::logger::{Logger, LoggerCommand};
+use crate::gui::components::request::{RequestParams, RequestUpdate};
+use crate::gui::components::script::{ScriptCommand, ScriptList};
+use crate::gui::components::simulator::Simulator;
+use crate::gui::components::table::{Table, TableCommand};
+use crate::gui::components::trend::Trend;
+use crate::gui::pages::diagnostics_page::diagnostics_page;
 use crate::gui::pages::home_page::home_page;
 use crate::gui::protocol::Protocol;
+use crate::simulator::SimulatorEvent;
+use crate::script;
+use crate::transport::RawFrame;
+
+const MAX_RAW_FRAMES: usize = 500;
+// Slack on top of the retry policy before an unanswered action step fails.
+const ACTION_STEP_GRACE: Duration = Duration::from_secs(1);
+
+// The Modbus worker, run for as long as the application is.
+fn worker() -> Subscription<WorkerEvent> {
+    struct Worker;
+    subscription::unfold(
+        std::any::TypeId::of::<Worker>(),
+        WorkerState::Disconnected,
+        controller::step,
+    )
+}
 
 pub fn run_app() -> iced::Result {
     let mut settings = Settings::default();
@@ -31,6 +58,7 @@ pub fn run_app() -> iced::Result {
 pub enum AppState {
     HomePage,
     ControlPage,
+    DiagnosticsPage,
 }
 
 impl std::default::Default for AppState {
@@ -44,11 +72,35 @@ pub struct App {
     #[serde(skip_deserializing, skip_serializing)]
     pub state: AppState,
     pub connection: ConnectionParams,
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    // Whether a Modbus exception stops table and request polling like any
+    // other error does.
+    #[serde(default = "default_stop_on_exception")]
+    pub stop_on_exception: bool,
     #[serde(skip_deserializing, skip_serializing)]
     pub connected: bool,
     #[serde(skip_deserializing, skip_serializing)]
+    pub link_state: LinkState,
+    #[serde(skip_deserializing, skip_serializing)]
     pub tx_handle: Option<UnboundedSender<Commands>>,
     pub table: Table,
+    #[serde(default)]
+    pub actions: ActionList,
+    #[serde(default)]
+    pub scripts: ScriptList,
+    #[serde(default)]
+    pub simulator: Simulator,
+    #[serde(default)]
+    pub logger: Logger,
+    #[serde(default)]
+    pub trend: Trend,
+    #[serde(default)]
+    pub device_info: DeviceInfo,
+    #[serde(default)]
+    pub diagnostics: Diagnostics,
     #[serde(skip_deserializing, skip_serializing)]
     pub config_file: Option<String>,
     #[serde(skip_deserializing, skip_serializing)]
@@ -57,8 +109,17 @@ pub struct App {
     is_error: bool,
     #[serde(skip_deserializing, skip_serializing)]
     error_text: String,
+    #[serde(default)]
+    pub request_history: RequestHistory,
     #[serde(skip_deserializing, skip_serializing)]
-    pub request_history: Vec<RequestParams>
+    pub raw_frames: VecDeque<RawFrame>,
+    // Responses still due from the current table poll.
+    #[serde(skip_deserializing, skip_serializing)]
+    poll_pending: usize,
+}
+
+fn default_stop_on_exception() -> bool {
+    true
 }
 
 impl std::fmt::Debug for App {
@@ -77,6 +138,57 @@ impl App {
         }
     }
 
+    fn schedule_action_step(&mut self) -> Command<Protocol> {
+        match self.actions.next_delay() {
+            Some((id, delay)) if delay.is_zero() => self.send_action_step(id),
+            Some((id, delay)) => Command::perform(tokio::time::sleep(delay), move |_| {
+                Protocol::ActionCommand(ActionCommand::Send(id))
+            }),
+            None => Command::none(),
+        }
+    }
+
+    // Sends the current step of run `id` and fails it when no response came
+    // back in time, which is the case for commands the worker dropped.
+    fn send_action_step(&mut self, id: u32) -> Command<Protocol> {
+        let (req, unit, over) = match self.actions.step_request(id) {
+            Some(step) => step,
+            None => return Command::none(),
+        };
+        let step = self.actions.step_index().unwrap_or_default();
+        if !self.link_state.is_up() {
+            self.actions.fail(id, step, format!("Not sent, {}", self.link_state));
+            return self.schedule_action_step();
+        }
+        let limit = self.retry.with_override(over).total_time() + ACTION_STEP_GRACE;
+        self.send_message(Commands::ActionCommand(id, step, req, unit, over));
+        Command::perform(tokio::time::sleep(limit), move |_| {
+            Protocol::ActionCommand(ActionCommand::Expire(id, step))
+        })
+    }
+
+    // Every read of a poll is in, the table holds a complete set of values.
+    fn polled(&mut self) {
+        let order = self.connection.byte_order();
+        self.logger.log(&self.table, order);
+        self.trend.sample(&self.table, order);
+    }
+
+    // Scripts block on their requests so they get a thread of their own.
+    fn start_script(&mut self, source: String) {
+        let tx = match &self.tx_handle {
+            Some(tx) if self.connected => tx.clone(),
+            _ => return,
+        };
+        if let Some((stop, events)) = self.scripts.start() {
+            let registers = self.table.registers().to_vec();
+            let order = self.connection.byte_order();
+            std::thread::spawn(move || {
+                script::run(source, tx, events, registers, order, stop)
+            });
+        }
+    }
+
     pub fn load_from_file(
         &mut self,
         path: &std::path::Path,
@@ -87,7 +199,15 @@ impl App {
         match u {
             Ok(app) => {
                 self.table = app.table;
+                self.actions = app.actions;
+                self.scripts = app.scripts;
+                self.logger = app.logger;
+                self.trend = app.trend;
                 self.connection = app.connection;
+                self.reconnect = app.reconnect;
+                self.retry = app.retry;
+                self.stop_on_exception = app.stop_on_exception;
+                self.request_history.set_limit(app.request_history.limit());
             }
             Err(e) => {
                 self.is_error = true;
@@ -132,15 +252,27 @@ impl Application for App {
         let mut app = App {
             state: AppState::HomePage,
             connection: ConnectionParams::new(),
+            reconnect: ReconnectPolicy::default(),
+            retry: RetryPolicy::default(),
+            stop_on_exception: default_stop_on_exception(),
             connected: false,
+            link_state: LinkState::Disconnected,
             tx_handle: None,
             table: Table::default(),
+            actions: ActionList::default(),
+            scripts: ScriptList::default(),
+            simulator: Simulator::default(),
+            logger: Logger::default(),
+            trend: Trend::default(),
+            device_info: DeviceInfo::default(),
+            diagnostics: Diagnostics::default(),
             config_file: None,
             request_params: RequestParams::default(),
             is_error: false,
             error_text: String::new(),
-            // request_history: Vec::new()
-            request_history: (1..20).into_iter().map(|x| RequestParams::default()).collect()
+            request_history: RequestHistory::default(),
+            raw_frames: VecDeque::new(),
+            poll_pending: 0,
         };
         app.load();
         (app, Command::none())
@@ -156,10 +288,34 @@ impl Application for App {
                 self.connection = params;
             }
             Protocol::Connect(params) => {
-                self.send_message(Commands::Connect(params));
+                self.send_message(Commands::Connect(params, self.reconnect, self.retry));
+            }
+            Protocol::ReconnectChanged(policy) => {
+                self.reconnect = policy;
+            }
+            Protocol::RetryChanged(policy) => {
+                self.retry = policy;
+            }
+            Protocol::SetStopOnException(stop) => {
+                self.stop_on_exception = stop;
             }
             Protocol::TableCommand(tc) => {
-                self.table.handle_commmand(tc);
+                self.table.handle_commmand(tc, self.connection.byte_order());
+                self.simulator.sync(&self.table);
+            }
+            Protocol::SimulatorCommand(sc) => {
+                self.simulator.handle_command(sc, &self.table);
+            }
+            Protocol::SimulatorEvent(ev) => {
+                if let SimulatorEvent::Written(unit, req, res) = &ev {
+                    self.table.handle_response(
+                        *unit,
+                        req.clone(),
+                        res.clone(),
+                        self.connection.address(),
+                    );
+                }
+                self.simulator.handle_event(&ev);
             }
             Protocol::WorkerEvent(ev) => {
                 // println!("{:?}", ev);
@@ -167,37 +323,358 @@ impl Application for App {
                     WorkerEvent::WorkerHandle(tx_handle) => {
                         self.tx_handle = Some(tx_handle);
                     },
-                    WorkerEvent::DeviceResponse(req, res) => {
-                        println!("{:?} {:?}", req, res);
-                        self.table.handle_response(req, res);
+                    WorkerEvent::DeviceResponse(tx) => {
+                        match &tx.result {
+                            Ok(res) => {
+                                self.table.handle_response(
+                                    tx.slave,
+                                    tx.request.clone(),
+                                    res.clone(),
+                                    self.connection.address(),
+                                );
+                                if self.poll_pending > 0 {
+                                    self.poll_pending -= 1;
+                                    if self.poll_pending == 0 {
+                                        self.polled();
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                self.table.handle_error(
+                                    tx.slave,
+                                    &tx.request,
+                                    e,
+                                    self.connection.address(),
+                                );
+                            }
+                        }
+                        self.request_history.push(tx);
+                    },
+                    WorkerEvent::RequestResponse(tx) => {
+                        match &tx.result {
+                            Ok(res) => {
+                                self.table.handle_response(
+                                    tx.slave,
+                                    tx.request.clone(),
+                                    res.clone(),
+                                    self.connection.address(),
+                                );
+                                self.request_params.response = Some(res.clone());
+                                self.request_params.error = None;
+                                if self.request_params.polling {
+                                    self.polled();
+                                }
+                            }
+                            Err(e) => {
+                                self.table.handle_error(
+                                    tx.slave,
+                                    &tx.request,
+                                    e,
+                                    self.connection.address(),
+                                );
+                                self.request_params.error = Some(e.clone());
+                            }
+                        }
+                        self.request_history.push(tx);
+                    },
+                    WorkerEvent::RerunResponse(tx) => {
+                        if let Ok(res) = &tx.result {
+                            self.table.handle_response(
+                                tx.slave,
+                                tx.request.clone(),
+                                res.clone(),
+                                self.connection.address(),
+                            );
+                        }
+                        self.request_history.push(tx);
                     },
-                    WorkerEvent::RequestResponse(req, res) => {
-                        println!("{:?} {:?}", req, res);
-                        self.request_params.response = Some(res);
+                    WorkerEvent::ActionResponse(id, step, tx) => {
+                        let recorded = self.actions.record(id, step, &tx);
+                        if let Ok(res) = &tx.result {
+                            self.table.handle_response(
+                                tx.slave,
+                                tx.request.clone(),
+                                res.clone(),
+                                self.connection.address(),
+                            );
+                        }
+                        self.request_history.push(tx);
+                        self.simulator.sync(&self.table);
+                        // A step that already failed has its successor scheduled.
+                        if recorded {
+                            return self.schedule_action_step();
+                        }
+                    },
+                    WorkerEvent::DeviceInfoResponse(tx) => {
+                        if let Some(req) = self.device_info.record(&tx) {
+                            self.send_message(Commands::DeviceInfoCommand(req, None));
+                        }
+                        self.request_history.push(tx);
+                    },
+                    WorkerEvent::DiagnosticResponse(tx) => {
+                        if let Some(req) = self.diagnostics.record(&tx) {
+                            self.send_message(Commands::DiagnosticCommand(req, None));
+                        }
+                        self.request_history.push(tx);
+                    },
+                    WorkerEvent::ScriptResponse(tx) => {
+                        if let Ok(res) = &tx.result {
+                            self.table.handle_response(
+                                tx.slave,
+                                tx.request.clone(),
+                                res.clone(),
+                                self.connection.address(),
+                            );
+                        }
+                        self.request_history.push(tx);
+                    },
+                    WorkerEvent::RawFrames(frames) => {
+                        self.raw_frames.extend(frames);
+                        while self.raw_frames.len() > MAX_RAW_FRAMES {
+                            self.raw_frames.pop_front();
+                        }
                     },
                     WorkerEvent::Error(e) => {
-                        self.is_error = true;
-                        self.error_text = e;
-                        self.request_params.polling = false;
+                        // A failed read leaves the poll without a complete row.
+                        self.poll_pending = 0;
+                        // Otherwise it is only shown next to the request.
+                        if self.stop_on_exception || !e.is_exception() {
+                            self.is_error = true;
+                            self.error_text = e.to_string();
+                            self.request_params.polling = false;
+                            self.table.handle_commmand(TableCommand::SetPolling(false), self.connection.byte_order());
+                        }
+                    },
+                    WorkerEvent::Timeout(_reason) => {
+                        // Shown next to the request and the table rows it
+                        // covered, a slow device should not stop polling.
+                        self.poll_pending = 0;
+                    },
+                    WorkerEvent::Connecting => {
+                        self.link_state = LinkState::Connecting;
                     },
                     WorkerEvent::Connected => {
                         self.connected = true;
+                        self.link_state = LinkState::Connected;
+                    },
+                    WorkerEvent::Reconnecting(attempt, delay, reason) => {
+                        self.actions.stop();
+                        self.scripts.stop();
+                        self.device_info.stop();
+                        self.diagnostics.stop();
+                        self.link_state = LinkState::Reconnecting(attempt, delay, reason);
+                    },
+                    WorkerEvent::Degraded(reason) => {
+                        self.link_state = LinkState::Degraded(reason);
+                    },
+                    WorkerEvent::Disconnected => {
+                        self.actions.stop();
+                        self.scripts.stop();
+                        self.device_info.stop();
+                        self.diagnostics.stop();
+                        self.connected = false;
+                        self.link_state = LinkState::Disconnected;
+                    },
+                    WorkerEvent::Failed(reason) => {
+                        self.connected = false;
+                        self.is_error = true;
+                        self.error_text = reason.clone();
+                        self.request_params.polling = false;
+                        self.table.handle_commmand(TableCommand::SetPolling(false), self.connection.byte_order());
+                        self.actions.stop();
+                        self.scripts.stop();
+                        self.device_info.stop();
+                        self.diagnostics.stop();
+                        self.link_state = LinkState::Failed(reason);
                     },
                     _ => ()
 
                     // WorkerEvent::Disconnected,
                     // WorkerEvent::Idle,
                 }
+                self.simulator.sync(&self.table);
             },
             Protocol::Disconnect => {
                 self.send_message(Commands::Disconnect);
                 self.connected = false;
             }
-            Protocol::ModbusRequest(req) => {
-                self.send_message(Commands::DeviceCommand(req));
+            Protocol::ModbusRequest(req, unit, over) => {
+                self.send_message(Commands::DeviceCommand(req, unit, over));
             }
             Protocol::ExecuteRequest => {
-                self.send_message(Commands::RequestCommand(self.request_params.request.clone()));
+                // Polling goes quiet until the payload is valid again.
+                if self.request_params.payload_error().is_none() {
+                    self.send_message(Commands::RequestCommand(
+                        self.request_params.request.clone(),
+                        self.request_params.unit,
+                    ));
+                }
+            }
+            Protocol::HistoryCommand(hc) => match hc {
+                HistoryCommand::Load(idx) => {
+                    if let Some(tx) = self.request_history.get(idx) {
+                        self.request_params.load(tx);
+                    }
+                }
+                HistoryCommand::Rerun(idx) => {
+                    if let Some(tx) = self.request_history.get(idx) {
+                        let cmd = Commands::RerunCommand(tx.request.clone(), tx.unit);
+                        self.send_message(cmd);
+                    }
+                }
+                HistoryCommand::Clear => self.request_history.clear(),
+                HistoryCommand::SetLimit(limit) => self.request_history.set_limit(limit),
+                HistoryCommand::ExportDialog => {
+                    let future = async {
+                        AsyncFileDialog::new()
+                            .add_filter("csv", &["csv"])
+                            .set_directory("/")
+                            .save_file()
+                            .await
+                    };
+                    return Command::perform(future, |file| match file {
+                        Some(f) => Protocol::HistoryCommand(HistoryCommand::Export(
+                            f.path().to_path_buf(),
+                        )),
+                        None => Protocol::None,
+                    });
+                }
+                HistoryCommand::Export(path) => {
+                    if let Err(e) = self.request_history.export_csv(&path) {
+                        self.is_error = true;
+                        self.error_text = e.to_string();
+                    }
+                }
+                HistoryCommand::None => (),
+            },
+            Protocol::ActionCommand(ac) => match ac {
+                ActionCommand::AddStep => self.actions.add_step(
+                    self.request_params.request.clone(),
+                    self.request_params.unit,
+                ),
+                ActionCommand::Run => {
+                    if !self.link_state.is_up() {
+                        self.is_error = true;
+                        self.error_text = format!("Can not run an action, {}", self.link_state);
+                    } else if self.actions.start() {
+                        return self.schedule_action_step();
+                    }
+                }
+                ActionCommand::Send(id) => return self.send_action_step(id),
+                ActionCommand::Expire(id, step) => {
+                    if self.actions.fail(id, step, "No response".to_string()) {
+                        return self.schedule_action_step();
+                    }
+                }
+                ac => self.actions.handle_command(ac),
+            },
+            Protocol::DeviceInfoCommand(dc) => match dc {
+                DeviceInfoCommand::Read if self.connected => {
+                    if let Some(req) = self.device_info.start() {
+                        self.send_message(Commands::DeviceInfoCommand(req, None));
+                    }
+                }
+                dc => self.device_info.handle_command(dc),
+            },
+            Protocol::DiagnosticsCommand(dc) => {
+                let req = match dc {
+                    DiagnosticsCommand::Send if self.connected => self.diagnostics.send(),
+                    DiagnosticsCommand::ReadCounters if self.connected => {
+                        self.diagnostics.read_counters()
+                    }
+                    dc => {
+                        self.diagnostics.handle_command(dc);
+                        None
+                    }
+                };
+                if let Some(req) = req {
+                    self.send_message(Commands::DiagnosticCommand(req, None));
+                }
+            }
+            Protocol::ChangePage(state) => {
+                self.state = state;
+            }
+            Protocol::ScriptEvent(ev) => self.scripts.handle_event(ev),
+            Protocol::ScriptCommand(sc) => match sc {
+                ScriptCommand::Run => {
+                    if let Some(source) = self.scripts.selected_source() {
+                        self.start_script(source);
+                    }
+                }
+                ScriptCommand::RunConsole => {
+                    if !self.scripts.is_running() {
+                        if let Some(line) = self.scripts.take_console() {
+                            self.start_script(line);
+                        }
+                    }
+                }
+                ScriptCommand::ImportDialog => {
+                    let future = async {
+                        AsyncFileDialog::new()
+                            .add_filter("rhai", &["rhai"])
+                            .set_directory("/")
+                            .pick_file()
+                            .await
+                    };
+                    return Command::perform(future, |file| match file {
+                        Some(f) => match std::fs::read_to_string(f.path()) {
+                            Ok(source) => {
+                                let name = f
+                                    .path()
+                                    .file_stem()
+                                    .map(|x| x.to_string_lossy().to_string())
+                                    .unwrap_or_else(|| f.file_name());
+                                Protocol::ScriptCommand(ScriptCommand::Import(name, source))
+                            }
+                            Err(e) => Protocol::Error(e.to_string()),
+                        },
+                        None => Protocol::None,
+                    });
+                }
+                ScriptCommand::ExportDialog => {
+                    let future = async {
+                        AsyncFileDialog::new()
+                            .add_filter("rhai", &["rhai"])
+                            .set_directory("/")
+                            .save_file()
+                            .await
+                    };
+                    return Command::perform(future, |file| match file {
+                        Some(f) => Protocol::ScriptCommand(ScriptCommand::Export(
+                            f.path().to_path_buf(),
+                        )),
+                        None => Protocol::None,
+                    });
+                }
+                ScriptCommand::Export(path) => {
+                    if let Some(source) = self.scripts.selected_source() {
+                        if let Err(e) = std::fs::write(path, source) {
+                            self.is_error = true;
+                            self.error_text = e.to_string();
+                        }
+                    }
+                }
+                sc => self.scripts.handle_command(sc),
+            },
+            Protocol::LoggerCommand(lc) => match lc {
+                LoggerCommand::DirectoryDialog => {
+                    let future = async { AsyncFileDialog::new().pick_folder().await };
+                    return Command::perform(future, |dir| match dir {
+                        Some(d) => Protocol::LoggerCommand(LoggerCommand::SetDirectory(
+                            d.path().to_path_buf(),
+                        )),
+                        None => Protocol::None,
+                    });
+                }
+                lc => self.logger.handle_command(lc, &self.table),
+            },
+            Protocol::TrendCommand(tc) => self.trend.handle_command(tc),
+            Protocol::PollTable => {
+                let requests = self.table.poll_requests();
+                self.poll_pending = requests.len();
+                for (req, unit, over) in requests {
+                    self.send_message(Commands::DeviceCommand(req, unit, over));
+                }
             }
             Protocol::StartPoll => {
                 self.request_params.polling = true;
@@ -211,12 +688,36 @@ impl Application for App {
             Protocol::ReqChanged(params) => {
                 self.request_params.request = params;
             }
-            Protocol::RequestUpdate(msg) => {
-                self.request_params.update(msg);
-            }
+            Protocol::RequestUpdate(msg) => match msg {
+                RequestUpdate::ExportDialog => {
+                    let future = async {
+                        AsyncFileDialog::new()
+                            .add_filter("bin", &["bin"])
+                            .set_directory("/")
+                            .save_file()
+                            .await
+                    };
+                    return Command::perform(future, |file| match file {
+                        Some(f) => {
+                            Protocol::RequestUpdate(RequestUpdate::Export(f.path().to_path_buf()))
+                        }
+                        None => Protocol::None,
+                    });
+                }
+                RequestUpdate::Export(path) => {
+                    if let Err(e) = self.request_params.export_raw(&path) {
+                        self.is_error = true;
+                        self.error_text = e.to_string();
+                    }
+                }
+                msg => self.request_params.update(msg),
+            },
             Protocol::SaveFile => {
                 self.save_to_file();
             }
+            Protocol::ClearRawFrames => {
+                self.raw_frames.clear();
+            }
             Protocol::CloseModal => {
                 self.is_error = false;
             }
@@ -268,7 +769,22 @@ impl Application for App {
             }
             Protocol::ApplyApp(app) => {
                 self.connection = app.connection;
+                self.reconnect = app.reconnect;
+                self.retry = app.retry;
+                self.stop_on_exception = app.stop_on_exception;
                 self.table = app.table;
+                if !self.actions.is_running() {
+                    self.actions = app.actions;
+                }
+                if !self.scripts.is_running() {
+                    self.scripts = app.scripts;
+                }
+                if !self.logger.is_running() {
+                    self.logger = app.logger;
+                }
+                self.trend = app.trend;
+                self.simulator.sync(&self.table);
+                self.request_history.set_limit(app.request_history.limit());
                 self.config_file = app.config_file;
             }
             _ => (),
@@ -278,12 +794,21 @@ impl Application for App {
 
     fn subscription(&self) -> Subscription<Protocol> {
         let mut subscriptions = vec![
-            connect().map(Protocol::WorkerEvent),
+            worker().map(Protocol::WorkerEvent),
             iced::time::every(Duration::from_secs(5)).map(|_x| Protocol::SaveFile)
         ];
         if self.request_params.polling {
             subscriptions.push(iced::time::every(self.request_params.poll).map(|_x| Protocol::ExecuteRequest));
         };
+        if let Some(sim) = self.simulator.subscription() {
+            subscriptions.push(sim);
+        };
+        if let Some(script) = self.scripts.subscription() {
+            subscriptions.push(script);
+        };
+        if self.table.is_polling() {
+            subscriptions.push(iced::time::every(self.table.poll_interval()).map(|_x| Protocol::PollTable));
+        };
         iced::Subscription::batch(subscriptions)
     }
 
@@ -291,6 +816,7 @@ impl Application for App {
         let c = match self.state {
             AppState::HomePage => home_page(&self),
             AppState::ControlPage => home_page(&self),
+            AppState::DiagnosticsPage => diagnostics_page(&self),
         };
         let content = Container::new(c)
             .width(Length::Fill)
@@ -0,0 +1,612 @@
+use crate::controller::{RetryOverride, Transaction};
+use crate::data_type::parse_u16;
+use crate::gui::components::request::{
+    deserialize_request, get_address, request_data, response_data, serialize_request, RequestType,
+};
+use crate::gui::protocol::Protocol;
+use iced::widget::{button, checkbox, column, pick_list, row, text, text_input, Column};
+use iced::{Alignment, Color, Element};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio_modbus::prelude::{Request, Response};
+
+#[derive(Debug, Clone)]
+pub enum ActionCommand {
+    Select(String),
+    SetName(String),
+    Create,
+    Delete,
+    AddStep,
+    SetDelay(usize, Duration),
+    SetExpect(usize, String),
+    MoveUp(usize),
+    DeleteStep(usize),
+    SetStopOnFailure(bool),
+    SetTimeout(Option<Duration>),
+    SetRetries(Option<u32>),
+    Run,
+    Send(u32),
+    // Run id and step that should have been answered by now.
+    Expire(u32, usize),
+    Stop,
+    None,
+}
+
+#[derive(Debug, Clone)]
+pub enum StepResult {
+    Passed(String, Duration),
+    Failed(String),
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ActionStep {
+    #[serde(
+        serialize_with = "serialize_request",
+        deserialize_with = "deserialize_request"
+    )]
+    pub request: Request,
+    #[serde(default)]
+    pub unit: Option<u8>,
+    // Wait before the step is sent.
+    #[serde(default)]
+    pub delay: Duration,
+    // Expected response values, "*" skips a position, empty only needs success.
+    #[serde(default)]
+    pub expect: String,
+    #[serde(skip)]
+    pub result: Option<StepResult>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Action {
+    pub name: String,
+    pub steps: Vec<ActionStep>,
+    #[serde(default = "default_stop_on_failure")]
+    pub stop_on_failure: bool,
+    // Unset fields follow the connection's retry policy.
+    #[serde(default)]
+    pub retry: RetryOverride,
+}
+
+fn default_stop_on_failure() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActionRun {
+    id: u32,
+    action: usize,
+    step: usize,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct ActionList {
+    actions: Vec<Action>,
+    #[serde(skip)]
+    selected: Option<usize>,
+    #[serde(skip)]
+    name: String,
+    #[serde(skip)]
+    run: Option<ActionRun>,
+    #[serde(skip)]
+    run_id: u32,
+}
+
+// Values a response can be checked against: bits as 0/1, registers as words
+// and the value echoed by single writes.
+fn response_values(resp: &Response) -> Vec<u16> {
+    match resp {
+        Response::ReadCoils(bits) | Response::ReadDiscreteInputs(bits) => {
+            bits.iter().map(|x| u16::from(*x)).collect()
+        }
+        Response::ReadInputRegisters(words)
+        | Response::ReadHoldingRegisters(words)
+        | Response::ReadWriteMultipleRegisters(words) => words.clone(),
+        Response::WriteSingleCoil(_addr, val) => vec![u16::from(*val)],
+        Response::WriteSingleRegister(_addr, val) => vec![*val],
+        _ => Vec::new(),
+    }
+}
+
+impl ActionStep {
+    fn check(&self, resp: &Response) -> Result<(), String> {
+        let values = response_values(resp);
+        let expected = self
+            .expect
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|x| !x.is_empty());
+        for (i, token) in expected.enumerate() {
+            if token == "*" {
+                continue;
+            }
+            let want = parse_u16(token).ok_or(format!("Invalid expected value '{}'", token))?;
+            match values.get(i) {
+                Some(got) if *got == want => (),
+                Some(got) => return Err(format!("Value {} is {}, expected {}", i, got, want)),
+                None => return Err(format!("Only {} values in response", values.len())),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ActionList {
+    pub fn is_running(&self) -> bool {
+        self.run.is_some()
+    }
+
+    fn selected(&self) -> Option<&Action> {
+        self.selected.and_then(|idx| self.actions.get(idx))
+    }
+
+    fn selected_mut(&mut self) -> Option<&mut Action> {
+        self.selected.and_then(move |idx| self.actions.get_mut(idx))
+    }
+
+    pub fn add_step(&mut self, request: Request, unit: Option<u8>) {
+        if self.is_running() {
+            return;
+        }
+        if let Some(action) = self.selected_mut() {
+            action.steps.push(ActionStep {
+                request,
+                unit,
+                delay: Duration::ZERO,
+                expect: String::new(),
+                result: None,
+            });
+        }
+    }
+
+    pub fn handle_command(&mut self, ac: ActionCommand) {
+        // The running action keeps its shape until it is finished or stopped.
+        let running = self.is_running();
+        match ac {
+            ActionCommand::Select(name) if !running => {
+                self.selected = self.actions.iter().position(|x| x.name == name);
+            }
+            ActionCommand::SetName(name) => self.name = name,
+            ActionCommand::Create => {
+                let name = self.name.trim().to_string();
+                if name.is_empty() || self.actions.iter().any(|x| x.name == name) {
+                    return;
+                }
+                self.actions.push(Action {
+                    name,
+                    steps: Vec::new(),
+                    stop_on_failure: true,
+                    retry: RetryOverride::default(),
+                });
+                if !running {
+                    self.selected = Some(self.actions.len() - 1);
+                }
+                self.name.clear();
+            }
+            ActionCommand::Delete if !running => {
+                if let Some(idx) = self.selected.take() {
+                    self.actions.remove(idx);
+                }
+            }
+            ActionCommand::SetDelay(idx, delay) => {
+                if let Some(step) = self.selected_mut().and_then(|x| x.steps.get_mut(idx)) {
+                    step.delay = delay;
+                }
+            }
+            ActionCommand::SetExpect(idx, expect) => {
+                if let Some(step) = self.selected_mut().and_then(|x| x.steps.get_mut(idx)) {
+                    step.expect = expect;
+                }
+            }
+            ActionCommand::MoveUp(idx) if !running => {
+                if let Some(action) = self.selected_mut() {
+                    if idx > 0 && idx < action.steps.len() {
+                        action.steps.swap(idx - 1, idx);
+                    }
+                }
+            }
+            ActionCommand::DeleteStep(idx) if !running => {
+                if let Some(action) = self.selected_mut() {
+                    if idx < action.steps.len() {
+                        action.steps.remove(idx);
+                    }
+                }
+            }
+            ActionCommand::SetStopOnFailure(stop) => {
+                if let Some(action) = self.selected_mut() {
+                    action.stop_on_failure = stop;
+                }
+            }
+            ActionCommand::SetTimeout(timeout) => {
+                if let Some(action) = self.selected_mut() {
+                    action.retry.timeout = timeout;
+                }
+            }
+            ActionCommand::SetRetries(retries) => {
+                if let Some(action) = self.selected_mut() {
+                    action.retry.retries = retries;
+                }
+            }
+            ActionCommand::Stop => self.run = None,
+            _ => (),
+        }
+    }
+
+    // Starts the selected action, results of the previous run are cleared.
+    pub fn start(&mut self) -> bool {
+        if self.is_running() {
+            return false;
+        }
+        let idx = match self.selected {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let action = &mut self.actions[idx];
+        if action.steps.is_empty() {
+            return false;
+        }
+        for step in action.steps.iter_mut() {
+            step.result = None;
+        }
+        self.run_id = self.run_id.wrapping_add(1);
+        self.run = Some(ActionRun {
+            id: self.run_id,
+            action: idx,
+            step: 0,
+        });
+        true
+    }
+
+    fn current_step(&self) -> Option<&ActionStep> {
+        self.run
+            .and_then(|run| self.actions.get(run.action)?.steps.get(run.step))
+    }
+
+    // Run id and delay of the step that goes out next.
+    pub fn next_delay(&self) -> Option<(u32, Duration)> {
+        let id = self.run?.id;
+        self.current_step().map(|step| (id, step.delay))
+    }
+
+    // Step of the run that is sent or waiting for its response.
+    pub fn step_index(&self) -> Option<usize> {
+        self.run.map(|run| run.step)
+    }
+
+    pub fn step_request(&self, id: u32) -> Option<(Request, Option<u8>, RetryOverride)> {
+        let run = self.run.filter(|run| run.id == id)?;
+        let retry = self.actions.get(run.action)?.retry;
+        self.current_step()
+            .map(|step| (step.request.clone(), step.unit, retry))
+    }
+
+    // Stores the outcome of step `step` of run `id` and moves the run along.
+    // Returns false for answers to a stopped run or an expired step.
+    pub fn record(&mut self, id: u32, step: usize, tx: &Transaction) -> bool {
+        let step = match (self.run, self.current_step()) {
+            (Some(run), Some(current)) if run.id == id && run.step == step => current,
+            _ => return false,
+        };
+        let result = match &tx.result {
+            Ok(resp) => match step.check(resp) {
+                Ok(()) => StepResult::Passed(response_data(resp), tx.rtt),
+                Err(e) => StepResult::Failed(e),
+            },
+            Err(e) => StepResult::Failed(e.to_string()),
+        };
+        self.finish_step(result);
+        true
+    }
+
+    // Fails step `step` of run `id` if the run is still waiting on it, for
+    // requests that never reached the device.
+    pub fn fail(&mut self, id: u32, step: usize, reason: String) -> bool {
+        match self.run {
+            Some(run) if run.id == id && run.step == step => {
+                self.finish_step(StepResult::Failed(reason));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn finish_step(&mut self, result: StepResult) {
+        let mut run = match self.run {
+            Some(run) => run,
+            None => return,
+        };
+        let action = &mut self.actions[run.action];
+        let failed = matches!(result, StepResult::Failed(_));
+        if let Some(step) = action.steps.get_mut(run.step) {
+            step.result = Some(result);
+        }
+        run.step += 1;
+        self.run = if run.step >= action.steps.len() || (failed && action.stop_on_failure) {
+            None
+        } else {
+            Some(run)
+        };
+    }
+
+    pub fn stop(&mut self) {
+        self.run = None;
+    }
+}
+
+fn step_row(idx: usize, step: &ActionStep, active: bool) -> Element<Protocol> {
+    let address = match &step.request {
+        Request::Custom(..) => String::new(),
+        req => get_address(req).to_string(),
+    };
+    let result = match &step.result {
+        Some(StepResult::Passed(data, rtt)) => text(format!("OK {} ({} ms)", data, rtt.as_millis()))
+            .style(iced::theme::Text::Color(Color::from_rgb8(74, 222, 128))),
+        Some(StepResult::Failed(e)) => {
+            text(e).style(iced::theme::Text::Color(Color::from_rgb8(248, 113, 113)))
+        }
+        None if active => text("Running..."),
+        None => text(""),
+    };
+    row![
+        text(idx + 1).width(30.0),
+        text(RequestType::from(&step.request).to_string()).width(150.0),
+        text(step.unit.map(|x| x.to_string()).unwrap_or_default()).width(40.0),
+        text(address).width(60.0),
+        text(request_data(&step.request)).width(150.0),
+        text_input("0", &step.delay.as_millis().to_string(), move |x| {
+            match x.parse::<u64>() {
+                Ok(n) => Protocol::ActionCommand(ActionCommand::SetDelay(
+                    idx,
+                    Duration::from_millis(n),
+                )),
+                Err(_e) => Protocol::ActionCommand(ActionCommand::None),
+            }
+        })
+        .width(70.0),
+        text_input("any", &step.expect, move |x| {
+            Protocol::ActionCommand(ActionCommand::SetExpect(idx, x))
+        })
+        .width(120.0),
+        result.width(200.0),
+        button("Up").on_press(Protocol::ActionCommand(ActionCommand::MoveUp(idx))),
+        button("Delete").on_press(Protocol::ActionCommand(ActionCommand::DeleteStep(idx))),
+    ]
+    .spacing(5.0)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+pub fn actions(list: &ActionList) -> Element<Protocol> {
+    let names: Vec<String> = list.actions.iter().map(|x| x.name.clone()).collect();
+    let selected = list.selected().map(|x| x.name.clone());
+    let run_btn = if list.is_running() {
+        button("Stop").on_press(Protocol::ActionCommand(ActionCommand::Stop))
+    } else {
+        button("Run").on_press(Protocol::ActionCommand(ActionCommand::Run))
+    };
+    let mut toolbar = row![
+        text("Actions"),
+        pick_list(names, selected, |x| {
+            Protocol::ActionCommand(ActionCommand::Select(x))
+        })
+        .placeholder("Select action")
+        .width(150.0),
+        run_btn,
+        text_input("New action", &list.name, |x| {
+            Protocol::ActionCommand(ActionCommand::SetName(x))
+        })
+        .width(150.0),
+        button("New").on_press(Protocol::ActionCommand(ActionCommand::Create)),
+    ]
+    .spacing(10.0)
+    .align_items(Alignment::Center);
+    let action = match list.selected() {
+        Some(action) => action,
+        None => return toolbar.into(),
+    };
+    toolbar = toolbar
+        .push(button("Delete").on_press(Protocol::ActionCommand(ActionCommand::Delete)))
+        .push(checkbox("Stop on failure", action.stop_on_failure, |x| {
+            Protocol::ActionCommand(ActionCommand::SetStopOnFailure(x))
+        }))
+        .push(text("Timeout (ms)"))
+        .push(
+            text_input(
+                "default",
+                &action
+                    .retry
+                    .timeout
+                    .map(|x| x.as_millis().to_string())
+                    .unwrap_or_default(),
+                |x| match x.parse::<u64>() {
+                    _ if x.is_empty() => Protocol::ActionCommand(ActionCommand::SetTimeout(None)),
+                    Ok(n) if n > 0 => Protocol::ActionCommand(ActionCommand::SetTimeout(Some(
+                        Duration::from_millis(n),
+                    ))),
+                    _ => Protocol::ActionCommand(ActionCommand::None),
+                },
+            )
+            .width(70.0),
+        )
+        .push(text("Retries"))
+        .push(
+            text_input(
+                "default",
+                &action
+                    .retry
+                    .retries
+                    .map(|x| x.to_string())
+                    .unwrap_or_default(),
+                |x| match x.parse::<u32>() {
+                    _ if x.is_empty() => Protocol::ActionCommand(ActionCommand::SetRetries(None)),
+                    Ok(n) => Protocol::ActionCommand(ActionCommand::SetRetries(Some(n))),
+                    Err(_e) => Protocol::ActionCommand(ActionCommand::None),
+                },
+            )
+            .width(50.0),
+        )
+        .push(
+            button("Add Current Request").on_press(Protocol::ActionCommand(ActionCommand::AddStep)),
+        );
+    let active = list
+        .run
+        .filter(|run| Some(run.action) == list.selected)
+        .map(|run| run.step);
+    let steps = Column::with_children(
+        action
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(idx, step)| step_row(idx, step, active == Some(idx)))
+            .collect(),
+    )
+    .spacing(5.0);
+    column![
+        toolbar,
+        row![
+            text("#").width(30.0),
+            text("Request Type").width(150.0),
+            text("Unit").width(40.0),
+            text("Address").width(60.0),
+            text("Data").width(150.0),
+            text("Delay (ms)").width(70.0),
+            text("Expect").width(120.0),
+            text("Result").width(200.0),
+        ]
+        .spacing(5.0),
+        steps
+    ]
+    .spacing(10.0)
+    .padding(10.0)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustbus_core::error::ModbusError;
+
+    fn step(expect: &str) -> ActionStep {
+        ActionStep {
+            request: Request::ReadHoldingRegisters(0, 3),
+            unit: None,
+            delay: Duration::ZERO,
+            expect: expect.to_string(),
+            result: None,
+        }
+    }
+
+    fn list(requests: &[Request], stop_on_failure: bool) -> ActionList {
+        let mut list = ActionList::default();
+        list.handle_command(ActionCommand::SetName("test".to_string()));
+        list.handle_command(ActionCommand::Create);
+        list.handle_command(ActionCommand::SetStopOnFailure(stop_on_failure));
+        for req in requests {
+            list.add_step(req.clone(), None);
+        }
+        list
+    }
+
+    fn transaction(request: Request, result: Result<Response, ModbusError>) -> Transaction {
+        Transaction {
+            request,
+            unit: None,
+            slave: 1,
+            timestamp: chrono::Local::now(),
+            rtt: Duration::from_millis(5),
+            result,
+        }
+    }
+
+    fn results(list: &ActionList) -> Vec<Option<bool>> {
+        list.actions[0]
+            .steps
+            .iter()
+            .map(|x| {
+                x.result
+                    .as_ref()
+                    .map(|x| matches!(x, StepResult::Passed(..)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_compares_listed_values() {
+        let resp = Response::ReadHoldingRegisters(vec![1, 5, 16]);
+        assert_eq!(step("").check(&resp), Ok(()));
+        assert_eq!(step("1, *, 0x10").check(&resp), Ok(()));
+        assert_eq!(
+            step("1 6").check(&resp),
+            Err("Value 1 is 5, expected 6".to_string())
+        );
+        assert_eq!(
+            step("1 5 16 0").check(&resp),
+            Err("Only 3 values in response".to_string())
+        );
+        assert!(step("x").check(&resp).is_err());
+        assert_eq!(step("1").check(&Response::WriteSingleCoil(3, true)), Ok(()));
+    }
+
+    #[test]
+    fn record_moves_the_run_along() {
+        let req = Request::ReadHoldingRegisters(0, 1);
+        let read = || transaction(req.clone(), Ok(Response::ReadHoldingRegisters(vec![0])));
+        let mut list = list(&[req.clone(), req.clone()], true);
+        assert!(list.start());
+        let (id, _delay) = list.next_delay().unwrap();
+        assert!(!list.record(id, 1, &read()));
+        assert!(list.record(id, 0, &read()));
+        assert_eq!(list.step_index(), Some(1));
+        // The same request answered late for the step before is not this one's.
+        assert!(!list.record(id, 0, &read()));
+        assert!(!list.record(id.wrapping_sub(1), 1, &read()));
+        assert!(list.record(id, 1, &read()));
+        assert!(!list.is_running());
+        assert_eq!(results(&list), vec![Some(true), Some(true)]);
+    }
+
+    #[test]
+    fn failed_step_ends_the_run_only_when_asked_to() {
+        let req = Request::ReadCoils(0, 1);
+        let timeout = || {
+            transaction(
+                req.clone(),
+                Err(ModbusError::Timeout("No response".to_string())),
+            )
+        };
+
+        let mut stopping = list(&[req.clone(), req.clone()], true);
+        stopping.start();
+        let (id, _delay) = stopping.next_delay().unwrap();
+        stopping.record(id, 0, &timeout());
+        assert!(!stopping.is_running());
+        assert_eq!(results(&stopping), vec![Some(false), None]);
+
+        let mut going_on = list(&[req.clone(), req.clone()], false);
+        going_on.start();
+        let (id, _delay) = going_on.next_delay().unwrap();
+        going_on.record(id, 0, &timeout());
+        assert_eq!(going_on.step_index(), Some(1));
+        going_on.record(
+            id,
+            1,
+            &transaction(req.clone(), Ok(Response::ReadCoils(vec![true]))),
+        );
+        assert_eq!(results(&going_on), vec![Some(false), Some(true)]);
+    }
+
+    #[test]
+    fn fail_only_hits_the_step_still_waiting() {
+        let req = Request::ReadCoils(0, 1);
+        let mut list = list(&[req.clone(), req], false);
+        list.start();
+        let (id, _delay) = list.next_delay().unwrap();
+        assert!(!list.fail(id.wrapping_add(1), 0, "No response".to_string()));
+        assert!(!list.fail(id, 1, "No response".to_string()));
+        assert!(list.fail(id, 0, "No response".to_string()));
+        assert!(!list.fail(id, 0, "No response".to_string()));
+        assert_eq!(list.step_index(), Some(1));
+        assert!(list.fail(id, 1, "No response".to_string()));
+        assert!(!list.is_running());
+        assert_eq!(results(&list), vec![Some(false), Some(false)]);
+    }
+}
@@ -1,5 +1,6 @@
 use iced::theme::Theme;
 use iced::widget::{image, image::Handle, row, text, Container, button};
+use iced_aw::native::wrap::Wrap;
 use iced::Element;
 use iced::Renderer;
 use iced::{Background, Color, Length};
@@ -64,6 +65,49 @@ where
 //     TextInput::new("", &value.to_string(), on_change)
 // }
 
+const LED_SIZE: f32 = 10.0;
+const LED_ON: Color = Color { r: 0.2, g: 0.85, b: 0.3, a: 1.0 };
+const LED_OFF: Color = Color { r: 0.25, g: 0.25, b: 0.25, a: 1.0 };
+
+pub fn led<'a, Message: 'a>(on: bool) -> Container<'a, Message, Renderer> {
+    Container::new(text(""))
+        .width(Length::Fixed(LED_SIZE))
+        .height(Length::Fixed(LED_SIZE))
+        .style(iced::theme::Container::Custom(Box::new(ContainerStyle {
+            text_color: None,
+            background: Some(Background::from(if on { LED_ON } else { LED_OFF })),
+            border_radius: LED_SIZE / 2.0,
+            border_width: 1.0,
+            border_color: Color::BLACK,
+        })))
+}
+
+// A row of bit lamps, most significant bit first. Lamps become buttons when
+// `on_press` is given.
+pub fn leds<'a>(
+    bits: &[bool],
+    max_width: f32,
+    on_press: Option<&dyn Fn(usize) -> Protocol>,
+) -> Element<'a, Protocol> {
+    Wrap::with_elements(
+        bits.iter()
+            .enumerate()
+            .map(|(i, on)| match on_press {
+                Some(f) => button(led(*on))
+                    .padding(0)
+                    .on_press(f(i))
+                    .style(ButtonType::Image.into())
+                    .into(),
+                None => led(*on).into(),
+            })
+            .collect(),
+    )
+    .spacing(2.0)
+    .line_spacing(2.0)
+    .max_width(max_width)
+    .into()
+}
+
 pub fn header() -> Element<'static, Protocol> {
     Container::new(
         row![
@@ -1,9 +1,12 @@
+use crate::controller::{ReconnectPolicy, RetryPolicy};
+use crate::data_type::ByteOrder;
 use crate::gui::app::App;
 use crate::gui::protocol::Protocol;
 use iced::{
-    widget::{button, column, pick_list, row},
+    widget::{button, checkbox, column, pick_list, row, text, text_input},
     Element,
 };
+use std::time::Duration;
 
 const CONNECT_OPTIONS: [&str; 2] = ["SERIAL", "TCP"];
 
@@ -12,28 +15,175 @@ pub mod tcp;
 
 use self::serial::{SerialPortComponent, SerialPortParams};
 use self::tcp::{TcpComponent, TcpParams};
-use serde::{Deserialize, Serialize};
 
-// #[derive(Debug, Copy, Clone)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ConnectionParams {
-    Serial(SerialPortParams),
-    Tcp(TcpParams),
+pub use rustbus_core::connection::ConnectionParams;
+
+fn connection_type(params: &ConnectionParams) -> &'static str {
+    match params {
+        ConnectionParams::Serial(_p) => CONNECT_OPTIONS[0],
+        ConnectionParams::Tcp(_p) => CONNECT_OPTIONS[1],
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Reconnecting(u32, Duration, String),
+    Degraded(String),
+    Failed(String),
+}
+
+impl std::default::Default for LinkState {
+    fn default() -> Self {
+        LinkState::Disconnected
+    }
 }
 
-impl ConnectionParams {
-    pub fn new() -> Self {
-        ConnectionParams::Serial(SerialPortParams::default())
+impl LinkState {
+    pub fn is_active(&self) -> bool {
+        !matches!(self, LinkState::Disconnected | LinkState::Failed(_))
     }
 
-    pub fn get_string_option(&self) -> &'static str {
+    // Whether the worker takes requests right now.
+    pub fn is_up(&self) -> bool {
+        matches!(self, LinkState::Connected | LinkState::Degraded(_))
+    }
+}
+
+impl std::fmt::Display for LinkState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ConnectionParams::Serial(_p) => CONNECT_OPTIONS[0],
-            ConnectionParams::Tcp(_p) => CONNECT_OPTIONS[1],
+            LinkState::Disconnected => write!(f, "Disconnected"),
+            LinkState::Connecting => write!(f, "Connecting..."),
+            LinkState::Connected => write!(f, "Connected"),
+            LinkState::Reconnecting(attempt, delay, reason) => write!(
+                f,
+                "Reconnecting (attempt {} in {} ms): {}",
+                attempt,
+                delay.as_millis(),
+                reason
+            ),
+            LinkState::Degraded(reason) => write!(f, "Degraded: {}", reason),
+            LinkState::Failed(reason) => write!(f, "Failed: {}", reason),
         }
     }
 }
 
+pub fn reconnect_settings<'a>(policy: ReconnectPolicy) -> Element<'a, Protocol> {
+    row![
+        checkbox("Auto Reconnect", policy.enabled, move |x| {
+            Protocol::ReconnectChanged(ReconnectPolicy {
+                enabled: x,
+                ..policy
+            })
+        }),
+        column![
+            "Initial Delay (ms)",
+            text_input("500", &policy.initial_delay.as_millis().to_string(), move |x| {
+                match x.parse::<u64>() {
+                    Ok(n) => Protocol::ReconnectChanged(ReconnectPolicy {
+                        initial_delay: Duration::from_millis(n),
+                        ..policy
+                    }),
+                    Err(_e) => Protocol::None,
+                }
+            })
+            .width(80)
+        ],
+        column![
+            "Max Delay (ms)",
+            text_input("30000", &policy.max_delay.as_millis().to_string(), move |x| {
+                match x.parse::<u64>() {
+                    Ok(n) => Protocol::ReconnectChanged(ReconnectPolicy {
+                        max_delay: Duration::from_millis(n),
+                        ..policy
+                    }),
+                    Err(_e) => Protocol::None,
+                }
+            })
+            .width(80)
+        ],
+        column![
+            "Max Attempts (0 = ∞)",
+            text_input("0", &policy.max_attempts.to_string(), move |x| {
+                match x.parse::<u32>() {
+                    Ok(n) => Protocol::ReconnectChanged(ReconnectPolicy {
+                        max_attempts: n,
+                        ..policy
+                    }),
+                    Err(_e) => Protocol::None,
+                }
+            })
+            .width(80)
+        ],
+        column![
+            "Degraded After",
+            text_input("3", &policy.degraded_after.to_string(), move |x| {
+                match x.parse::<u32>() {
+                    Ok(n) => Protocol::ReconnectChanged(ReconnectPolicy {
+                        degraded_after: n,
+                        ..policy
+                    }),
+                    Err(_e) => Protocol::None,
+                }
+            })
+            .width(80)
+        ],
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::End)
+    .into()
+}
+
+pub fn retry_settings<'a>(policy: RetryPolicy) -> Element<'a, Protocol> {
+    row![
+        column![
+            "Timeout (ms)",
+            text_input("1000", &policy.timeout.as_millis().to_string(), move |x| {
+                match x.parse::<u64>() {
+                    Ok(n) if n > 0 => Protocol::RetryChanged(RetryPolicy {
+                        timeout: Duration::from_millis(n),
+                        ..policy
+                    }),
+                    _ => Protocol::None,
+                }
+            })
+            .width(80)
+        ],
+        column![
+            "Retries",
+            text_input("0", &policy.retries.to_string(), move |x| {
+                match x.parse::<u32>() {
+                    Ok(n) => Protocol::RetryChanged(RetryPolicy {
+                        retries: n,
+                        ..policy
+                    }),
+                    Err(_e) => Protocol::None,
+                }
+            })
+            .width(80)
+        ],
+        column![
+            "Backoff (ms)",
+            text_input("100", &policy.backoff.as_millis().to_string(), move |x| {
+                match x.parse::<u64>() {
+                    Ok(n) => Protocol::RetryChanged(RetryPolicy {
+                        backoff: Duration::from_millis(n),
+                        ..policy
+                    }),
+                    Err(_e) => Protocol::None,
+                }
+            })
+            .width(80)
+        ],
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::End)
+    .into()
+}
+
 pub fn connection<'a>(app: &App) -> Element<'a, Protocol> {
     let c: Element<_> = match &app.connection {
         ConnectionParams::Serial(params) => {
@@ -47,7 +197,7 @@ pub fn connection<'a>(app: &App) -> Element<'a, Protocol> {
         })
         .into(),
     };
-    let b: Element<_> = match &app.connected {
+    let b: Element<_> = match app.link_state.is_active() {
         true => button("Disconnect")
             .on_press(Protocol::Disconnect)
             .style(iced_style::theme::Button::Destructive)
@@ -57,8 +207,25 @@ pub fn connection<'a>(app: &App) -> Element<'a, Protocol> {
             .style(iced_style::theme::Button::Primary)
             .into(),
     };
-    let current_type = app.connection.get_string_option();
-    row![
+    let current_type = connection_type(&app.connection);
+    let connection = app.connection.clone();
+    let byte_order = column![
+        "Byte Order",
+        pick_list(&ByteOrder::ALL[..], Some(app.connection.byte_order()), move |x| {
+            Protocol::ConnectionChanged(connection.with_byte_order(x))
+        })
+        .width(80)
+    ]
+    .spacing(10);
+    let link = row![
+        reconnect_settings(app.reconnect),
+        retry_settings(app.retry),
+        checkbox("Exceptions Stop Polling", app.stop_on_exception, Protocol::SetStopOnException),
+        text(app.link_state.to_string())
+    ]
+    .spacing(20)
+    .align_items(iced::Alignment::Center);
+    column![row![
         column![
             "Connection Type",
             pick_list(&CONNECT_OPTIONS[..], Some(&current_type), |val| {
@@ -80,10 +247,13 @@ pub fn connection<'a>(app: &App) -> Element<'a, Protocol> {
         ]
         .spacing(10),
         c,
+        byte_order,
         b
     ]
     .spacing(10)
+    .align_items(iced::Alignment::End), link]
+    .spacing(10)
     .padding(10)
-    .align_items(iced::Alignment::End)
+    .align_items(iced::Alignment::Center)
     .into()
 }
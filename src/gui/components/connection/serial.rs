@@ -5,12 +5,11 @@ use iced::Element;
 use iced_lazy::Component;
 use iced_native;
 use iced_style;
-use serde::de::{Deserializer, Error};
-use serde::ser::{SerializeStruct, Serializer};
-use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use rustbus_core::connection::{data_bits_name, parity_name, stop_bits_name};
 use tokio_serial::{self, DataBits, Parity, StopBits};
 
+pub use rustbus_core::connection::SerialPortParams;
+
 const BAUDRATES: [u32; 14] = [
     110, 300, 600, 1200, 2400, 4800, 9600, 14400, 19200, 38400, 57600, 115200, 128000, 256000,
 ];
@@ -65,131 +64,19 @@ impl From<GuiStopBits> for StopBits {
 
 impl std::fmt::Display for GuiParity {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                GuiParity(Parity::None) => "None",
-                GuiParity(Parity::Odd) => "Odd",
-                GuiParity(Parity::Even) => "Even",
-            }
-        )
+        write!(f, "{}", parity_name(self.0))
     }
 }
 
 impl std::fmt::Display for GuiDataBits {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                GuiDataBits(DataBits::Five) => "5",
-                GuiDataBits(DataBits::Six) => "6",
-                GuiDataBits(DataBits::Seven) => "7",
-                GuiDataBits(DataBits::Eight) => "8",
-            }
-        )
+        write!(f, "{}", data_bits_name(self.0))
     }
 }
 
 impl std::fmt::Display for GuiStopBits {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                GuiStopBits(StopBits::One) => "1",
-                GuiStopBits(StopBits::Two) => "2",
-            }
-        )
-    }
-}
-
-#[derive(Debug, Clone, Deserialize)]
-pub struct SerialPortParams {
-    pub port: String,
-    pub baudrate: u32,
-    #[serde(deserialize_with = "deserialize_parity")]
-    pub parity: Parity,
-    #[serde(deserialize_with = "deserialize_data_bits")]
-    pub data_bits: DataBits,
-    #[serde(deserialize_with = "deserialize_stop_bits")]
-    pub stop_bits: StopBits,
-    pub timeout: std::time::Duration,
-    pub address: u8,
-}
-
-fn deserialize_parity<'de, D>(deserializer: D) -> Result<Parity, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    match s.as_str() {
-        "Odd" => Ok(Parity::Odd),
-        "Even" => Ok(Parity::Even),
-        "None" => Ok(Parity::None),
-        _ => Err(D::Error::custom("Invalid Field")),
-    }
-}
-
-fn deserialize_data_bits<'de, D>(deserializer: D) -> Result<DataBits, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    match s.as_str() {
-        "5" => Ok(DataBits::Five),
-        "6" => Ok(DataBits::Six),
-        "7" => Ok(DataBits::Seven),
-        "8" => Ok(DataBits::Eight),
-        _ => Err(D::Error::custom("Invalid Field")),
-    }
-}
-
-fn deserialize_stop_bits<'de, D>(deserializer: D) -> Result<StopBits, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    match s.as_str() {
-        "1" => Ok(StopBits::One),
-        "2" => Ok(StopBits::Two),
-        _ => Err(D::Error::custom("Invalid Field")),
-    }
-}
-
-impl Serialize for SerialPortParams {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let parity = GuiParity(self.parity).to_string();
-        let data_bits = GuiDataBits(self.data_bits).to_string();
-        let stop_bits = GuiStopBits(self.stop_bits).to_string();
-        // 3 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("serial_port", 7)?;
-        state.serialize_field("port", &self.port)?;
-        state.serialize_field("baudrate", &self.baudrate)?;
-        state.serialize_field("parity", &parity)?;
-        state.serialize_field("data_bits", &data_bits)?;
-        state.serialize_field("stop_bits", &stop_bits)?;
-        state.serialize_field("timeout", &self.timeout)?;
-        state.serialize_field("address", &self.address)?;
-        state.end()
-    }
-}
-
-impl Default for SerialPortParams {
-    fn default() -> Self {
-        Self {
-            port: "".to_string(),
-            baudrate: 115200,
-            parity: Parity::None,
-            data_bits: DataBits::Eight,
-            stop_bits: StopBits::One,
-            timeout: std::time::Duration::from_millis(1000),
-            address: 1,
-        }
+        write!(f, "{}", stop_bits_name(self.0))
     }
 }
 
@@ -361,16 +248,3 @@ pub enum SerialPortParamsMessage {
     None,
 }
 
-impl SerialPortParams {
-    pub fn new() -> Self {
-        Self {
-            port: "".to_string(),
-            baudrate: 115200,
-            parity: Parity::None,
-            data_bits: DataBits::Eight,
-            stop_bits: StopBits::One,
-            timeout: Duration::from_secs(1),
-            address: 1,
-        }
-    }
-}
@@ -1,53 +1,11 @@
-use std::net::Ipv4Addr;
-
 use iced::alignment::Alignment;
 use iced::widget::{self, text_input};
 use iced::widget::{column, row, text};
 use iced::Element;
 use iced_lazy::Component;
 use iced_native;
-use serde::de::{Deserializer, Error};
-use serde::ser::{SerializeStruct, Serializer};
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Copy, Clone, Deserialize)]
-pub struct TcpParams {
-    #[serde(deserialize_with = "deserialize_ip")]
-    pub ip: [u8; 4],
-    pub port: u16,
-    pub address: u8,
-}
 
-impl Serialize for TcpParams {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut state = serializer.serialize_struct("serial_port", 2)?;
-        let ip = Ipv4Addr::new(self.ip[0], self.ip[1], self.ip[2], self.ip[3]);
-        state.serialize_field("ip", &ip.to_string())?;
-        state.serialize_field("port", &self.port)?;
-        state.serialize_field("address", &self.address)?;
-        state.end()
-    }
-}
-
-fn deserialize_ip<'de, D>(deserializer: D) -> Result<[u8; 4], D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    match s.parse::<Ipv4Addr>() {
-        Ok(ip) => Ok(ip.octets()),
-        Err(_e) => Err(D::Error::custom("Invalid IP")),
-    }
-}
-
-impl TcpParams {
-    pub fn new(ip: [u8; 4], port: u16, address: u8) -> Self {
-        Self { ip, port, address }
-    }
-}
+pub use rustbus_core::connection::TcpParams;
 
 pub struct TcpComponent<Message> {
     params: TcpParams,
@@ -0,0 +1,139 @@
+use crate::controller::Transaction;
+use crate::gui::protocol::Protocol;
+use iced::widget::{button, column, pick_list, row, text, Column};
+use iced::{Alignment, Element};
+use rustbus_core::device_id::{
+    conformity_text, object_name, object_text, DeviceIdLevel, DeviceIdentity,
+};
+use serde::{Deserialize, Serialize};
+use tokio_modbus::prelude::Request;
+
+#[derive(Debug, Clone)]
+pub enum DeviceInfoCommand {
+    SetLevel(DeviceIdLevel),
+    Read,
+    Clear,
+    None,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct DeviceInfo {
+    #[serde(default)]
+    level: DeviceIdLevel,
+    #[serde(skip)]
+    identity: Option<DeviceIdentity>,
+    #[serde(skip)]
+    reading: bool,
+    #[serde(skip)]
+    status: String,
+}
+
+impl DeviceInfo {
+    pub fn handle_command(&mut self, dc: DeviceInfoCommand) {
+        match dc {
+            DeviceInfoCommand::SetLevel(level) => self.level = level,
+            DeviceInfoCommand::Clear if !self.reading => {
+                self.identity = None;
+                self.status.clear();
+            }
+            _ => (),
+        }
+    }
+
+    // First request of a new read, `None` while one is still going.
+    pub fn start(&mut self) -> Option<Request> {
+        if self.reading {
+            return None;
+        }
+        let identity = DeviceIdentity::new(self.level);
+        let req = identity.first_request();
+        self.identity = Some(identity);
+        self.reading = true;
+        self.status = String::from("Reading");
+        Some(req)
+    }
+
+    // Takes a response, giving the follow-up request while more objects follow.
+    pub fn record(&mut self, tx: &Transaction) -> Option<Request> {
+        let identity = match &mut self.identity {
+            Some(identity) if self.reading => identity,
+            _ => return None,
+        };
+        let next = tx
+            .result
+            .as_ref()
+            .map_err(|e| e.to_string())
+            .and_then(|resp| identity.apply(resp));
+        match next {
+            Ok(Some(req)) => Some(req),
+            Ok(None) => {
+                self.reading = false;
+                self.status = format!("{} objects", identity.objects.len());
+                None
+            }
+            Err(e) => {
+                self.reading = false;
+                self.status = e;
+                None
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if self.reading {
+            self.reading = false;
+            self.status = String::from("Stopped");
+        }
+    }
+}
+
+pub fn device_info(info: &DeviceInfo) -> Element<Protocol> {
+    let read = if info.reading {
+        button("Read")
+    } else {
+        button("Read").on_press(Protocol::DeviceInfoCommand(DeviceInfoCommand::Read))
+    };
+    let mut objects = Column::new().spacing(5.0);
+    if let Some(identity) = &info.identity {
+        if let Some(conformity) = identity.conformity {
+            objects = objects.push(text(format!("Conformity {}", conformity_text(conformity))));
+        }
+        objects = objects.push(
+            row![
+                text("Id").width(50.0),
+                text("Object").width(180.0),
+                text("Value").width(350.0),
+            ]
+            .spacing(5.0),
+        );
+        for (id, value) in &identity.objects {
+            objects = objects.push(
+                row![
+                    text(format!("0x{:02X}", id)).width(50.0),
+                    text(object_name(*id)).width(180.0),
+                    text(object_text(value)).width(350.0),
+                ]
+                .spacing(5.0),
+            );
+        }
+    }
+    column![
+        row![
+            text("Device Info"),
+            pick_list(&DeviceIdLevel::ALL[..], Some(info.level), |x| {
+                Protocol::DeviceInfoCommand(DeviceInfoCommand::SetLevel(x))
+            })
+            .width(110.0),
+            read,
+            button("Clear").on_press(Protocol::DeviceInfoCommand(DeviceInfoCommand::Clear)),
+            text(&info.status),
+        ]
+        .spacing(10.0)
+        .align_items(Alignment::Center),
+        objects,
+    ]
+    .spacing(10.0)
+    .padding(10.0)
+    .align_items(Alignment::Center)
+    .into()
+}
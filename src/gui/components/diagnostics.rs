@@ -0,0 +1,209 @@
+use crate::controller::Transaction;
+use crate::gui::components::connection::ConnectionParams;
+use crate::gui::protocol::Protocol;
+use crate::transport::parse_hex_bytes;
+use chrono::{DateTime, Local};
+use iced::widget::{button, column, pick_list, row, text, text_input, Column};
+use iced::{Alignment, Color, Element};
+use rustbus_core::diagnostics::Diagnostic;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio_modbus::prelude::Request;
+
+#[derive(Debug, Clone)]
+pub enum DiagnosticsCommand {
+    Select(Diagnostic),
+    SetEcho(String),
+    Send,
+    ReadCounters,
+    Clear,
+    None,
+}
+
+#[derive(Clone, Debug)]
+struct DiagnosticResult {
+    diagnostic: Diagnostic,
+    timestamp: DateTime<Local>,
+    result: Result<String, String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Diagnostics {
+    #[serde(default = "default_selected")]
+    selected: Diagnostic,
+    #[serde(default = "default_echo")]
+    echo: String,
+    // Latest result of each diagnostic, in the order of `Diagnostic::ALL`.
+    #[serde(skip)]
+    results: Vec<DiagnosticResult>,
+    #[serde(skip)]
+    queue: VecDeque<Diagnostic>,
+    #[serde(skip)]
+    waiting: Option<Diagnostic>,
+    #[serde(skip)]
+    status: String,
+}
+
+fn default_selected() -> Diagnostic {
+    Diagnostic::ReturnQueryData
+}
+
+fn default_echo() -> String {
+    String::from("A5 37")
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self {
+            selected: default_selected(),
+            echo: default_echo(),
+            results: Vec::new(),
+            queue: VecDeque::new(),
+            waiting: None,
+            status: String::new(),
+        }
+    }
+}
+
+impl Diagnostics {
+    pub fn handle_command(&mut self, dc: DiagnosticsCommand) {
+        match dc {
+            DiagnosticsCommand::Select(diagnostic) => self.selected = diagnostic,
+            DiagnosticsCommand::SetEcho(echo) => self.echo = echo,
+            DiagnosticsCommand::Clear => {
+                self.results.clear();
+                self.status.clear();
+            }
+            _ => (),
+        }
+    }
+
+    // Request for the selected diagnostic, `None` while one is still out.
+    pub fn send(&mut self) -> Option<Request> {
+        if self.waiting.is_some() {
+            return None;
+        }
+        self.queue = VecDeque::from([self.selected]);
+        self.next()
+    }
+
+    pub fn read_counters(&mut self) -> Option<Request> {
+        if self.waiting.is_some() {
+            return None;
+        }
+        self.queue = VecDeque::from(Diagnostic::COUNTERS);
+        self.next()
+    }
+
+    fn next(&mut self) -> Option<Request> {
+        let diagnostic = self.queue.pop_front()?;
+        let echo = match parse_hex_bytes(&self.echo) {
+            Some(echo) => echo,
+            None => {
+                self.queue.clear();
+                self.status = format!("{} is not hex data", self.echo);
+                return None;
+            }
+        };
+        self.waiting = Some(diagnostic);
+        self.status.clear();
+        Some(diagnostic.request(&echo))
+    }
+
+    // Stores a result, giving the next queued request. A failed transaction
+    // drops the rest of the queue.
+    pub fn record(&mut self, tx: &Transaction) -> Option<Request> {
+        let diagnostic = self.waiting.take()?;
+        let result = match &tx.result {
+            Ok(resp) => diagnostic.parse_response(&tx.request, resp),
+            Err(e) => {
+                self.queue.clear();
+                Err(e.to_string())
+            }
+        };
+        let entry = DiagnosticResult {
+            diagnostic,
+            timestamp: tx.timestamp,
+            result,
+        };
+        match self.results.iter_mut().find(|x| x.diagnostic == diagnostic) {
+            Some(x) => *x = entry,
+            None => {
+                self.results.push(entry);
+                self.results
+                    .sort_by_key(|x| Diagnostic::ALL.iter().position(|d| *d == x.diagnostic));
+            }
+        }
+        self.next()
+    }
+
+    pub fn stop(&mut self) {
+        self.queue.clear();
+        self.waiting = None;
+    }
+}
+
+fn result_row(entry: &DiagnosticResult) -> Element<Protocol> {
+    let result = match &entry.result {
+        Ok(x) => text(x).width(300.0),
+        Err(e) => text(e)
+            .width(300.0)
+            .style(iced::theme::Text::Color(Color::from_rgb8(248, 113, 113))),
+    };
+    row![
+        text(entry.timestamp.format("%H:%M:%S%.3f")).width(100.0),
+        text(entry.diagnostic.to_string()).width(250.0),
+        result,
+    ]
+    .spacing(5.0)
+    .into()
+}
+
+pub fn diagnostics<'a>(diag: &'a Diagnostics, params: &ConnectionParams) -> Element<'a, Protocol> {
+    let busy = diag.waiting.is_some();
+    let mut send = button("Send");
+    let mut counters = button("Read Counters");
+    if !busy {
+        send = send.on_press(Protocol::DiagnosticsCommand(DiagnosticsCommand::Send));
+        counters = counters.on_press(Protocol::DiagnosticsCommand(
+            DiagnosticsCommand::ReadCounters,
+        ));
+    }
+    let mut controls = row![pick_list(&Diagnostic::ALL[..], Some(diag.selected), |x| {
+        Protocol::DiagnosticsCommand(DiagnosticsCommand::Select(x))
+    })
+    .width(280.0)]
+    .spacing(10.0)
+    .align_items(Alignment::Center);
+    if diag.selected == Diagnostic::ReturnQueryData {
+        controls = controls.push(text("Data")).push(
+            text_input("A5 37", &diag.echo, |x| {
+                Protocol::DiagnosticsCommand(DiagnosticsCommand::SetEcho(x))
+            })
+            .width(150.0),
+        );
+    }
+    controls = controls
+        .push(send)
+        .push(counters)
+        .push(button("Clear").on_press(Protocol::DiagnosticsCommand(DiagnosticsCommand::Clear)))
+        .push(text(&diag.status));
+
+    let mut c = column![controls].spacing(10.0).padding(10.0);
+    if let ConnectionParams::Tcp(_) = params {
+        c = c.push(text(
+            "FC 0x08 is a serial line function, TCP devices may not answer it",
+        ));
+    }
+    c.push(
+        row![
+            text("Time").width(100.0),
+            text("Diagnostic").width(250.0),
+            text("Result").width(300.0),
+        ]
+        .spacing(5.0),
+    )
+    .push(Column::with_children(diag.results.iter().map(result_row).collect()).spacing(5.0))
+    .align_items(Alignment::Center)
+    .into()
+}
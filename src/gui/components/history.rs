@@ -0,0 +1,249 @@
+use crate::controller::Transaction;
+use crate::gui::components::request::{get_address, request_data, response_data, RequestType};
+use crate::gui::protocol::Protocol;
+use crate::gui::style::ButtonType;
+use iced::widget::{button, column, row, scrollable, text, text_input, tooltip, Column};
+use iced::{Alignment, Color, Element, Length};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use tokio_modbus::prelude::Request;
+
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Clone)]
+pub enum HistoryCommand {
+    Load(usize),
+    Rerun(usize),
+    Clear,
+    SetLimit(usize),
+    ExportDialog,
+    Export(PathBuf),
+    None,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RequestHistory {
+    #[serde(skip)]
+    entries: VecDeque<Transaction>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+fn default_limit() -> usize {
+    DEFAULT_HISTORY_LIMIT
+}
+
+impl Default for RequestHistory {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+}
+
+impl RequestHistory {
+    pub fn push(&mut self, transaction: Transaction) {
+        self.entries.push_front(transaction);
+        self.entries.truncate(self.limit);
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&Transaction> {
+        self.entries.get(idx)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit.max(1);
+        self.entries.truncate(self.limit);
+    }
+
+    pub fn export_csv(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(file, "timestamp,unit,request,address,data,result,error,rtt_ms")?;
+        // Oldest first so the file reads in the order the requests were sent.
+        for tx in self.entries.iter().rev() {
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{},{:.3}",
+                tx.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+                tx.unit.map(|x| x.to_string()).unwrap_or_default(),
+                csv_field(&RequestType::from(&tx.request).to_string()),
+                address_text(tx),
+                csv_field(&request_data(&tx.request)),
+                csv_field(&result_text(tx)),
+                tx.result.as_ref().err().map(|e| e.kind()).unwrap_or_default(),
+                tx.rtt.as_secs_f64() * 1000.0
+            )?;
+        }
+        file.flush()
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+fn address_text(tx: &Transaction) -> String {
+    match &tx.request {
+        Request::Custom(..) => String::new(),
+        req => get_address(req).to_string(),
+    }
+}
+
+fn result_text(tx: &Transaction) -> String {
+    match &tx.result {
+        Ok(resp) => response_data(resp),
+        Err(e) => e.to_string(),
+    }
+}
+
+fn history_row(idx: usize, tx: &Transaction) -> Element<Protocol> {
+    let result = match &tx.result {
+        Ok(_) => text(result_text(tx)).width(200.0),
+        Err(_) => text(result_text(tx))
+            .width(200.0)
+            .style(iced::theme::Text::Color(Color::from_rgb8(248, 113, 113))),
+    };
+    row![
+        tooltip(
+            button(
+                row![
+                    text(tx.timestamp.format("%H:%M:%S%.3f")).width(100.0),
+                    text(RequestType::from(&tx.request).to_string()).width(150.0),
+                    text(tx.unit.map(|x| x.to_string()).unwrap_or_default()).width(40.0),
+                    text(address_text(tx)).width(60.0),
+                    text(request_data(&tx.request)).width(150.0),
+                    result,
+                    text(format!("{} ms", tx.rtt.as_millis())).width(60.0),
+                ]
+                .spacing(5.0)
+            )
+            .on_press(Protocol::HistoryCommand(HistoryCommand::Load(idx)))
+            .style(ButtonType::Image.into()),
+            "Click to Load",
+            tooltip::Position::FollowCursor
+        ),
+        button("Re-run").on_press(Protocol::HistoryCommand(HistoryCommand::Rerun(idx))),
+    ]
+    .spacing(5.0)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+pub fn history(history: &RequestHistory) -> Element<Protocol> {
+    let requests = Column::with_children(
+        history
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(idx, tx)| history_row(idx, tx))
+            .collect(),
+    );
+    column![
+        row![
+            text("Request History"),
+            text("Keep"),
+            text_input("100", &history.limit.to_string(), |x| {
+                match x.parse::<usize>() {
+                    Ok(n) => Protocol::HistoryCommand(HistoryCommand::SetLimit(n)),
+                    Err(_e) => Protocol::HistoryCommand(HistoryCommand::None),
+                }
+            })
+            .width(60.0),
+            button("Export").on_press(Protocol::HistoryCommand(HistoryCommand::ExportDialog)),
+            button("Clear").on_press(Protocol::HistoryCommand(HistoryCommand::Clear)),
+        ]
+        .spacing(10.0)
+        .align_items(Alignment::Center),
+        row![
+            text("Time").width(100.0),
+            text("Request Type").width(150.0),
+            text("Unit").width(40.0),
+            text("Address").width(60.0),
+            text("Data").width(150.0),
+            text("Result").width(200.0),
+            text("RTT").width(60.0),
+        ]
+        .spacing(5.0),
+        scrollable(requests).height(Length::Fill).vertical_scroll(
+            iced::widget::scrollable::Properties::new()
+                .width(1.0)
+                .margin(1.0)
+                .scroller_width(1.0),
+        )
+    ]
+    .spacing(10.0)
+    .height(200)
+    .width(Length::Fill)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustbus_core::error::ModbusError;
+    use std::time::Duration;
+    use tokio_modbus::prelude::Response;
+
+    fn transaction(address: u16, result: Result<Response, ModbusError>) -> Transaction {
+        Transaction {
+            request: Request::ReadHoldingRegisters(address, 1),
+            unit: None,
+            slave: 1,
+            timestamp: chrono::Local::now(),
+            rtt: Duration::from_micros(1500),
+            result,
+        }
+    }
+
+    fn addresses(history: &RequestHistory) -> Vec<u16> {
+        history.entries.iter().map(|x| get_address(&x.request)).collect()
+    }
+
+    #[test]
+    fn newest_first_and_capped() {
+        let mut history = RequestHistory::default();
+        history.set_limit(3);
+        for address in 0..5 {
+            history.push(transaction(address, Ok(Response::ReadHoldingRegisters(vec![0]))));
+        }
+        assert_eq!(addresses(&history), vec![4, 3, 2]);
+        history.set_limit(2);
+        assert_eq!(addresses(&history), vec![4, 3]);
+        history.set_limit(0);
+        assert_eq!(history.limit(), 1);
+        assert_eq!(addresses(&history), vec![4]);
+    }
+
+    #[test]
+    fn csv_fields_escape_quotes() {
+        assert_eq!(csv_field("plain"), "\"plain\"");
+        assert_eq!(csv_field("a \"b\", c"), "\"a \"\"b\"\", c\"");
+    }
+
+    #[test]
+    fn export_writes_oldest_first() {
+        let mut history = RequestHistory::default();
+        history.push(transaction(7, Ok(Response::ReadHoldingRegisters(vec![42]))));
+        history.push(transaction(8, Err(ModbusError::Io("bad \"frame\", again".to_string()))));
+        let path = std::env::temp_dir().join(format!("rustbus-history-{}.csv", std::process::id()));
+        history.export_csv(&path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,unit,request,address,data,result,error,rtt_ms");
+        assert!(lines[1].ends_with(",,\"Read Holding Registers (0x03)\",7,\"1\",\"42\",,1.500"));
+        assert!(lines[2].ends_with(",\"I/O error: bad \"\"frame\"\", again\",I/O,1.500"));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::data_type::ByteOrder;
+use crate::gui::components::table::Table;
+use crate::gui::protocol::Protocol;
+use iced::widget::{button, pick_list, row, text, text_input};
+use iced::{Alignment, Element};
+use rustbus_core::logger::{DataLogger, LogConfig, LogValues, Rotation};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum LoggerCommand {
+    SetDirectory(PathBuf),
+    DirectoryDialog,
+    SetPrefix(String),
+    SetValues(LogValues),
+    SetRotation(Rotation),
+    SetMaxSize(u64),
+    SetMaxAge(Duration),
+    Start,
+    Stop,
+    None,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct Logger {
+    #[serde(default)]
+    config: LogConfig,
+    #[serde(skip)]
+    active: Option<Arc<Mutex<DataLogger>>>,
+    #[serde(skip)]
+    status: String,
+}
+
+impl Logger {
+    pub fn is_running(&self) -> bool {
+        self.active.is_some()
+    }
+
+    pub fn handle_command(&mut self, lc: LoggerCommand, table: &Table) {
+        match lc {
+            LoggerCommand::SetDirectory(dir) if !self.is_running() => self.config.directory = dir,
+            LoggerCommand::SetPrefix(prefix) if !self.is_running() => self.config.prefix = prefix,
+            LoggerCommand::SetValues(values) if !self.is_running() => self.config.values = values,
+            LoggerCommand::SetRotation(rotation) if !self.is_running() => {
+                self.config.rotation = rotation
+            }
+            LoggerCommand::SetMaxSize(kb) if !self.is_running() => self.config.max_size_kb = kb,
+            LoggerCommand::SetMaxAge(age) if !self.is_running() => self.config.max_age = age,
+            LoggerCommand::Start if !self.is_running() => {
+                match DataLogger::start(self.config.clone(), table) {
+                    Ok(logger) => {
+                        self.status = format!("Logging to {}", logger.path().display());
+                        self.active = Some(Arc::new(Mutex::new(logger)));
+                    }
+                    Err(e) => self.status = e.to_string(),
+                }
+            }
+            LoggerCommand::Stop => {
+                if let Some(logger) = self.active.take() {
+                    let logger = logger.lock().unwrap();
+                    self.status = format!("Stopped, {} rows logged", logger.rows());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn log(&mut self, table: &Table, default_order: ByteOrder) {
+        let result = match &self.active {
+            Some(logger) => logger.lock().unwrap().log(table, default_order),
+            None => return,
+        };
+        if let Err(e) = result {
+            self.active = None;
+            self.status = format!("Logging stopped: {}", e);
+        }
+    }
+
+    fn status(&self) -> String {
+        match &self.active {
+            Some(logger) => {
+                let logger = logger.lock().unwrap();
+                format!("{} rows to {}", logger.rows(), logger.path().display())
+            }
+            None => self.status.clone(),
+        }
+    }
+}
+
+pub fn logger(logger: &Logger) -> Element<Protocol> {
+    let config = &logger.config;
+    let btn = if logger.is_running() {
+        button("Stop Log").on_press(Protocol::LoggerCommand(LoggerCommand::Stop))
+    } else {
+        button("Start Log").on_press(Protocol::LoggerCommand(LoggerCommand::Start))
+    };
+    let limit: Element<Protocol> = match config.rotation {
+        Rotation::Never => text("").into(),
+        Rotation::Size => row![
+            text_input("1024", &config.max_size_kb.to_string(), |x| {
+                match x.parse::<u64>() {
+                    Ok(n) => Protocol::LoggerCommand(LoggerCommand::SetMaxSize(n.max(1))),
+                    Err(_e) => Protocol::LoggerCommand(LoggerCommand::None),
+                }
+            })
+            .width(70.0),
+            text("KB"),
+        ]
+        .spacing(5.0)
+        .align_items(Alignment::Center)
+        .into(),
+        Rotation::Time => row![
+            text_input(
+                "60",
+                &(config.max_age.as_secs() / 60).to_string(),
+                |x| match x.parse::<u64>() {
+                    Ok(n) => Protocol::LoggerCommand(LoggerCommand::SetMaxAge(
+                        Duration::from_secs(n.max(1) * 60)
+                    )),
+                    Err(_e) => Protocol::LoggerCommand(LoggerCommand::None),
+                }
+            )
+            .width(70.0),
+            text("min"),
+        ]
+        .spacing(5.0)
+        .align_items(Alignment::Center)
+        .into(),
+    };
+    row![
+        text("Log to"),
+        text_input("Directory", &config.directory.to_string_lossy(), |x| {
+            Protocol::LoggerCommand(LoggerCommand::SetDirectory(PathBuf::from(x)))
+        })
+        .width(200.0),
+        button("...").on_press(Protocol::LoggerCommand(LoggerCommand::DirectoryDialog)),
+        text_input("File prefix", &config.prefix, |x| {
+            Protocol::LoggerCommand(LoggerCommand::SetPrefix(x))
+        })
+        .width(100.0),
+        pick_list(&LogValues::ALL[..], Some(config.values), |x| {
+            Protocol::LoggerCommand(LoggerCommand::SetValues(x))
+        })
+        .width(90.0),
+        pick_list(&Rotation::ALL[..], Some(config.rotation), |x| {
+            Protocol::LoggerCommand(LoggerCommand::SetRotation(x))
+        })
+        .width(140.0),
+        limit,
+        btn,
+        text(logger.status()),
+    ]
+    .spacing(10.0)
+    .padding(10.0)
+    .align_items(Alignment::Center)
+    .into()
+}
@@ -1,4 +1,12 @@
+pub mod action;
 pub mod common;
 pub mod connection;
+pub mod device_info;
+pub mod diagnostics;
+pub mod history;
+pub mod logger;
 pub mod request;
+pub mod script;
+pub mod simulator;
 pub mod table;
+pub mod trend;
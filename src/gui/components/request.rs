@@ -1,15 +1,32 @@
-use crate::gui::components::common::ContainerStyle;
+use crate::controller::Transaction;
+use crate::data_type::{
+    format_u16, parse_bool, parse_u16, parse_unsigned, ByteOrder, DataType, DisplayFormat,
+};
+use crate::gui::components::common::{leds, ContainerStyle};
 use crate::gui::protocol::Protocol;
 use crate::gui::style::ButtonType;
-use iced::widget::{self, button, pick_list, text_input, Container, Row, image, image::Handle};
-use iced::widget::{column, row, text};
+use crate::transport::{hex_string, parse_hex_bytes};
+use bytes::Bytes;
+use iced::widget::{self, button, pick_list, text_input, Container, image, image::Handle};
+use iced::widget::{column, row, text, Column};
 use iced::Renderer;
-use iced::{Alignment, Color, Element};
-use iced_aw::native::wrap::Wrap;
+use iced::{Alignment, Color, Element, Length};
+use iced_aw::native::wrap::{direction, Wrap};
 use iced_lazy::Component;
 use iced_native;
+use rustbus_core::error::ModbusError;
+use rustbus_core::file_record::{self, FileRecord};
+use std::path::{Path, PathBuf};
 use tokio_modbus::prelude::{Request, Response};
 
+pub use rustbus_core::request::{
+    deserialize_request, get_address, request_data, response_data, serialize_request, RequestType,
+};
+use rustbus_core::request::{
+    MAX_READ_BITS, MAX_READ_REGISTERS, MAX_READ_WRITE_REGISTERS, MAX_WRITE_COILS,
+    MAX_WRITE_REGISTERS,
+};
+
 const PANEL_WIDTH: f32 = 500.0;
 
 pub struct RequestComponent<Message> {
@@ -22,8 +39,14 @@ pub struct RequestParams {
     pub request: Request,
     pub request_type: RequestType,
     pub response: Option<Response>,
+    // Why the last execution failed, cleared by the next response.
+    pub error: Option<ModbusError>,
     pub poll: std::time::Duration,
-    pub polling: bool
+    pub polling: bool,
+    pub unit: Option<u8>,
+    pub payload_text: String,
+    pub data_type: DataType,
+    pub display_format: DisplayFormat,
 }
 
 #[derive(Debug, Clone)]
@@ -34,18 +57,149 @@ pub enum RequestUpdate {
     Request(Request),
     UpdateVecU16(usize, u16),
     UpdateVecBool(usize, bool),
-    SetPoll(std::time::Duration)
+    SetPoll(std::time::Duration),
+    SetUnit(Option<u8>),
+    SetPayload(String),
+    SetDataType(DataType),
+    SetDisplayFormat(DisplayFormat),
+    SetFileRecord(FileRecord),
+    ExportDialog,
+    Export(PathBuf),
+}
+
+// Register responses are decoded as the selected data type in the connection's
+// byte order.
+fn register_values<'a>(
+    words: &[u16],
+    params: &RequestParams,
+    order: ByteOrder,
+) -> Wrap<'a, Protocol, Renderer, direction::Horizontal> {
+    let data_type = params.data_type;
+    match params.display_format {
+        DisplayFormat::Led => Wrap::with_elements(
+            words
+                .chunks(usize::from(data_type.register_count(1)))
+                .map(|x| leds(&data_type.bits(x, order), PANEL_WIDTH, None))
+                .collect(),
+        )
+        .spacing(10.0)
+        .line_spacing(5.0),
+        format => Wrap::with_elements(
+            data_type
+                .format_all(words, order, format)
+                .into_iter()
+                .map(|x| response_data_box(text(x)).into())
+                .collect(),
+        ),
+    }
+}
+
+fn bit_values<'a>(
+    bits: &[bool],
+    format: DisplayFormat,
+) -> Wrap<'a, Protocol, Renderer, direction::Horizontal> {
+    match format {
+        DisplayFormat::Led => Wrap::with_elements(vec![leds(bits, PANEL_WIDTH, None)]),
+        _ => Wrap::with_elements(
+            bits.iter()
+                .map(|x| response_data_box(text(u8::from(*x))).into())
+                .collect(),
+        ),
+    }
+}
+
+fn value_inputs<'a>(
+    vals: &[u16],
+    format: DisplayFormat,
+) -> Wrap<'a, Protocol, Renderer, direction::Horizontal> {
+    Wrap::with_elements(
+        vals.iter()
+            .enumerate()
+            .map(|(i, x)| {
+                text_input("", &format_u16(*x, format), move |z| {
+                    let parsed = parse_u16(&z);
+                    match parsed {
+                        Some(new_val) => {
+                            Protocol::RequestUpdate(RequestUpdate::UpdateVecU16(i, new_val))
+                        }
+                        None => Protocol::None,
+                    }
+                })
+                .width(50.0)
+                .into()
+            })
+            .collect(),
+    )
+}
+
+fn error_text<'a>(e: String) -> Element<'a, Protocol> {
+    text(e)
+        .style(iced::theme::Text::Color(Color::from_rgb8(248, 113, 113)))
+        .into()
 }
 
-fn response_or_request(request_paramters: &RequestParams) -> Element<Protocol> {
+// File records and FIFO queues travel as custom PDUs, their mode decides how
+// they are shown.
+fn file_record_response(request_params: &RequestParams, order: ByteOrder) -> Element<Protocol> {
+    let rec = FileRecord::from_request(&request_params.request).unwrap_or_default();
+    match request_params.request_type {
+        RequestType::WriteFileRecord => value_inputs(&rec.values, request_params.display_format)
+            .max_width(PANEL_WIDTH)
+            .into(),
+        RequestType::ReadFifoQueue => match &request_params.response {
+            Some(resp) => match file_record::parse_fifo_response(resp) {
+                Ok(vals) => column![
+                    text(format!("{} queued", vals.len())),
+                    Column::with_children(
+                        vals.iter()
+                            .enumerate()
+                            .map(|(i, x)| {
+                                row![
+                                    text(i).width(50.0),
+                                    text(format_u16(*x, request_params.display_format)),
+                                ]
+                                .spacing(5.0)
+                                .into()
+                            })
+                            .collect(),
+                    )
+                    .spacing(5.0),
+                ]
+                .spacing(5.0)
+                .into(),
+                Err(e) => error_text(e),
+            },
+            None => text("").into(),
+        },
+        _ => match &request_params.response {
+            Some(resp) => match file_record::parse_read_response(resp) {
+                Ok(groups) => register_values(&groups.concat(), request_params, order)
+                    .max_width(PANEL_WIDTH)
+                    .into(),
+                Err(e) => error_text(e),
+            },
+            None => Wrap::with_elements(
+                (0..rec.length)
+                    .map(|_x| response_data_box(text("")).into())
+                    .collect(),
+            )
+            .max_width(PANEL_WIDTH)
+            .into(),
+        },
+    }
+}
+
+fn response_or_request(request_paramters: &RequestParams, order: ByteOrder) -> Element<Protocol> {
+    match request_paramters.request_type {
+        RequestType::ReadFileRecord | RequestType::WriteFileRecord | RequestType::ReadFifoQueue => {
+            return file_record_response(request_paramters, order)
+        }
+        _ => (),
+    }
     match &request_paramters.request {
         Request::ReadCoils(_addr, val) => {
             if let Some(Response::ReadCoils(v)) = &request_paramters.response {
-                Wrap::with_elements(
-                    v.iter()
-                        .map(|x| response_data_box(text(x.to_string())).into())
-                        .collect(),
-                )
+                bit_values(v, request_paramters.display_format)
                 .max_width(PANEL_WIDTH)
                 .into()
             } else {
@@ -60,12 +214,7 @@ fn response_or_request(request_paramters: &RequestParams) -> Element<Protocol> {
         }
         Request::ReadDiscreteInputs(_addr, val) => {
             if let Some(Response::ReadDiscreteInputs(v)) = &request_paramters.response {
-                Wrap::with_elements(
-                    v.iter()
-                        .map(|x| response_data_box(text(x.to_string())).into())
-                        .collect(),
-                )
-                .into()
+                bit_values(v, request_paramters.display_format).into()
             } else {
                 Wrap::with_elements(
                     (0..*val)
@@ -81,12 +230,12 @@ fn response_or_request(request_paramters: &RequestParams) -> Element<Protocol> {
                 .enumerate()
                 .map(|(i, x)| {
                     text_input("", &x.to_string(), move |z| {
-                        let parsed = z.parse::<bool>();
+                        let parsed = parse_bool(&z);
                         match parsed {
-                            Ok(new_val) => {
+                            Some(new_val) => {
                                 Protocol::RequestUpdate(RequestUpdate::UpdateVecBool(i, new_val))
                             }
-                            Err(_e) => Protocol::None,
+                            None => Protocol::None,
                         }
                     })
                     .width(50.0)
@@ -97,12 +246,7 @@ fn response_or_request(request_paramters: &RequestParams) -> Element<Protocol> {
         .into(),
         Request::ReadInputRegisters(_addr, val) => {
             if let Some(Response::ReadInputRegisters(v)) = &request_paramters.response {
-                Wrap::with_elements(
-                    v.iter()
-                        .map(|x| response_data_box(text(x.to_string())).into())
-                        .collect(),
-                )
-                .into()
+                register_values(v, request_paramters, order).into()
             } else {
                 Wrap::with_elements(
                     (0..*val)
@@ -114,12 +258,7 @@ fn response_or_request(request_paramters: &RequestParams) -> Element<Protocol> {
         }
         Request::ReadHoldingRegisters(_addr, val) => {
             if let Some(Response::ReadHoldingRegisters(v)) = &request_paramters.response {
-                Wrap::with_elements(
-                    v.iter()
-                        .map(|x| response_data_box(text(x.to_string())).into())
-                        .collect(),
-                )
-                .into()
+                register_values(v, request_paramters, order).into()
             } else {
                 Wrap::with_elements(
                     (0..*val)
@@ -134,13 +273,13 @@ fn response_or_request(request_paramters: &RequestParams) -> Element<Protocol> {
             vals.iter()
                 .enumerate()
                 .map(|(i, x)| {
-                    text_input("", &x.to_string(), move |z| {
-                        let parsed = z.parse::<u16>();
+                    text_input("", &format_u16(*x, request_paramters.display_format), move |z| {
+                        let parsed = parse_u16(&z);
                         match parsed {
-                            Ok(new_val) => {
+                            Some(new_val) => {
                                 Protocol::RequestUpdate(RequestUpdate::UpdateVecU16(i, new_val))
                             }
-                            Err(_e) => Protocol::None,
+                            None => Protocol::None,
                         }
                     })
                     .width(50.0)
@@ -149,33 +288,123 @@ fn response_or_request(request_paramters: &RequestParams) -> Element<Protocol> {
                 .collect(),
         )
         .into(),
+        Request::MaskWriteRegister(_addr, _and_mask, _or_mask) => {
+            if let Some(Response::MaskWriteRegister(addr, and_mask, or_mask)) =
+                &request_paramters.response
+            {
+                text(format!(
+                    "Register {} masked: AND 0x{:04X} OR 0x{:04X}",
+                    addr, and_mask, or_mask
+                ))
+                .into()
+            } else {
+                text("").into()
+            }
+        }
+        Request::ReadWriteMultipleRegisters(_read_addr, read_qty, _write_addr, vals) => {
+            let write_values = Wrap::with_elements(
+                vals.iter()
+                    .enumerate()
+                    .map(|(i, x)| {
+                        text_input("", &format_u16(*x, request_paramters.display_format), move |z| {
+                            let parsed = parse_u16(&z);
+                            match parsed {
+                                Some(new_val) => Protocol::RequestUpdate(
+                                    RequestUpdate::UpdateVecU16(i, new_val),
+                                ),
+                                None => Protocol::None,
+                            }
+                        })
+                        .width(50.0)
+                        .into()
+                    })
+                    .collect(),
+            )
+            .max_width(PANEL_WIDTH);
+            let read_values = if let Some(Response::ReadWriteMultipleRegisters(v)) =
+                &request_paramters.response
+            {
+                register_values(v, request_paramters, order)
+            } else {
+                Wrap::with_elements(
+                    (0..*read_qty)
+                        .map(|_x| response_data_box(text("")).into())
+                        .collect(),
+                )
+            }
+            .max_width(PANEL_WIDTH);
+            column!["Write", write_values, "Read", read_values]
+                .spacing(5.0)
+                .into()
+        }
+        Request::Custom(_function, _data) => {
+            if let Some(resp) = &request_paramters.response {
+                let pdu = Bytes::from(resp.clone());
+                response_data_box(text(hex_string(&pdu)))
+                    .width(PANEL_WIDTH)
+                    .height(Length::Shrink)
+                    .padding(5.0)
+                    .into()
+            } else {
+                text("").into()
+            }
+        }
         _ => row![text("Unsupported Code")].into(),
     }
 }
 
-pub fn get_address(req: &Request) -> u16 {
-    match req {
-        Request::ReadCoils(addr, _val) => *addr,
-        Request::ReadDiscreteInputs(addr, _val) => *addr,
-        Request::WriteSingleCoil(addr, _val) => *addr,
-        Request::WriteMultipleCoils(addr, _val) => *addr,
-        Request::ReadInputRegisters(addr, _val) => *addr,
-        Request::ReadHoldingRegisters(addr, _val) => *addr,
-        Request::WriteSingleRegister(addr, _val) => *addr,
-        Request::WriteMultipleRegisters(addr, _val) => *addr,
-        _ => unreachable!(),
-    }
+fn file_record_editor(request_params: &RequestParams) -> Element<Protocol> {
+    let rec = FileRecord::from_request(&request_params.request).unwrap_or_default();
+    let write = request_params.request_type == RequestType::WriteFileRecord;
+    let length = if write {
+        rec.values.len() as u16
+    } else {
+        rec.length
+    };
+    let field = |label, value: u16, set: fn(&mut FileRecord, u16)| {
+        let rec = rec.clone();
+        column![
+            label,
+            text_input("", &value.to_string(), move |x| match parse_unsigned(&x) {
+                Some(new_val) => {
+                    let mut rec = rec.clone();
+                    set(&mut rec, new_val);
+                    Protocol::RequestUpdate(RequestUpdate::SetFileRecord(rec))
+                }
+                None => Protocol::None,
+            })
+        ]
+        .align_items(Alignment::Center)
+        .width(100.0)
+    };
+    row![
+        field("File", rec.file, |rec, x| rec.file = x),
+        field("Record", rec.record, |rec, x| rec.record = x.min(file_record::MAX_RECORD)),
+        field("Length", length, |rec, x| {
+            rec.length = x.min(file_record::MAX_RECORD_LENGTH);
+            rec.values.resize(rec.length.into(), 0);
+        }),
+    ]
+    .spacing(10.0)
+    .into()
 }
 
-fn get_value(req: &Request) -> Element<Protocol> {
-    match req {
+fn get_value(request_params: &RequestParams) -> Element<Protocol> {
+    match request_params.request_type {
+        RequestType::ReadFileRecord | RequestType::WriteFileRecord => {
+            return file_record_editor(request_params)
+        }
+        RequestType::ReadFifoQueue => return column![].into(),
+        _ => (),
+    }
+    match &request_params.request {
         Request::ReadCoils(addr, val) => column![
             "Coils",
             text_input("", &val.to_string(), |x| {
-                let parsed = x.parse::<u16>();
+                let parsed = parse_unsigned(&x).map(|x| x.min(MAX_READ_BITS));
                 match parsed {
-                    Ok(new_val) => Protocol::ReqChanged(Request::ReadCoils(*addr, new_val)),
-                    Err(_e) => Protocol::None,
+                    Some(new_val) => Protocol::ReqChanged(Request::ReadCoils(*addr, new_val)),
+                    None => Protocol::None,
                 }
             })
         ]
@@ -185,12 +414,12 @@ fn get_value(req: &Request) -> Element<Protocol> {
         Request::ReadDiscreteInputs(addr, val) => column![
             "Inputs",
             text_input("", &val.to_string(), |x| {
-                let parsed = x.parse::<u16>();
+                let parsed = parse_unsigned(&x).map(|x| x.min(MAX_READ_BITS));
                 match parsed {
-                    Ok(new_val) => {
+                    Some(new_val) => {
                         Protocol::ReqChanged(Request::ReadDiscreteInputs(*addr, new_val))
                     }
-                    Err(_e) => Protocol::None,
+                    None => Protocol::None,
                 }
             })
         ]
@@ -200,10 +429,10 @@ fn get_value(req: &Request) -> Element<Protocol> {
         Request::WriteSingleCoil(addr, val) => column![
             "Value",
             text_input("", &val.to_string(), |x| {
-                let parsed = x.parse::<bool>();
+                let parsed = parse_bool(&x);
                 match parsed {
-                    Ok(new_val) => Protocol::ReqChanged(Request::WriteSingleCoil(*addr, new_val)),
-                    Err(_e) => Protocol::None,
+                    Some(new_val) => Protocol::ReqChanged(Request::WriteSingleCoil(*addr, new_val)),
+                    None => Protocol::None,
                 }
             })
         ]
@@ -213,14 +442,14 @@ fn get_value(req: &Request) -> Element<Protocol> {
         Request::WriteMultipleCoils(addr, val) => column![
             "Coils",
             text_input("", &val.len().to_string(), |x| {
-                let parsed = x.parse::<u16>();
+                let parsed = parse_unsigned(&x).map(|x| x.min(MAX_WRITE_COILS));
                 match parsed {
-                    Ok(new_val) => {
+                    Some(new_val) => {
                         let mut vec_copy = val.clone();
                         vec_copy.resize(new_val.into(), false);
                         Protocol::ReqChanged(Request::WriteMultipleCoils(*addr, vec_copy))
                     }
-                    Err(_e) => Protocol::None,
+                    None => Protocol::None,
                 }
             })
         ]
@@ -230,12 +459,12 @@ fn get_value(req: &Request) -> Element<Protocol> {
         Request::ReadInputRegisters(addr, val) => column![
             "Registers",
             text_input("", &val.to_string(), |x| {
-                let parsed = x.parse::<u16>();
+                let parsed = parse_unsigned(&x).map(|x| x.min(MAX_READ_REGISTERS));
                 match parsed {
-                    Ok(new_val) => {
+                    Some(new_val) => {
                         Protocol::ReqChanged(Request::ReadInputRegisters(*addr, new_val))
                     }
-                    Err(_e) => Protocol::None,
+                    None => Protocol::None,
                 }
             })
         ]
@@ -245,12 +474,12 @@ fn get_value(req: &Request) -> Element<Protocol> {
         Request::ReadHoldingRegisters(addr, val) => column![
             "Registers",
             text_input("", &val.to_string(), |x| {
-                let parsed = x.parse::<u16>();
+                let parsed = parse_unsigned(&x).map(|x| x.min(MAX_READ_REGISTERS));
                 match parsed {
-                    Ok(new_val) => {
+                    Some(new_val) => {
                         Protocol::ReqChanged(Request::ReadHoldingRegisters(*addr, new_val))
                     }
-                    Err(_e) => Protocol::None,
+                    None => Protocol::None,
                 }
             })
         ]
@@ -259,13 +488,13 @@ fn get_value(req: &Request) -> Element<Protocol> {
         .into(),
         Request::WriteSingleRegister(addr, val) => column![
             "Value",
-            text_input("", &val.to_string(), |x| {
-                let parsed = x.parse::<u16>();
+            text_input("", &format_u16(*val, request_params.display_format), |x| {
+                let parsed = parse_u16(&x);
                 match parsed {
-                    Ok(new_val) => {
+                    Some(new_val) => {
                         Protocol::ReqChanged(Request::WriteSingleRegister(*addr, new_val))
                     }
-                    Err(_e) => Protocol::None,
+                    None => Protocol::None,
                 }
             })
         ]
@@ -275,20 +504,134 @@ fn get_value(req: &Request) -> Element<Protocol> {
         Request::WriteMultipleRegisters(addr, val) => column![
             "Registers",
             text_input("", &val.len().to_string(), |x| {
-                let parsed = x.parse::<u16>();
+                let parsed = parse_unsigned(&x).map(|x| x.min(MAX_WRITE_REGISTERS));
                 match parsed {
-                    Ok(new_val) => {
+                    Some(new_val) => {
                         let mut vec_copy = val.clone();
                         vec_copy.resize(new_val.into(), 0);
                         Protocol::ReqChanged(Request::WriteMultipleRegisters(*addr, vec_copy))
                     }
-                    Err(_e) => Protocol::None,
+                    None => Protocol::None,
                 }
             })
         ]
         .align_items(Alignment::Center)
         .width(100.0)
         .into(),
+        Request::MaskWriteRegister(addr, and_mask, or_mask) => row![
+            column![
+                "AND Mask",
+                text_input("", &format_u16(*and_mask, request_params.display_format), |x| {
+                    let parsed = parse_u16(&x);
+                    match parsed {
+                        Some(new_val) => Protocol::ReqChanged(Request::MaskWriteRegister(
+                            *addr, new_val, *or_mask,
+                        )),
+                        None => Protocol::None,
+                    }
+                })
+            ]
+            .align_items(Alignment::Center)
+            .width(100.0),
+            column![
+                "OR Mask",
+                text_input("", &format_u16(*or_mask, request_params.display_format), |x| {
+                    let parsed = parse_u16(&x);
+                    match parsed {
+                        Some(new_val) => Protocol::ReqChanged(Request::MaskWriteRegister(
+                            *addr, *and_mask, new_val,
+                        )),
+                        None => Protocol::None,
+                    }
+                })
+            ]
+            .align_items(Alignment::Center)
+            .width(100.0)
+        ]
+        .spacing(10.0)
+        .into(),
+        Request::ReadWriteMultipleRegisters(read_addr, read_qty, write_addr, vals) => row![
+            column![
+                "Read Regs",
+                text_input("", &read_qty.to_string(), |x| {
+                    let parsed = parse_unsigned(&x).map(|x| x.min(MAX_READ_REGISTERS));
+                    match parsed {
+                        Some(new_val) => Protocol::ReqChanged(Request::ReadWriteMultipleRegisters(
+                            *read_addr,
+                            new_val,
+                            *write_addr,
+                            vals.clone(),
+                        )),
+                        None => Protocol::None,
+                    }
+                })
+            ]
+            .align_items(Alignment::Center)
+            .width(100.0),
+            column![
+                "Write Address",
+                text_input("", &write_addr.to_string(), |x| {
+                    let parsed = parse_unsigned(&x);
+                    match parsed {
+                        Some(new_val) => Protocol::ReqChanged(Request::ReadWriteMultipleRegisters(
+                            *read_addr,
+                            *read_qty,
+                            new_val,
+                            vals.clone(),
+                        )),
+                        None => Protocol::None,
+                    }
+                })
+            ]
+            .align_items(Alignment::Center)
+            .width(100.0),
+            column![
+                "Write Regs",
+                text_input("", &vals.len().to_string(), |x| {
+                    let parsed = parse_unsigned(&x).map(|x| x.min(MAX_READ_WRITE_REGISTERS));
+                    match parsed {
+                        Some(new_val) => {
+                            let mut vec_copy = vals.clone();
+                            vec_copy.resize(new_val.into(), 0);
+                            Protocol::ReqChanged(Request::ReadWriteMultipleRegisters(
+                                *read_addr, *read_qty, *write_addr, vec_copy,
+                            ))
+                        }
+                        None => Protocol::None,
+                    }
+                })
+            ]
+            .align_items(Alignment::Center)
+            .width(100.0)
+        ]
+        .spacing(10.0)
+        .into(),
+        Request::Custom(function, data) => row![
+            column![
+                "Function (hex)",
+                text_input("41", &format!("{:02X}", function), |x| {
+                    let parsed = u8::from_str_radix(x.trim_start_matches("0x"), 16);
+                    match parsed {
+                        Ok(new_val) if new_val > 0 && new_val < 0x80 => {
+                            Protocol::ReqChanged(Request::Custom(new_val, data.clone()))
+                        }
+                        _ => Protocol::None,
+                    }
+                })
+            ]
+            .align_items(Alignment::Center)
+            .width(100.0),
+            column![
+                "Payload (hex)",
+                text_input("00 01 00 0A", &request_params.payload_text, |x| {
+                    Protocol::RequestUpdate(RequestUpdate::SetPayload(x))
+                })
+            ]
+            .align_items(Alignment::Center)
+            .width(200.0)
+        ]
+        .spacing(10.0)
+        .into(),
         _ => unreachable!(),
     }
 }
@@ -307,6 +650,9 @@ impl RequestParams {
                 let req = req_type.new_request();
                 self.request_type = req_type;
                 self.request = req;
+                self.response = None;
+                self.error = None;
+                self.payload_text = String::new();
             }
             RequestUpdate::SetAddress(new_addr) => match &mut self.request {
                 Request::ReadCoils(addr, _val) => *addr = new_addr,
@@ -317,6 +663,15 @@ impl RequestParams {
                 Request::ReadHoldingRegisters(addr, _val) => *addr = new_addr,
                 Request::WriteSingleRegister(addr, _val) => *addr = new_addr,
                 Request::WriteMultipleRegisters(addr, _val) => *addr = new_addr,
+                Request::MaskWriteRegister(addr, _and_mask, _or_mask) => *addr = new_addr,
+                Request::ReadWriteMultipleRegisters(addr, _qty, _write_addr, _vals) => {
+                    *addr = new_addr
+                }
+                Request::Custom(_function, _data) => {
+                    if self.request_type == RequestType::ReadFifoQueue {
+                        self.request = file_record::fifo_request(new_addr);
+                    }
+                }
                 _ => unreachable!(),
             },
             RequestUpdate::Request(req) => self.request = req,
@@ -325,6 +680,15 @@ impl RequestParams {
                     Request::WriteMultipleRegisters(_addr, vals) => {
                         vals.get_mut(idx).map(|x| *x = val);
                     }
+                    Request::ReadWriteMultipleRegisters(_addr, _qty, _write_addr, vals) => {
+                        vals.get_mut(idx).map(|x| *x = val);
+                    }
+                    Request::Custom(file_record::WRITE_FILE_RECORD, _data) => {
+                        if let Some(mut rec) = FileRecord::from_request(&self.request) {
+                            rec.values.get_mut(idx).map(|x| *x = val);
+                            self.request = rec.write_request();
+                        }
+                    }
                     _ => (),
                 };
             },
@@ -337,10 +701,46 @@ impl RequestParams {
             RequestUpdate::SetPoll(duration) => {
                 self.poll = duration
             },
+            RequestUpdate::SetUnit(unit) => {
+                self.unit = unit
+            },
+            RequestUpdate::SetPayload(payload) => {
+                if let (Request::Custom(_function, data), Some(parsed)) =
+                    (&mut self.request, parse_hex_bytes(&payload))
+                {
+                    *data = parsed;
+                }
+                self.payload_text = payload;
+            },
+            RequestUpdate::SetDataType(data_type) => {
+                self.data_type = data_type
+            },
+            RequestUpdate::SetDisplayFormat(format) => {
+                self.display_format = format
+            },
+            RequestUpdate::SetFileRecord(rec) => {
+                self.request = match self.request_type {
+                    RequestType::WriteFileRecord => rec.write_request(),
+                    _ => rec.read_request(),
+                }
+            },
+            // The app opens the dialog and does the writing.
+            RequestUpdate::ExportDialog | RequestUpdate::Export(_) => (),
         }
     }
 
-    pub fn view(&self) -> Element<Protocol> {
+    // Writes the last response's data, see `file_record::raw_bytes`.
+    pub fn export_raw(&self, path: &Path) -> std::io::Result<()> {
+        match &self.response {
+            Some(resp) => std::fs::write(path, file_record::raw_bytes(resp)),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "There is no response to export",
+            )),
+        }
+    }
+
+    pub fn view(&self, order: ByteOrder) -> Element<Protocol> {
         let poll_btn = if !self.polling {
            button(image(Handle::from_path("./resources/sync.png")).width(25.0))
             .on_press(Protocol::StartPoll)
@@ -350,6 +750,56 @@ impl RequestParams {
             .on_press(Protocol::StopPoll)
             .style(ButtonType::Image.into())
         };
+        let address: Element<Protocol> = match &self.request {
+            Request::Custom(_function, _data) if self.request_type != RequestType::ReadFifoQueue => {
+                column![].into()
+            }
+            _ => column![
+                "Address",
+                text_input("Address", &self.get_address().to_string(), |x| {
+                    let parsed = parse_unsigned(&x);
+                    match parsed {
+                        Some(new_addr) => {
+                            Protocol::RequestUpdate(RequestUpdate::SetAddress(new_addr))
+                        }
+                        None => Protocol::None,
+                    }
+                }),
+            ]
+            .align_items(Alignment::Center)
+            .width(100.0)
+            .into(),
+        };
+        let data_type: Element<Protocol> = match &self.request {
+            Request::ReadInputRegisters(..)
+            | Request::ReadHoldingRegisters(..)
+            | Request::ReadWriteMultipleRegisters(..)
+            | Request::Custom(file_record::READ_FILE_RECORD, _) => column![
+                "Data Type",
+                pick_list(&DataType::ALL[..], Some(self.data_type), |x| {
+                    Protocol::RequestUpdate(RequestUpdate::SetDataType(x))
+                })
+            ]
+            .align_items(Alignment::Center)
+            .into(),
+            _ => column![].into(),
+        };
+        let payload_error = self.payload_error();
+        let failure: Element<Protocol> = match (&payload_error, &self.error) {
+            (Some(e), _) => error_text(e.clone()),
+            (None, Some(e)) => error_text(e.to_string()),
+            (None, None) => column![].into(),
+        };
+        let execute = match payload_error {
+            Some(_) => button("Execute"),
+            None => button("Execute").on_press(Protocol::ExecuteRequest),
+        };
+        let export: Element<Protocol> = match &self.response {
+            Some(_) => button("Export Raw")
+                .on_press(Protocol::RequestUpdate(RequestUpdate::ExportDialog))
+                .into(),
+            None => column![].into(),
+        };
         Container::new(
             column![
                 row![
@@ -361,21 +811,30 @@ impl RequestParams {
                     ]
                     .align_items(Alignment::Center),
                     column![
-                        "Address",
-                        text_input("Address", &self.get_address().to_string(), |x| {
-                            let parsed = x.parse::<u16>();
-                            match parsed {
-                                Ok(new_addr) => {
-                                    Protocol::RequestUpdate(RequestUpdate::SetAddress(new_addr))
-                                }
+                        "Unit",
+                        text_input("default", &self.unit.map(|x| x.to_string()).unwrap_or_default(), |x| {
+                            if x.is_empty() {
+                                return Protocol::RequestUpdate(RequestUpdate::SetUnit(None));
+                            }
+                            match x.parse::<u8>() {
+                                Ok(n) => Protocol::RequestUpdate(RequestUpdate::SetUnit(Some(n))),
                                 Err(_e) => Protocol::None,
                             }
                         }),
                     ]
                     .align_items(Alignment::Center)
-                    .width(100.0),
-                    get_value(&self.request),
-                    button("Execute").on_press(Protocol::ExecuteRequest),
+                    .width(60.0),
+                    address,
+                    get_value(&self),
+                    data_type,
+                    column![
+                        "Format",
+                        pick_list(&DisplayFormat::ALL[..], Some(self.display_format), |x| {
+                            Protocol::RequestUpdate(RequestUpdate::SetDisplayFormat(x))
+                        })
+                    ]
+                    .align_items(Alignment::Center),
+                    execute,
                     column![
                         "Poll (ms)",
                         text_input("1000", &self.poll.as_millis().to_string(), |x| {
@@ -392,7 +851,9 @@ impl RequestParams {
                 ]
                 .spacing(10.0)
                 .align_items(Alignment::End),
-                response_or_request(&self)
+                failure,
+                response_or_request(&self, order),
+                export,
             ]
             .spacing(10.0),
         )
@@ -411,30 +872,39 @@ impl RequestParams {
             Request::ReadHoldingRegisters(addr, _val) => *addr,
             Request::WriteSingleRegister(addr, _val) => *addr,
             Request::WriteMultipleRegisters(addr, _val) => *addr,
+            Request::MaskWriteRegister(addr, _and_mask, _or_mask) => *addr,
+            Request::ReadWriteMultipleRegisters(addr, _qty, _write_addr, _vals) => *addr,
+            Request::Custom(_function, _data) => {
+                file_record::fifo_address(&self.request).unwrap_or(0)
+            }
             _ => unreachable!(),
         }
     }
 
-    pub fn get_data(&self) -> Element<Protocol> {
-        match &self.request {
-            Request::ReadCoils(_addr, val) => request_history_single_data(val.to_string()),
-            Request::ReadDiscreteInputs(_addr, val) => request_history_single_data(val.to_string()),
-            Request::WriteSingleCoil(_addr, val) => request_history_single_data(val.to_string()),
-            Request::WriteMultipleCoils(_addr, val) => Row::with_children( val.iter().map(|x| text(x.to_string()).into() ).collect() ).into(),
-            Request::ReadInputRegisters(_addr, val) => request_history_single_data(val.to_string()),
-            Request::ReadHoldingRegisters(_addr, val) => request_history_single_data(val.to_string()),
-            Request::WriteSingleRegister(_addr, val) => request_history_single_data(val.to_string()),
-            Request::WriteMultipleRegisters(_addr, val) => Row::with_children( val.iter().map(|x| text(x.to_string()).into() ).collect() ).into(),
-            _ => unreachable!(),
+    // Why the typed custom payload can not be sent, the request keeps the
+    // last bytes that parsed until it is fixed.
+    pub fn payload_error(&self) -> Option<String> {
+        match self.request_type {
+            RequestType::Custom if parse_hex_bytes(&self.payload_text).is_none() => Some(format!(
+                "Payload \"{}\" is not a list of hex bytes",
+                self.payload_text.trim()
+            )),
+            _ => None,
         }
     }
-}
 
-fn request_history_single_data(data: String) -> Element<'static, Protocol> {
-    text(data)
-        .width(200.0)
-        .into()
-        
+    // Puts a recorded transaction back into the builder.
+    pub fn load(&mut self, transaction: &Transaction) {
+        self.request_type = RequestType::from(&transaction.request);
+        self.request = transaction.request.clone();
+        self.unit = transaction.unit;
+        self.response = transaction.result.clone().ok();
+        self.error = transaction.result.clone().err();
+        self.payload_text = match &transaction.request {
+            Request::Custom(_function, data) => hex_string(data),
+            _ => String::new(),
+        };
+    }
 }
 
 impl<Message> RequestComponent<Message> {
@@ -454,73 +924,17 @@ impl std::default::Default for RequestParams {
             request: Request::ReadCoils(0, 0),
             request_type: RequestType::ReadCoils,
             response: None,
+            error: None,
             poll: std::time::Duration::from_millis(1000),
-            polling: false
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub enum RequestType {
-    ReadCoils,
-    ReadDiscreteInputs,
-    WriteSingleCoil,
-    WriteMultipleCoils,
-    ReadInputRegisters,
-    ReadHoldingRegisters,
-    WriteSingleRegister,
-    WriteMultipleRegisters,
-    // MaskWriteRegister,
-    // ReadWriteMultipleRegisters
-}
-
-impl RequestType {
-    fn new_request(&self) -> Request {
-        match self {
-            RequestType::ReadCoils => Request::ReadCoils(0, 0),
-            RequestType::ReadDiscreteInputs => Request::ReadDiscreteInputs(0, 0),
-            RequestType::WriteSingleCoil => Request::WriteSingleCoil(0, false),
-            RequestType::WriteMultipleCoils => Request::WriteMultipleCoils(0, Vec::new()),
-            RequestType::ReadInputRegisters => Request::ReadInputRegisters(0, 0),
-            RequestType::ReadHoldingRegisters => Request::ReadHoldingRegisters(0, 0),
-            RequestType::WriteSingleRegister => Request::WriteSingleRegister(0, 0),
-            RequestType::WriteMultipleRegisters => Request::WriteMultipleRegisters(0, Vec::new()),
+            polling: false,
+            unit: None,
+            payload_text: String::new(),
+            data_type: DataType::default(),
+            display_format: DisplayFormat::default(),
         }
     }
 }
 
-impl std::fmt::Display for RequestType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                RequestType::ReadCoils => "Read Coils (0x01)",
-                RequestType::ReadDiscreteInputs => "Read Discrete Inputs (0x02)",
-                RequestType::WriteSingleCoil => "Write Single Coil (0x05)",
-                RequestType::WriteMultipleCoils => "Write Multiple Coils (0x0F)",
-                RequestType::ReadInputRegisters => "Read Input Registers (0x04)",
-                RequestType::ReadHoldingRegisters => "Read Holding Registers (0x03)",
-                RequestType::WriteSingleRegister => "Write Single Register (0x06)",
-                RequestType::WriteMultipleRegisters => "Write Multiple Registers (0x10)",
-            }
-        )
-    }
-}
-
-impl RequestType {
-    const ALL: [RequestType; 8] = [
-        RequestType::ReadCoils,
-        RequestType::ReadDiscreteInputs,
-        RequestType::WriteSingleCoil,
-        RequestType::WriteMultipleCoils,
-        RequestType::ReadInputRegisters,
-        RequestType::ReadHoldingRegisters,
-        RequestType::WriteSingleRegister,
-        RequestType::WriteMultipleRegisters,
-    ];
-}
-
 fn response_data_box<'a, T, Message>(t: T) -> Container<'a, Message>
 where
     T: Into<Element<'a, Message, Renderer>>,
@@ -0,0 +1,239 @@
+use crate::gui::protocol::Protocol;
+use crate::script::{self, ScriptEvent, ScriptEvents};
+use iced::widget::{button, column, pick_list, row, scrollable, text, text_input, Column};
+use iced::{Alignment, Element, Length, Subscription};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+const MAX_LOG_LINES: usize = 500;
+
+#[derive(Debug, Clone)]
+pub enum ScriptCommand {
+    Select(String),
+    SetName(String),
+    Create,
+    Delete,
+    ImportDialog,
+    Import(String, String),
+    ExportDialog,
+    Export(PathBuf),
+    SetConsole(String),
+    RunConsole,
+    Run,
+    Stop,
+    ClearLog,
+    None,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Script {
+    pub name: String,
+    pub source: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct ScriptList {
+    scripts: Vec<Script>,
+    #[serde(skip)]
+    selected: Option<usize>,
+    #[serde(skip)]
+    name: String,
+    #[serde(skip)]
+    console: String,
+    #[serde(skip)]
+    running: Option<Arc<AtomicBool>>,
+    // Output of the last run, kept after a stop until the script is done.
+    #[serde(skip)]
+    events: Option<(u32, ScriptEvents)>,
+    #[serde(skip)]
+    runs: u32,
+    #[serde(skip)]
+    log: VecDeque<String>,
+}
+
+impl ScriptList {
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    fn selected(&self) -> Option<&Script> {
+        self.selected.and_then(|idx| self.scripts.get(idx))
+    }
+
+    pub fn selected_source(&self) -> Option<String> {
+        self.selected().map(|x| x.source.clone())
+    }
+
+    // Takes the console line, it is echoed to the log before it runs.
+    pub fn take_console(&mut self) -> Option<String> {
+        if self.console.trim().is_empty() {
+            return None;
+        }
+        let line = std::mem::take(&mut self.console);
+        self.log(format!("> {}", line));
+        Some(line)
+    }
+
+    pub fn log(&mut self, line: String) {
+        self.log.push_back(line);
+        while self.log.len() > MAX_LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+
+    // Flag the script thread polls to know when to give up, and where it
+    // sends its output.
+    pub fn start(&mut self) -> Option<(Arc<AtomicBool>, UnboundedSender<ScriptEvent>)> {
+        if self.is_running() {
+            return None;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let (events, rx) = unbounded_channel();
+        self.runs = self.runs.wrapping_add(1);
+        self.running = Some(stop.clone());
+        self.events = Some((self.runs, Arc::new(Mutex::new(Some(rx)))));
+        Some((stop, events))
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(stop) = self.running.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn handle_event(&mut self, ev: ScriptEvent) {
+        match ev {
+            ScriptEvent::Log(line) => self.log(line),
+            ScriptEvent::Finished(result) => {
+                self.running = None;
+                self.events = None;
+                match result {
+                    Ok(()) => self.log("Script finished".to_string()),
+                    Err(e) => self.log(format!("Script error: {}", e)),
+                }
+            }
+        }
+    }
+
+    pub fn subscription(&self) -> Option<Subscription<Protocol>> {
+        self.events
+            .as_ref()
+            .map(|(id, events)| script::events(*id, events.clone()).map(Protocol::ScriptEvent))
+    }
+
+    pub fn handle_command(&mut self, sc: ScriptCommand) {
+        match sc {
+            ScriptCommand::Select(name) => {
+                self.selected = self.scripts.iter().position(|x| x.name == name);
+            }
+            ScriptCommand::SetName(name) => self.name = name,
+            ScriptCommand::Create => {
+                let name = self.name.trim().to_string();
+                if name.is_empty() || self.scripts.iter().any(|x| x.name == name) {
+                    return;
+                }
+                self.scripts.push(Script {
+                    name,
+                    source: String::new(),
+                });
+                self.selected = Some(self.scripts.len() - 1);
+                self.name.clear();
+            }
+            ScriptCommand::Delete => {
+                if let Some(idx) = self.selected.take() {
+                    self.scripts.remove(idx);
+                }
+            }
+            // Importing a file again replaces the script of the same name.
+            ScriptCommand::Import(name, source) => {
+                match self.scripts.iter().position(|x| x.name == name) {
+                    Some(idx) => {
+                        self.scripts[idx].source = source;
+                        self.selected = Some(idx);
+                    }
+                    None => {
+                        self.scripts.push(Script { name, source });
+                        self.selected = Some(self.scripts.len() - 1);
+                    }
+                }
+            }
+            ScriptCommand::SetConsole(line) => self.console = line,
+            ScriptCommand::Stop => self.stop(),
+            ScriptCommand::ClearLog => self.log.clear(),
+            _ => (),
+        }
+    }
+}
+
+pub fn scripts(list: &ScriptList) -> Element<Protocol> {
+    let names: Vec<String> = list.scripts.iter().map(|x| x.name.clone()).collect();
+    let selected = list.selected().map(|x| x.name.clone());
+    let run_btn = if list.is_running() {
+        button("Stop").on_press(Protocol::ScriptCommand(ScriptCommand::Stop))
+    } else {
+        button("Run").on_press(Protocol::ScriptCommand(ScriptCommand::Run))
+    };
+    let mut toolbar = row![
+        text("Scripts"),
+        pick_list(names, selected, |x| {
+            Protocol::ScriptCommand(ScriptCommand::Select(x))
+        })
+        .placeholder("Select script")
+        .width(150.0),
+        run_btn,
+        text_input("New script", &list.name, |x| {
+            Protocol::ScriptCommand(ScriptCommand::SetName(x))
+        })
+        .width(150.0),
+        button("New").on_press(Protocol::ScriptCommand(ScriptCommand::Create)),
+        button("Import").on_press(Protocol::ScriptCommand(ScriptCommand::ImportDialog)),
+    ]
+    .spacing(10.0)
+    .align_items(Alignment::Center);
+    if list.selected().is_some() {
+        toolbar = toolbar
+            .push(button("Export").on_press(Protocol::ScriptCommand(ScriptCommand::ExportDialog)))
+            .push(button("Delete").on_press(Protocol::ScriptCommand(ScriptCommand::Delete)));
+    }
+    let source = list.selected().map(|x| x.source.as_str()).unwrap_or("");
+    let log = Column::with_children(list.log.iter().map(|x| text(x).into()).collect());
+    let properties = || {
+        iced::widget::scrollable::Properties::new()
+            .width(1.0)
+            .margin(1.0)
+            .scroller_width(1.0)
+    };
+    column![
+        toolbar,
+        row![
+            scrollable(text(source))
+                .height(Length::Fill)
+                .vertical_scroll(properties()),
+            column![
+                row![
+                    text("Log"),
+                    button("Clear").on_press(Protocol::ScriptCommand(ScriptCommand::ClearLog)),
+                ]
+                .spacing(10.0)
+                .align_items(Alignment::Center),
+                scrollable(log)
+                    .height(Length::Fill)
+                    .vertical_scroll(properties()),
+            ]
+            .spacing(5.0),
+        ]
+        .spacing(20.0)
+        .height(150),
+        text_input("let x = read_holding(0, 2); log(`${x}`);", &list.console, |x| {
+            Protocol::ScriptCommand(ScriptCommand::SetConsole(x))
+        })
+        .on_submit(Protocol::ScriptCommand(ScriptCommand::RunConsole)),
+    ]
+    .spacing(10.0)
+    .padding(10.0)
+    .into()
+}
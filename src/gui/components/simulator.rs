@@ -0,0 +1,224 @@
+use crate::gui::components::connection::serial::{SerialPortComponent, SerialPortParams};
+use crate::gui::components::table::Table;
+use crate::gui::protocol::Protocol;
+use crate::simulator::rtu::PTY;
+use crate::simulator::{serve, Faults, SimulatorConfig, SimulatorData, SimulatorEvent};
+use iced::widget::{button, column, pick_list, row, text, text_input};
+use iced::{Alignment, Element, Subscription};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SimulatorMode {
+    #[default]
+    Tcp,
+    Rtu,
+}
+
+impl SimulatorMode {
+    const ALL: [SimulatorMode; 2] = [SimulatorMode::Tcp, SimulatorMode::Rtu];
+}
+
+impl std::fmt::Display for SimulatorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SimulatorMode::Tcp => "TCP Slave",
+                SimulatorMode::Rtu => "RTU Slave",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SimulatorCommand {
+    SetMode(SimulatorMode),
+    SetPort(u16),
+    SetSerial(SerialPortParams),
+    SetDelay(Duration),
+    SetCrcErrors(u8),
+    SetDropped(u8),
+    Start,
+    Stop,
+    None,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Simulator {
+    #[serde(default)]
+    mode: SimulatorMode,
+    #[serde(default = "default_port")]
+    port: u16,
+    #[serde(default = "default_serial")]
+    serial: SerialPortParams,
+    #[serde(default)]
+    faults: Faults,
+    #[serde(skip)]
+    running: bool,
+    #[serde(skip)]
+    status: String,
+    #[serde(skip)]
+    data: Arc<Mutex<SimulatorData>>,
+}
+
+fn default_port() -> u16 {
+    5020
+}
+
+fn default_serial() -> SerialPortParams {
+    SerialPortParams {
+        port: PTY.to_string(),
+        ..SerialPortParams::default()
+    }
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self {
+            mode: SimulatorMode::default(),
+            port: default_port(),
+            serial: default_serial(),
+            faults: Faults::default(),
+            running: false,
+            status: String::new(),
+            data: Arc::default(),
+        }
+    }
+}
+
+impl Simulator {
+    pub fn handle_command(&mut self, sc: SimulatorCommand, table: &Table) {
+        match sc {
+            SimulatorCommand::SetMode(mode) if !self.running => self.mode = mode,
+            SimulatorCommand::SetPort(port) if !self.running => self.port = port,
+            SimulatorCommand::SetSerial(params) if !self.running => self.serial = params,
+            // Faults apply to a running slave straight away.
+            SimulatorCommand::SetDelay(delay) => self.faults.delay = delay,
+            SimulatorCommand::SetCrcErrors(percent) => self.faults.crc_errors = percent.min(100),
+            SimulatorCommand::SetDropped(percent) => self.faults.dropped = percent.min(100),
+            SimulatorCommand::Start => {
+                self.running = true;
+                self.status = String::from("Starting");
+                self.sync(table);
+            }
+            SimulatorCommand::Stop => {
+                self.running = false;
+                self.status = String::from("Stopped");
+            }
+            _ => (),
+        }
+        self.data.lock().unwrap().faults = self.faults;
+    }
+
+    // Writes from masters have been applied to the table by the time this is
+    // called, only the status needs updating.
+    pub fn handle_event(&mut self, ev: &SimulatorEvent) {
+        match ev {
+            SimulatorEvent::Listening(addr) => self.status = format!("Listening on {}", addr),
+            SimulatorEvent::Written(..) => (),
+            SimulatorEvent::Error(e) => {
+                self.running = false;
+                self.status = e.clone();
+            }
+        }
+    }
+
+    pub fn sync(&self, table: &Table) {
+        if self.running {
+            self.data.lock().unwrap().load_table(table);
+        }
+    }
+
+    fn config(&self) -> SimulatorConfig {
+        match self.mode {
+            SimulatorMode::Tcp => SimulatorConfig::Tcp(self.port),
+            SimulatorMode::Rtu => SimulatorConfig::Rtu(self.serial.clone()),
+        }
+    }
+
+    pub fn subscription(&self) -> Option<Subscription<Protocol>> {
+        if self.running {
+            Some(serve(self.config(), self.data.clone()).map(Protocol::SimulatorEvent))
+        } else {
+            None
+        }
+    }
+}
+
+fn percent_input(label: &str, value: u8, msg: fn(u8) -> SimulatorCommand) -> Element<Protocol> {
+    column![
+        text(label),
+        text_input("0", &value.to_string(), move |x| match x.parse::<u8>() {
+            Ok(n) => Protocol::SimulatorCommand(msg(n)),
+            Err(_e) => Protocol::SimulatorCommand(SimulatorCommand::None),
+        })
+        .width(60.0),
+    ]
+    .spacing(10.0)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+pub fn simulator(sim: &Simulator) -> Element<Protocol> {
+    let btn = if sim.running {
+        button("Stop").on_press(Protocol::SimulatorCommand(SimulatorCommand::Stop))
+    } else {
+        button("Simulate").on_press(Protocol::SimulatorCommand(SimulatorCommand::Start))
+    };
+    let mode = pick_list(&SimulatorMode::ALL[..], Some(sim.mode), |x| {
+        Protocol::SimulatorCommand(SimulatorCommand::SetMode(x))
+    })
+    .width(110.0);
+    let settings: Element<Protocol> = match sim.mode {
+        SimulatorMode::Tcp => row![
+            text("Port"),
+            text_input("5020", &sim.port.to_string(), |x| match x.parse::<u16>() {
+                Ok(n) => Protocol::SimulatorCommand(SimulatorCommand::SetPort(n)),
+                Err(_e) => Protocol::SimulatorCommand(SimulatorCommand::None),
+            })
+            .width(70.0),
+        ]
+        .spacing(10.0)
+        .align_items(Alignment::Center)
+        .into(),
+        // The address is the slave id answered to, "pty" opens a virtual port.
+        SimulatorMode::Rtu => row![
+            SerialPortComponent::<Protocol>::new(sim.serial.clone(), |params| {
+                Protocol::SimulatorCommand(SimulatorCommand::SetSerial(params))
+            }),
+            column![
+                text("Delay (ms)"),
+                text_input(
+                    "0",
+                    &sim.faults.delay.as_millis().to_string(),
+                    |x| match x.parse::<u64>() {
+                        Ok(n) => Protocol::SimulatorCommand(SimulatorCommand::SetDelay(
+                            Duration::from_millis(n)
+                        )),
+                        Err(_e) => Protocol::SimulatorCommand(SimulatorCommand::None),
+                    }
+                )
+                .width(70.0),
+            ]
+            .spacing(10.0)
+            .align_items(Alignment::Center),
+            percent_input(
+                "CRC errors %",
+                sim.faults.crc_errors,
+                SimulatorCommand::SetCrcErrors
+            ),
+            percent_input("Dropped %", sim.faults.dropped, SimulatorCommand::SetDropped),
+        ]
+        .spacing(10.0)
+        .align_items(Alignment::Center)
+        .into(),
+    };
+    row![mode, settings, btn, text(&sim.status)]
+        .spacing(10.0)
+        .padding(10.0)
+        .align_items(Alignment::Center)
+        .into()
+}
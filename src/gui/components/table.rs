@@ -1,238 +1,81 @@
-use crate::gui::components::common::ContainerStyle;
+use crate::data_type::{ByteOrder, DataType, DisplayFormat};
+use crate::gui::components::common::{leds, ContainerStyle};
 use crate::gui::protocol::Protocol;
 use crate::gui::style::ButtonType;
 use iced::{Padding, Renderer};
 
 use iced::{
     widget::{
-        button, image, image::Handle, pick_list, row, scrollable, text, text_input, Column,
-        Container,
+        button, image, image::Handle, pick_list, row, scrollable, text, text_input, tooltip,
+        Column, Container,
     },
     Alignment, Background, Color, Element, Length,
 };
-use tokio_modbus::prelude::{Request, Response};
+use std::time::Duration;
+use tokio_modbus::prelude::Request;
 
-use serde::{Deserialize, Serialize};
-use serde_json;
-use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
+pub use rustbus_core::table::{Register, RegisterRequest, RegisterType, Table, TableCommand};
 
-#[derive(Debug, Clone)]
-pub enum TableCommand {
-    AddRegister(Option<usize>),
-    SetAddress(usize, u16),
-    SetType(usize, RegisterType),
-    SetName(usize, String),
-    SetDescription(usize, String),
-    SetValue(usize, u16),
-    Delete(usize),
-    None,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Table {
-    registers: Vec<Register>,
-}
-
-#[derive(Debug, Clone)]
-pub enum RegisterRequest {
-    Read(Register),
-    Write(Register),
-}
-
-impl From<RegisterRequest> for Request {
-    fn from(register: RegisterRequest) -> Request {
-        match register {
-            RegisterRequest::Read(reg) => match reg.register_type {
-                RegisterType::Coil => Request::ReadCoils(reg.address, 1),
-                RegisterType::DiscreteInputs => Request::ReadDiscreteInputs(reg.address, 1),
-                RegisterType::InputRegister => Request::ReadInputRegisters(reg.address, 1),
-                RegisterType::HoldingRegister => Request::ReadHoldingRegisters(reg.address, 1),
-            },
-            RegisterRequest::Write(reg) => match reg.register_type {
-                RegisterType::Coil => Request::WriteSingleCoil(reg.address, reg.value != 0),
-                RegisterType::InputRegister => Request::WriteSingleRegister(reg.address, reg.value),
-                RegisterType::HoldingRegister => {
-                    Request::WriteMultipleRegisters(reg.address, vec![reg.value])
-                }
-                RegisterType::DiscreteInputs => unreachable!(),
-            },
-        }
-        // Request::MaskWriteRegister(u16, u16, u16),
-        // Request::ReadWriteMultipleRegisters(u16, u16, u16, Vec<u16>),
-        // Request::Custom(u8, Vec<u8>),
-    }
-}
+// Byte order choice of a single register, `None` being the connection default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterOrder(Option<ByteOrder>);
 
-pub fn get_address_from_request(req: Request) -> Option<u16> {
-    match req {
-        Request::ReadCoils(addr, _) => Some(addr),
-        Request::ReadDiscreteInputs(addr, _) => Some(addr),
-        Request::WriteSingleCoil(addr, _) => Some(addr),
-        Request::WriteMultipleCoils(addr, _) => Some(addr),
-        Request::ReadInputRegisters(addr, _) => Some(addr),
-        Request::ReadHoldingRegisters(addr, _) => Some(addr),
-        Request::WriteSingleRegister(addr, _) => Some(addr),
-        Request::WriteMultipleRegisters(addr, _) => Some(addr),
-        _ => None
-        // Request::MaskWriteRegister(addr, _, u16),
-        // Request::ReadWriteMultipleRegisters(addr, u16, addr, Vec<u16>),
-        // Request::Custom(u8, Vec<u8>),
-        // Request::Disconnect,
-    }
-}
-
-impl Table {
-    pub fn handle_commmand(&mut self, tc: TableCommand) {
-        match tc {
-            TableCommand::AddRegister(idx) => {
-                if let Some(index) = idx {
-                    self.registers.insert(index+1, Register::default());
-                } else {
-                    self.registers.push(Register::default());
-                }
-            }
-            TableCommand::SetAddress(idx, addr) => {
-                self.registers.get_mut(idx).map(|x| {
-                    x.address = addr;
-                });
-            }
-            TableCommand::SetType(idx, rtype) => {
-                self.registers.get_mut(idx).map(|x| {
-                    x.register_type = rtype;
-                });
-            }
-            TableCommand::SetName(idx, name) => {
-                self.registers.get_mut(idx).map(|x| {
-                    x.name = name;
-                });
-            }
-            TableCommand::SetDescription(idx, desc) => {
-                self.registers.get_mut(idx).map(|x| {
-                    x.description = desc;
-                });
-            }
-            TableCommand::SetValue(idx, val) => {
-                println!("{idx} {val}");
-                self.registers.get_mut(idx).map(|x| {
-                    x.value = val;
-                });
-            }
-            TableCommand::Delete(idx) => {
-                self.registers.remove(idx);
-            }
-            TableCommand::None => (),
-        }
-    }
-
-    pub fn handle_response(&mut self, req: Request, resp: Response) {
-        if let Some(addr) = get_address_from_request(req) {
-            self.registers.iter_mut().for_each(|x| {
-                if x.address == addr {
-                    x.apply_response(&resp)
-                }
-            })
-        } else {
-            println!("NO Address");
-        };
-    }
-
-    pub fn load_from_file(path: &std::path::Path) -> Result<Table, Box<dyn std::error::Error>> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let u = serde_json::from_reader(reader)?;
-        Ok(u)
-    }
-}
-
-impl std::default::Default for Table {
-    fn default() -> Self {
-        Self {
-            registers: Vec::new(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum RegisterType {
-    Coil,
-    DiscreteInputs,
-    InputRegister,
-    HoldingRegister,
-}
-
-impl std::fmt::Display for RegisterType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                RegisterType::Coil => "COIL",
-                RegisterType::DiscreteInputs => "DISCRETE",
-                RegisterType::InputRegister => "INPUT",
-                RegisterType::HoldingRegister => "HOLDING",
-            }
-        )
-    }
-}
-
-impl RegisterType {
-    const ALL: [RegisterType; 4] = [
-        RegisterType::Coil,
-        RegisterType::DiscreteInputs,
-        RegisterType::InputRegister,
-        RegisterType::HoldingRegister,
+impl RegisterOrder {
+    const ALL: [RegisterOrder; 5] = [
+        RegisterOrder(None),
+        RegisterOrder(Some(ByteOrder::Abcd)),
+        RegisterOrder(Some(ByteOrder::Cdab)),
+        RegisterOrder(Some(ByteOrder::Badc)),
+        RegisterOrder(Some(ByteOrder::Dcba)),
     ];
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Register {
-    register_type: RegisterType,
-    address: u16,
-    length: u8,
-    name: String,
-    value: u16,
-    description: String,
-}
-
-impl Register {
-    fn apply_response(&mut self, resp: &Response) {
-        match resp {
-            Response::ReadCoils(vbool) => self.value = u16::from(vbool[0]),
-            Response::ReadDiscreteInputs(vbool) => self.value = u16::from(vbool[0]),
-            Response::WriteSingleCoil(_addr, val) => self.value = u16::from(*val),
-            // Fix
-            Response::ReadInputRegisters(vinputs) => self.value = vinputs[0],
-            Response::ReadHoldingRegisters(vholding) => self.value = vholding[0],
-            Response::WriteSingleRegister(_addr, val) => self.value = *val,
-            Response::WriteMultipleRegisters(_addr, val) => self.value = *val,
-            Response::WriteMultipleCoils(_addr, val) => self.value = *val,
-            // Response::MaskWriteRegister(u16, u16, u16),
-            // Response::ReadWriteMultipleRegisters(Vec<u16>),
-            // Response::Custom(u8, Vec<u8>),
-            _ => (),
-        }
-    }
-}
-
-impl std::default::Default for Register {
-    fn default() -> Self {
-        Self {
-            register_type: RegisterType::Coil,
-            address: 0,
-            length: 1,
-            name: "".to_string(),
-            value: 0,
-            description: "".to_string(),
+impl std::fmt::Display for RegisterOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(order) => write!(f, "{}", order),
+            None => write!(f, "Default"),
         }
     }
 }
 
-pub fn row_from_register<'a>(idx: usize, register: &Register) -> Container<'a, Protocol, Renderer> {
+pub fn row_from_register<'a>(
+    idx: usize,
+    register: &Register,
+    default_order: ByteOrder,
+    format: DisplayFormat,
+) -> Container<'a, Protocol, Renderer> {
+    let value: Element<Protocol> = match format {
+        DisplayFormat::Led => leds(
+            &register.bits(default_order),
+            100.0,
+            Some(&|bit| Protocol::TableCommand(TableCommand::ToggleBit(idx, bit))),
+        ),
+        _ => text_input(
+            "value",
+            &register.value_edit(default_order, format),
+            move |x| Protocol::TableCommand(TableCommand::SetValue(idx, x)),
+        )
+        .width(100.0)
+        .into(),
+    };
+    let error = register
+        .edit_error(default_order, format)
+        .or_else(|| register.error().map(String::from));
+    let value_state: Element<Protocol> = match &error {
+        Some(reason) => tooltip(
+            text("!").style(iced::theme::Text::Color(Color::from_rgb(0.9, 0.2, 0.2))),
+            reason,
+            tooltip::Position::Bottom,
+        )
+        .into(),
+        None => text("").into(),
+    };
+    let scale_text = register.scale_text();
+    let offset_text = register.offset_text();
     Container::new(
         row![
-            text_input("0", &register.address.to_string(), move |x| {
+            text_input("0", &register.address().to_string(), move |x| {
                 let parsed = x.parse::<u16>();
                 match parsed {
                     Ok(addr) => Protocol::TableCommand(TableCommand::SetAddress(idx, addr)),
@@ -240,30 +83,85 @@ pub fn row_from_register<'a>(idx: usize, register: &Register) -> Container<'a, P
                 }
             })
             .width(100.0),
+            text_input("default", &register.unit().map(|x| x.to_string()).unwrap_or_default(), move |x| {
+                if x.is_empty() {
+                    return Protocol::TableCommand(TableCommand::SetUnit(idx, None));
+                }
+                match x.parse::<u8>() {
+                    Ok(unit) => Protocol::TableCommand(TableCommand::SetUnit(idx, Some(unit))),
+                    Err(_e) => Protocol::TableCommand(TableCommand::None),
+                }
+            })
+            .width(60.0),
             pick_list(
                 &RegisterType::ALL[..],
-                Some(register.register_type),
+                Some(register.register_type()),
                 move |x| { Protocol::TableCommand(TableCommand::SetType(idx, x)) }
             ),
-            text_input("name", &register.name, move |x| {
+            text_input("name", register.name(), move |x| {
                 Protocol::TableCommand(TableCommand::SetName(idx, x))
             })
             .width(100.0),
-            text_input("description", &register.description, move |x| {
+            text_input("description", register.description(), move |x| {
                 Protocol::TableCommand(TableCommand::SetDescription(idx, x))
             })
             .width(100.0),
-            text_input("value", &register.value.to_string(), move |x| {
-                println!("input: {:?}", x);
-                let parsed = x.parse::<u16>();
-                println!("parsed: {:?}", parsed);
-                match parsed {
-                    Ok(addr) => Protocol::TableCommand(TableCommand::SetValue(idx, addr)),
+            pick_list(&DataType::ALL[..], Some(register.data_type()), move |x| {
+                Protocol::TableCommand(TableCommand::SetDataType(idx, x))
+            })
+            .width(100.0),
+            length_input(idx, register),
+            pick_list(
+                &RegisterOrder::ALL[..],
+                Some(RegisterOrder(register.byte_order())),
+                move |x| { Protocol::TableCommand(TableCommand::SetByteOrder(idx, x.0)) }
+            )
+            .width(80.0),
+            value,
+            Container::new(value_state).width(10.0),
+            text_input("1", &scale_text, move |x| {
+                Protocol::TableCommand(TableCommand::SetScale(idx, x))
+            })
+            .width(60.0),
+            text_input("0", &offset_text, move |x| {
+                Protocol::TableCommand(TableCommand::SetOffset(idx, x))
+            })
+            .width(60.0),
+            text_input("unit", register.eng_unit(), move |x| {
+                Protocol::TableCommand(TableCommand::SetEngUnit(idx, x))
+            })
+            .width(50.0),
+            text_input("", &register.decimals().map(|x| x.to_string()).unwrap_or_default(), move |x| {
+                if x.is_empty() {
+                    return Protocol::TableCommand(TableCommand::SetDecimals(idx, None));
+                }
+                match x.parse::<u8>() {
+                    Ok(decimals) => Protocol::TableCommand(TableCommand::SetDecimals(idx, Some(decimals.min(12)))),
                     Err(_e) => Protocol::TableCommand(TableCommand::None),
                 }
             })
-            .width(100.0),
-            actions(idx, register)
+            .width(40.0),
+            text_input("default", &register.retry().timeout.map(|x| x.as_millis().to_string()).unwrap_or_default(), move |x| {
+                if x.is_empty() {
+                    return Protocol::TableCommand(TableCommand::SetTimeout(idx, None));
+                }
+                match x.parse::<u64>() {
+                    Ok(ms) if ms > 0 => Protocol::TableCommand(TableCommand::SetTimeout(idx, Some(Duration::from_millis(ms)))),
+                    _ => Protocol::TableCommand(TableCommand::None),
+                }
+            })
+            .width(60.0),
+            text_input("default", &register.retry().retries.map(|x| x.to_string()).unwrap_or_default(), move |x| {
+                if x.is_empty() {
+                    return Protocol::TableCommand(TableCommand::SetRetries(idx, None));
+                }
+                match x.parse::<u32>() {
+                    Ok(retries) => Protocol::TableCommand(TableCommand::SetRetries(idx, Some(retries))),
+                    Err(_e) => Protocol::TableCommand(TableCommand::None),
+                }
+            })
+            .width(60.0),
+            actions(idx, register, error.is_none())
         ]
         .align_items(Alignment::Center)
         .spacing(5.0),
@@ -273,20 +171,45 @@ pub fn row_from_register<'a>(idx: usize, register: &Register) -> Container<'a, P
     .align_y(iced::alignment::Vertical::Center)
 }
 
-pub fn actions<'a>(idx: usize, reg: &Register) -> Container<'a, Protocol> {
+// Only strings have a configurable length, the other types show their fixed
+// register count.
+fn length_input<'a>(idx: usize, register: &Register) -> Element<'a, Protocol, Renderer> {
+    match register.data_type() {
+        DataType::Ascii => text_input("1", &register.length().to_string(), move |x| {
+            match x.parse::<u8>() {
+                Ok(length) => Protocol::TableCommand(TableCommand::SetLength(idx, length)),
+                Err(_e) => Protocol::TableCommand(TableCommand::None),
+            }
+        })
+        .width(60.0)
+        .into(),
+        _ => text(register.register_count()).width(60.0).into(),
+    }
+}
+
+pub fn actions<'a>(idx: usize, reg: &Register, writable: bool) -> Container<'a, Protocol> {
     let image_size = 24.0;
+    // Writes are held back while the typed value is invalid or out of range.
+    let write = button(image(Handle::from_path("./resources/write.png")).width(image_size))
+        .style(ButtonType::Image.into());
+    let write = match writable {
+        true => write.on_press(Protocol::ModbusRequest(
+            Request::from(RegisterRequest::Write(reg.clone())),
+            reg.unit(),
+            reg.retry(),
+        )),
+        false => write,
+    };
     Container::new(
         row![
             button(image(Handle::from_path("./resources/read.png")).width(image_size))
-                .on_press(Protocol::ModbusRequest(Request::from(
-                    RegisterRequest::Read(reg.clone())
-                )))
-                .style(ButtonType::Image.into()),
-            button(image(Handle::from_path("./resources/write.png")).width(image_size))
-                .on_press(Protocol::ModbusRequest(Request::from(
-                    RegisterRequest::Write(reg.clone())
-                )))
+                .on_press(Protocol::ModbusRequest(
+                    Request::from(RegisterRequest::Read(reg.clone())),
+                    reg.unit(),
+                    reg.retry()
+                ))
                 .style(ButtonType::Image.into()),
+            write,
             button(image(Handle::from_path("./resources/garbage.png")).width(image_size))
                 .on_press(Protocol::TableCommand(TableCommand::Delete(idx)))
                 .style(ButtonType::Image.into()),
@@ -335,19 +258,33 @@ where
         })))
 }
 
-pub fn table<'a>(table: &Table) -> Column<'a, Protocol, Renderer> {
+pub fn table<'a>(table: &Table, default_order: ByteOrder) -> Column<'a, Protocol, Renderer> {
     let addr_width = 100.0;
+    let unit_width = 60.0;
     let type_width = 100.0;
     let name_width = 100.0;
+    let data_type_width = 100.0;
+    let length_width = 60.0;
+    let order_width = 80.0;
     let value_width = 100.0;
     let description_width = 100.0;
     let actions_width = 100.0;
     let headers = row![
         text("Address").width(addr_width),
+        text("Unit").width(unit_width),
         text("Type").width(type_width),
         text("Name").width(name_width),
-        text("Description").width(value_width),
-        text("Value").width(description_width),
+        text("Description").width(description_width),
+        text("Data Type").width(data_type_width),
+        text("Regs").width(length_width),
+        text("Order").width(order_width),
+        text("Value").width(value_width + 15.0),
+        text("Scale").width(60.0),
+        text("Offset").width(60.0),
+        text("Eng Unit").width(50.0),
+        text("Dec").width(40.0),
+        text("Timeout").width(60.0),
+        text("Retries").width(60.0),
         text("Actions").width(actions_width)
     ]
     .spacing(5)
@@ -355,14 +292,52 @@ pub fn table<'a>(table: &Table) -> Column<'a, Protocol, Renderer> {
     let mut c = Column::new()
         .spacing(0)
         .align_items(iced::Alignment::Center);
-    c = c.push(text("Register Mapping"));
+    let poll_btn = if !table.is_polling() {
+        button(image(Handle::from_path("./resources/sync.png")).width(25.0))
+            .on_press(Protocol::TableCommand(TableCommand::SetPolling(true)))
+            .style(ButtonType::Image.into())
+    } else {
+        button(image(Handle::from_path("./resources/stop_poll.png")).width(25.0))
+            .on_press(Protocol::TableCommand(TableCommand::SetPolling(false)))
+            .style(ButtonType::Image.into())
+    };
+    c = c.push(
+        row![
+            text("Register Mapping"),
+            text("Value Format"),
+            pick_list(&DisplayFormat::ALL[..], Some(table.value_format()), |x| {
+                Protocol::TableCommand(TableCommand::SetValueFormat(x))
+            }),
+            text("Poll (ms)"),
+            text_input("1000", &table.poll_interval().as_millis().to_string(), |x| {
+                match x.parse::<u64>() {
+                    Ok(n) => Protocol::TableCommand(TableCommand::SetPollInterval(
+                        Duration::from_millis(n),
+                    )),
+                    Err(_e) => Protocol::TableCommand(TableCommand::None),
+                }
+            })
+            .width(70.0),
+            text("Max Gap"),
+            text_input("0", &table.poll_gap().to_string(), |x| {
+                match x.parse::<u16>() {
+                    Ok(n) => Protocol::TableCommand(TableCommand::SetPollGap(n)),
+                    Err(_e) => Protocol::TableCommand(TableCommand::None),
+                }
+            })
+            .width(50.0),
+            poll_btn
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center),
+    );
     c = c.push(headers);
     let rc = Column::with_children(
         table
-            .registers
+            .registers()
             .iter()
             .enumerate()
-            .map(|(idx, reg)| row_from_register(idx, reg).into())
+            .map(|(idx, reg)| row_from_register(idx, reg, default_order, table.value_format()).into())
             .collect(),
     )
     .align_items(iced::Alignment::Center);
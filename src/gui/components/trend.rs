@@ -0,0 +1,483 @@
+use crate::data_type::ByteOrder;
+use crate::gui::components::table::Table;
+use crate::gui::protocol::Protocol;
+use chrono::{DateTime, Local};
+use iced::alignment;
+use iced::mouse::ScrollDelta;
+use iced::widget::canvas::{self, event, Cursor, Frame, Geometry, Path, Stroke, Text};
+use iced::widget::{button, canvas as chart_canvas, column, pick_list, row, text, text_input, Row};
+use iced::{Alignment, Color, Element, Length, Point, Rectangle, Size, Theme};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+// Bounds memory when the window is long and polling fast.
+const MAX_SAMPLES: usize = 20000;
+const MAX_ZOOM: u8 = 6;
+
+// Cycles through the palette once there are more series than colors.
+fn series_color(idx: usize) -> Color {
+    match idx % 6 {
+        0 => Color::from_rgb8(198, 151, 75),
+        1 => Color::from_rgb8(96, 165, 250),
+        2 => Color::from_rgb8(134, 217, 115),
+        3 => Color::from_rgb8(248, 113, 113),
+        4 => Color::from_rgb8(192, 132, 252),
+        _ => Color::from_rgb8(250, 204, 21),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum YRange {
+    #[default]
+    Auto,
+    Fixed,
+}
+
+impl YRange {
+    const ALL: [YRange; 2] = [YRange::Auto, YRange::Fixed];
+}
+
+impl std::fmt::Display for YRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                YRange::Auto => "Autoscale",
+                YRange::Fixed => "Fixed Y",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TrendCommand {
+    AddSeries(String),
+    RemoveSeries(String),
+    SetWindow(Duration),
+    SetYRange(YRange),
+    SetYMin(String),
+    SetYMax(String),
+    Pause,
+    Resume,
+    Zoom(i8),
+    Clear,
+    None,
+}
+
+// Selected registers are followed by name, values are engineering values.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Trend {
+    #[serde(default)]
+    series: Vec<String>,
+    #[serde(default = "default_window")]
+    window: Duration,
+    #[serde(default)]
+    y_range: YRange,
+    #[serde(default)]
+    y_min: f64,
+    #[serde(default = "default_y_max")]
+    y_max: f64,
+    #[serde(skip)]
+    samples: HashMap<String, VecDeque<(DateTime<Local>, f64)>>,
+    // Time the view was frozen at, no samples are taken while paused.
+    #[serde(skip)]
+    paused: Option<DateTime<Local>>,
+    // Each level halves the part of the window shown.
+    #[serde(skip)]
+    zoom: u8,
+    #[serde(skip)]
+    y_min_text: Option<String>,
+    #[serde(skip)]
+    y_max_text: Option<String>,
+}
+
+fn default_window() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_y_max() -> f64 {
+    100.0
+}
+
+impl Default for Trend {
+    fn default() -> Self {
+        Self {
+            series: Vec::new(),
+            window: default_window(),
+            y_range: YRange::default(),
+            y_min: 0.0,
+            y_max: default_y_max(),
+            samples: HashMap::new(),
+            paused: None,
+            zoom: 0,
+            y_min_text: None,
+            y_max_text: None,
+        }
+    }
+}
+
+impl Trend {
+    pub fn handle_command(&mut self, tc: TrendCommand) {
+        match tc {
+            TrendCommand::AddSeries(name) => {
+                if !self.series.contains(&name) {
+                    self.series.push(name);
+                }
+            }
+            TrendCommand::RemoveSeries(name) => {
+                self.series.retain(|x| *x != name);
+                self.samples.remove(&name);
+            }
+            TrendCommand::SetWindow(window) => self.window = window.max(Duration::from_secs(1)),
+            TrendCommand::SetYRange(y_range) => self.y_range = y_range,
+            TrendCommand::SetYMin(s) => {
+                if let Ok(x) = s.trim().parse::<f64>() {
+                    self.y_min = x;
+                }
+                self.y_min_text = Some(s);
+            }
+            TrendCommand::SetYMax(s) => {
+                if let Ok(x) = s.trim().parse::<f64>() {
+                    self.y_max = x;
+                }
+                self.y_max_text = Some(s);
+            }
+            TrendCommand::Pause => self.paused = Some(Local::now()),
+            TrendCommand::Resume => {
+                self.paused = None;
+                self.zoom = 0;
+            }
+            TrendCommand::Zoom(step) => {
+                self.zoom = (i16::from(self.zoom) + i16::from(step)).clamp(0, MAX_ZOOM.into()) as u8
+            }
+            TrendCommand::Clear => self.samples.clear(),
+            TrendCommand::None => (),
+        }
+    }
+
+    // Takes a point for every series from the table's current values.
+    pub fn sample(&mut self, table: &Table, default_order: ByteOrder) {
+        if self.paused.is_some() || self.series.is_empty() {
+            return;
+        }
+        let now = Local::now();
+        let oldest = now
+            - chrono::Duration::from_std(self.window).unwrap_or_else(|_e| chrono::Duration::zero());
+        for name in &self.series {
+            let value = table
+                .registers()
+                .iter()
+                .find(|x| x.name().trim() == name)
+                .and_then(|x| x.engineering_value(default_order));
+            let points = self.samples.entry(name.clone()).or_default();
+            if let Some(value) = value {
+                points.push_back((now, value));
+            }
+            while points.len() > MAX_SAMPLES || points.front().map_or(false, |x| x.0 < oldest) {
+                points.pop_front();
+            }
+        }
+    }
+
+    // The span of time shown, ending now or when paused.
+    fn span(&self) -> (DateTime<Local>, f64) {
+        let end = self.paused.unwrap_or_else(Local::now);
+        let seconds = self.window.as_secs_f64() / f64::from(1u32 << self.zoom);
+        (end, seconds)
+    }
+
+    fn y_bounds(&self, end: DateTime<Local>, seconds: f64) -> (f64, f64) {
+        let (min, max) = match self.y_range {
+            YRange::Fixed => (self.y_min.min(self.y_max), self.y_min.max(self.y_max)),
+            YRange::Auto => {
+                let mut bounds: Option<(f64, f64)> = None;
+                for (_, value) in self.visible(end, seconds) {
+                    bounds = Some(match bounds {
+                        Some((min, max)) => (min.min(value), max.max(value)),
+                        None => (value, value),
+                    });
+                }
+                let (min, max) = bounds.unwrap_or((0.0, 1.0));
+                let pad = (max - min) * 0.05;
+                (min - pad, max + pad)
+            }
+        };
+        if max - min < f64::EPSILON {
+            (min - 1.0, max + 1.0)
+        } else {
+            (min, max)
+        }
+    }
+
+    fn visible(&self, end: DateTime<Local>, seconds: f64) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.series
+            .iter()
+            .filter_map(|name| self.samples.get(name))
+            .flat_map(move |points| points.iter().map(move |(t, v)| (age(end, *t), *v)))
+            .filter(move |(age, _)| *age >= 0.0 && *age <= seconds)
+    }
+}
+
+fn age(end: DateTime<Local>, t: DateTime<Local>) -> f64 {
+    (end - t).num_microseconds().unwrap_or(i64::MAX) as f64 / 1e6
+}
+
+fn axis_label(value: f64) -> String {
+    if value.abs() >= 10000.0 || (value != 0.0 && value.abs() < 0.01) {
+        format!("{:.2e}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+struct Chart<'a> {
+    trend: &'a Trend,
+}
+
+impl<'a> canvas::Program<Protocol> for Chart<'a> {
+    type State = ();
+
+    // Scrolling over the chart zooms into the newest part of the window.
+    fn update(
+        &self,
+        _state: &mut (),
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Protocol>) {
+        if !cursor.is_over(&bounds) {
+            return (event::Status::Ignored, None);
+        }
+        match event {
+            canvas::Event::Mouse(iced::mouse::Event::WheelScrolled { delta }) => {
+                let y = match delta {
+                    ScrollDelta::Lines { y, .. } | ScrollDelta::Pixels { y, .. } => y,
+                };
+                let step = if y > 0.0 {
+                    1
+                } else if y < 0.0 {
+                    -1
+                } else {
+                    0
+                };
+                (
+                    event::Status::Captured,
+                    Some(Protocol::TrendCommand(TrendCommand::Zoom(step))),
+                )
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &(),
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let text_color = theme.palette().text;
+        let grid_color = Color {
+            a: 0.2,
+            ..text_color
+        };
+        let mut frame = Frame::new(bounds.size());
+        let plot = Rectangle::new(
+            Point::new(70.0, 10.0),
+            Size::new(
+                (bounds.width - 80.0).max(1.0),
+                (bounds.height - 35.0).max(1.0),
+            ),
+        );
+        let (end, seconds) = self.trend.span();
+        let (y_min, y_max) = self.trend.y_bounds(end, seconds);
+        let x_of = |age: f64| plot.x + plot.width * (1.0 - (age / seconds) as f32);
+        let y_of = |value: f64| {
+            let y = plot.y + plot.height * (1.0 - ((value - y_min) / (y_max - y_min)) as f32);
+            y.clamp(plot.y, plot.y + plot.height)
+        };
+
+        for i in 0..=4 {
+            let value = y_min + (y_max - y_min) * f64::from(i) / 4.0;
+            let y = y_of(value);
+            frame.stroke(
+                &Path::line(Point::new(plot.x, y), Point::new(plot.x + plot.width, y)),
+                Stroke::default().with_color(grid_color).with_width(1.0),
+            );
+            frame.fill_text(Text {
+                content: axis_label(value),
+                position: Point::new(plot.x - 5.0, y),
+                color: text_color,
+                size: 12.0,
+                horizontal_alignment: alignment::Horizontal::Right,
+                vertical_alignment: alignment::Vertical::Center,
+                ..Text::default()
+            });
+        }
+        frame.stroke(
+            &Path::rectangle(plot.position(), plot.size()),
+            Stroke::default().with_color(text_color).with_width(1.0),
+        );
+        let right_label = match self.trend.paused {
+            Some(t) => format!("Paused {}", t.format("%H:%M:%S")),
+            None => "Now".to_string(),
+        };
+        for (label, x, align) in [
+            (
+                format!("-{:.1}s", seconds),
+                plot.x,
+                alignment::Horizontal::Left,
+            ),
+            (
+                right_label,
+                plot.x + plot.width,
+                alignment::Horizontal::Right,
+            ),
+        ] {
+            frame.fill_text(Text {
+                content: label,
+                position: Point::new(x, plot.y + plot.height + 5.0),
+                color: text_color,
+                size: 12.0,
+                horizontal_alignment: align,
+                ..Text::default()
+            });
+        }
+
+        for (idx, name) in self.trend.series.iter().enumerate() {
+            let points: Vec<Point> = match self.trend.samples.get(name) {
+                Some(samples) => samples
+                    .iter()
+                    .map(|(t, v)| (age(end, *t), *v))
+                    .filter(|(age, _)| *age >= 0.0 && *age <= seconds)
+                    .map(|(age, v)| Point::new(x_of(age), y_of(v)))
+                    .collect(),
+                None => continue,
+            };
+            if points.len() < 2 {
+                continue;
+            }
+            let line = Path::new(|b| {
+                b.move_to(points[0]);
+                for p in &points[1..] {
+                    b.line_to(*p);
+                }
+            });
+            frame.stroke(
+                &line,
+                Stroke::default()
+                    .with_color(series_color(idx))
+                    .with_width(2.0),
+            );
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+pub fn trend<'a>(trend: &'a Trend, table: &Table) -> Element<'a, Protocol> {
+    let names: Vec<String> = table
+        .registers()
+        .iter()
+        .map(|x| x.name().trim().to_string())
+        .filter(|x| !x.is_empty() && !trend.series.contains(x))
+        .fold(Vec::new(), |mut names, x| {
+            if !names.contains(&x) {
+                names.push(x);
+            }
+            names
+        });
+    let add = pick_list(names, None, |x| {
+        Protocol::TrendCommand(TrendCommand::AddSeries(x))
+    })
+    .placeholder("Add series")
+    .width(150.0);
+    let window = text_input("60", &trend.window.as_secs().to_string(), |x| {
+        match x.parse::<u64>() {
+            Ok(n) => Protocol::TrendCommand(TrendCommand::SetWindow(Duration::from_secs(n))),
+            Err(_e) => Protocol::TrendCommand(TrendCommand::None),
+        }
+    })
+    .width(60.0);
+    let y_range = pick_list(&YRange::ALL[..], Some(trend.y_range), |x| {
+        Protocol::TrendCommand(TrendCommand::SetYRange(x))
+    })
+    .width(110.0);
+    let mut controls = row![add, text("Window (s)"), window, y_range]
+        .spacing(10.0)
+        .align_items(Alignment::Center);
+    if trend.y_range == YRange::Fixed {
+        let y_min = trend
+            .y_min_text
+            .clone()
+            .unwrap_or_else(|| trend.y_min.to_string());
+        let y_max = trend
+            .y_max_text
+            .clone()
+            .unwrap_or_else(|| trend.y_max.to_string());
+        controls = controls
+            .push(
+                text_input("Min", &y_min, |x| {
+                    Protocol::TrendCommand(TrendCommand::SetYMin(x))
+                })
+                .width(70.0),
+            )
+            .push(
+                text_input("Max", &y_max, |x| {
+                    Protocol::TrendCommand(TrendCommand::SetYMax(x))
+                })
+                .width(70.0),
+            );
+    }
+    let pause = if trend.paused.is_some() {
+        button("Resume").on_press(Protocol::TrendCommand(TrendCommand::Resume))
+    } else {
+        button("Pause").on_press(Protocol::TrendCommand(TrendCommand::Pause))
+    };
+    controls = controls
+        .push(pause)
+        .push(button("+").on_press(Protocol::TrendCommand(TrendCommand::Zoom(1))))
+        .push(button("-").on_press(Protocol::TrendCommand(TrendCommand::Zoom(-1))))
+        .push(button("Clear").on_press(Protocol::TrendCommand(TrendCommand::Clear)));
+
+    if trend.series.is_empty() {
+        return controls.padding(10.0).into();
+    }
+    let legend = Row::with_children(
+        trend
+            .series
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let color = series_color(idx);
+                let latest = trend
+                    .samples
+                    .get(name)
+                    .and_then(|x| x.back())
+                    .map(|(_, v)| axis_label(*v))
+                    .unwrap_or_default();
+                row![
+                    text(format!("{} {}", name, latest)).style(color),
+                    button("x").on_press(Protocol::TrendCommand(TrendCommand::RemoveSeries(
+                        name.clone()
+                    ))),
+                ]
+                .spacing(5.0)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect(),
+    )
+    .spacing(20.0);
+    column![
+        controls,
+        chart_canvas(Chart { trend })
+            .width(Length::Fill)
+            .height(250.0),
+        legend,
+    ]
+    .spacing(10.0)
+    .padding(10.0)
+    .into()
+}
@@ -0,0 +1,27 @@
+use crate::gui::app::{App, AppState};
+use crate::gui::components::common::{header, screen};
+use crate::gui::components::connection::connection;
+use crate::gui::components::diagnostics::diagnostics;
+use crate::gui::protocol::Protocol;
+
+use iced::widget::{button, column, text, Column};
+use iced::{Alignment, Element, Length};
+
+pub fn diagnostics_page(app: &App) -> Element<Protocol> {
+    let mut c = Column::new()
+        .width(Length::Fill)
+        .align_items(Alignment::Center);
+    c = c.push(connection(app));
+    c = c.push(button("Back").on_press(Protocol::ChangePage(AppState::HomePage)));
+    if app.connected {
+        c = c.push(diagnostics(&app.diagnostics, &app.connection));
+    } else {
+        c = c.push(text("Connect to a device to run diagnostics"));
+    }
+    screen(
+        column![header(), c]
+            .width(Length::Fill)
+            .align_items(Alignment::Center),
+    )
+    .into()
+}
@@ -1,27 +1,51 @@
-use crate::gui::app::App;
+use crate::gui::app::{App, AppState};
+use crate::gui::components::action::actions;
 use crate::gui::components::common::{header, screen};
 use crate::gui::components::connection::connection;
+use crate::gui::components::device_info::device_info;
+use crate::gui::components::history::history;
+use crate::gui::components::logger::logger;
+use crate::gui::components::script::scripts;
+use crate::gui::components::simulator::simulator;
 use crate::gui::components::table::table;
-use crate::gui::components::request::RequestParams;
+use crate::gui::components::trend::trend;
 use crate::gui::protocol::Protocol;
+use crate::transport::Direction;
 
-use iced::widget::{column, row, scrollable, text, Column, Container, tooltip};
+use iced::widget::{button, column, row, scrollable, text, Column, Container};
 use iced::{Alignment, Element, Color};
 use iced::{Length, Padding, Renderer};
 use crate::gui::style::ContainerStyle;
 
-fn raw_data_viewer<'a>() -> Element<'a, Protocol> {
+fn raw_data_viewer(app: &App) -> Element<Protocol> {
     let column_widths = 200;
-    let tx_data =
-        Column::with_children((0..100).map(|idx| text(idx).into()).collect()).width(column_widths);
-    let rx_data =
-        Column::with_children((0..100).map(|idx| text(idx).into()).collect()).width(column_widths);
+    let time_width = 100;
+    let frames = Column::with_children(
+        app.raw_frames
+            .iter()
+            .map(|frame| {
+                let (tx, rx) = match frame.direction {
+                    Direction::Tx => (frame.hex(), String::new()),
+                    Direction::Rx => (String::new(), frame.hex()),
+                };
+                row![
+                    text(frame.timestamp.format("%H:%M:%S%.3f")).width(time_width),
+                    text(tx).width(column_widths),
+                    text(rx).width(column_widths),
+                ]
+                .into()
+            })
+            .collect(),
+    );
     Container::new(column![
         row![
+            text("Time").width(time_width),
             text("Tx Data").width(column_widths),
             text("Rx Data").width(column_widths),
-        ],
-        scrollable(row![tx_data, rx_data])
+            button("Clear").on_press(Protocol::ClearRawFrames),
+        ]
+        .align_items(Alignment::Center),
+        scrollable(frames)
             .height(Length::Fill)
             .vertical_scroll(
                 iced::widget::scrollable::Properties::new()
@@ -30,75 +54,8 @@ fn raw_data_viewer<'a>() -> Element<'a, Protocol> {
                     .scroller_width(1.0),
             )
     ])
-    .padding(Padding::from([0, 20]))
-    .into()
-    // scrollable(rc).height(Length::Fixed(400.0)).vertical_scroll(
-    //     iced::widget::scrollable::Properties::new()
-    //         .width(1.0)
-    //         .margin(1.0)
-    //         .scroller_width(1.0),
-    // ),
-    // .style(iced::theme::Container::Custom(Box::new(ContainerStyle {
-    //     text_color: None,
-    //     background: None,
-    //     border_radius: 0.0,
-    //     border_width: 2.0,
-    //     border_color: Color::WHITE,
-    // })))
-}
-
-pub fn request_history_row(request: &RequestParams) -> Container<Protocol>
-{
-    let mut req_type = text(request.request_type.to_string()).width(150.0);
-    let mut addr = text(request.get_address()).width(75.0);
-    let mut data = request.get_data();
-    Container::new(row![
-                   req_type,
-                   addr,
-                   data
-        ])
-        .padding(5.0)
-        .center_x()
-        .center_y()
-        // .style(iced::theme::Container::Custom(Box::new(ContainerStyle {
-        //     text_color: None,
-        //     background: None,
-        //     border_radius: 0.0,
-        //     border_width: 1.0,
-        //     border_color: Color::WHITE,
-        // })))
-}
-
-pub fn request_history(app: &App) -> Element<Protocol> {
-    let requests = Column::with_children(
-        app.request_history.iter().map(|x| {
-            tooltip(
-                request_history_row(x),
-                "Click to Load",
-                iced_native::widget::tooltip::Position::FollowCursor
-            ).into()
-
-        }).collect()
-    )
-    .align_items(Alignment::Center);
-    column![
-        "Request History",
-        row![
-            text("Request Type").width(150.0),
-            text("Address").width(75.0),
-            text("Data").width(200.0)
-        ],
-        scrollable(requests).height(Length::Fill).vertical_scroll(
-            iced::widget::scrollable::Properties::new()
-                .width(1.0)
-                .margin(1.0)
-                .scroller_width(1.0),
-        )
-    ]
-    .spacing(10.0)
     .height(200)
-    .width(Length::Fill)
-    .align_items(Alignment::Center)
+    .padding(Padding::from([0, 20]))
     .into()
 }
 
@@ -108,10 +65,17 @@ pub fn home_page(app: &App) -> Element<Protocol> {
         .align_items(Alignment::Center);
     c = c.push(connection(app));
     if app.connected {
-        c = c.push(app.request_params.view());
-        c = c.push(request_history(&app));
+        c = c.push(button("Diagnostics").on_press(Protocol::ChangePage(AppState::DiagnosticsPage)));
+        c = c.push(app.request_params.view(app.connection.byte_order()));
+        c = c.push(actions(&app.actions));
+        c = c.push(scripts(&app.scripts));
+        c = c.push(device_info(&app.device_info));
+        c = c.push(logger(&app.logger));
+        c = c.push(trend(&app.trend, &app.table));
+        c = c.push(row![history(&app.request_history), raw_data_viewer(&app)]);
     };
-    c = c.push(table(&app.table));
+    c = c.push(simulator(&app.simulator));
+    c = c.push(table(&app.table, app.connection.byte_order()));
     screen(
         column![
             header(),
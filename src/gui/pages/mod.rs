@@ -1 +1,2 @@
+pub mod diagnostics_page;
 pub mod home_page;
@@ -1,8 +1,18 @@
-use crate::controller::WorkerEvent;
+use crate::controller::{ReconnectPolicy, RetryOverride, RetryPolicy, WorkerEvent};
 use crate::gui::components::connection::ConnectionParams;
-use crate::gui::app::App;
+use crate::gui::app::{App, AppState};
+use crate::gui::components::action::ActionCommand;
+use crate::gui::components::device_info::DeviceInfoCommand;
+use crate::gui::components::diagnostics::DiagnosticsCommand;
+use crate::gui::components::history::HistoryCommand;
+use crate::gui::components::logger::LoggerCommand;
+use crate::gui::components::script::ScriptCommand;
+use crate::script::ScriptEvent;
+use crate::gui::components::simulator::SimulatorCommand;
 use crate::gui::components::request::{RequestParams, RequestUpdate};
 use crate::gui::components::table::TableCommand;
+use crate::gui::components::trend::TrendCommand;
+use crate::simulator::SimulatorEvent;
 use tokio_modbus::prelude::Request;
 
 #[derive(Debug, Clone)]
@@ -10,11 +20,26 @@ pub enum Protocol {
     Debug,
     ConnectionChanged(ConnectionParams),
     Connect(ConnectionParams),
+    ReconnectChanged(ReconnectPolicy),
+    RetryChanged(RetryPolicy),
+    SetStopOnException(bool),
     Disconnect,
     WorkerEvent(WorkerEvent),
     TableCommand(TableCommand),
-    ModbusRequest(Request),
+    HistoryCommand(HistoryCommand),
+    ActionCommand(ActionCommand),
+    DeviceInfoCommand(DeviceInfoCommand),
+    DiagnosticsCommand(DiagnosticsCommand),
+    ChangePage(AppState),
+    ScriptCommand(ScriptCommand),
+    ScriptEvent(ScriptEvent),
+    LoggerCommand(LoggerCommand),
+    TrendCommand(TrendCommand),
+    SimulatorCommand(SimulatorCommand),
+    SimulatorEvent(SimulatorEvent),
+    ModbusRequest(Request, Option<u8>, RetryOverride),
     ExecuteRequest,
+    PollTable,
     StartPoll,
     StopPoll,
     RequestChanged(RequestParams),
@@ -25,6 +50,7 @@ pub enum Protocol {
     OpenFileDialog,
     SaveFileDialog,
     CloseModal,
+    ClearRawFrames,
     ApplyApp(App),
     SaveFileWithPath(String), 
     None,
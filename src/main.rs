@@ -2,8 +2,11 @@ use gui::app::run_app;
 use iced;
 use log::debug;
 
-pub mod controller;
+pub use rustbus_core::{controller, data_type, transport};
+
 pub mod gui;
+pub mod script;
+pub mod simulator;
 
 
 fn main() -> iced::Result {
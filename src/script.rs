@@ -0,0 +1,278 @@
+use crate::controller::Commands;
+use crate::data_type::{ByteOrder, DisplayFormat};
+use crate::gui::components::table::Register;
+use iced::{subscription, Subscription};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FLOAT, INT};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio_modbus::prelude::{Request, Response};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+#[derive(Debug, Clone)]
+pub enum ScriptEvent {
+    Log(String),
+    Finished(Result<(), String>),
+}
+
+// The receiving end of a run's events, taken by its subscription once.
+pub type ScriptEvents = Arc<Mutex<Option<UnboundedReceiver<ScriptEvent>>>>;
+
+enum EventsState {
+    Starting(ScriptEvents),
+    Running(UnboundedReceiver<ScriptEvent>),
+    Finished,
+}
+
+// Output of run `id`, it ends with the run.
+pub fn events(id: u32, events: ScriptEvents) -> Subscription<ScriptEvent> {
+    struct Script;
+    subscription::unfold(
+        (std::any::TypeId::of::<Script>(), id),
+        EventsState::Starting(events),
+        |state| async move {
+            match state {
+                EventsState::Starting(events) => {
+                    let rx = events.lock().unwrap().take();
+                    match rx {
+                        Some(rx) => (None, EventsState::Running(rx)),
+                        None => (None, EventsState::Finished),
+                    }
+                }
+                EventsState::Running(mut rx) => match rx.recv().await {
+                    Some(ScriptEvent::Finished(result)) => {
+                        (Some(ScriptEvent::Finished(result)), EventsState::Finished)
+                    }
+                    Some(ev) => (Some(ev), EventsState::Running(rx)),
+                    None => (None, EventsState::Finished),
+                },
+                EventsState::Finished => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+// Everything a running script can reach. Requests go through the worker like
+// any other command and the reply comes back on a channel of their own, the
+// output goes straight to the front-end.
+struct ScriptContext {
+    tx: UnboundedSender<Commands>,
+    events: UnboundedSender<ScriptEvent>,
+    unit: Mutex<Option<u8>>,
+    registers: Vec<Register>,
+    order: ByteOrder,
+    stop: Arc<AtomicBool>,
+}
+
+impl ScriptContext {
+    fn call(&self, req: Request, unit: Option<u8>) -> ScriptResult<Response> {
+        let (reply, mut rx) = unbounded_channel();
+        let unit = unit.or(*self.unit.lock().unwrap());
+        self.tx
+            .send(Commands::ScriptRequest(req, unit, reply))
+            .map_err(|_e| "Worker is not running")?;
+        match rx.blocking_recv() {
+            Some(Ok(resp)) => Ok(resp),
+            Some(Err(e)) => Err(e.into()),
+            None => Err("Connection lost".into()),
+        }
+    }
+
+    fn log(&self, line: String) {
+        let _ = self.events.send(ScriptEvent::Log(line));
+    }
+
+    fn register(&self, name: &str) -> ScriptResult<&Register> {
+        self.registers
+            .iter()
+            .find(|x| x.name() == name)
+            .ok_or_else(|| format!("No register named '{}'", name).into())
+    }
+
+    fn sleep(&self, ms: INT) {
+        let mut left = Duration::from_millis(u64::try_from(ms).unwrap_or(0));
+        let tick = Duration::from_millis(50);
+        while !left.is_zero() && !self.stop.load(Ordering::Relaxed) {
+            let step = left.min(tick);
+            std::thread::sleep(step);
+            left -= step;
+        }
+    }
+}
+
+fn to_u16(x: INT) -> ScriptResult<u16> {
+    u16::try_from(x).map_err(|_e| format!("{} is not a valid address or quantity", x).into())
+}
+
+// Register values may be given signed, they are sent as their 16 bit pattern.
+fn to_word(x: INT) -> ScriptResult<u16> {
+    match x {
+        0..=0xFFFF => Ok(x as u16),
+        -0x8000..=-1 => Ok(x as i16 as u16),
+        _ => Err(format!("{} does not fit in a register", x).into()),
+    }
+}
+
+fn words(values: Array) -> ScriptResult<Vec<u16>> {
+    values
+        .into_iter()
+        .map(|x| to_word(x.as_int()?))
+        .collect()
+}
+
+fn bits(values: Array) -> ScriptResult<Vec<bool>> {
+    values
+        .into_iter()
+        .map(|x| x.as_bool().or_else(|_e| x.as_int().map(|x| x != 0)))
+        .collect::<Result<Vec<bool>, &str>>()
+        .map_err(|e| e.into())
+}
+
+fn to_number(value: &Dynamic) -> ScriptResult<f64> {
+    if let Ok(x) = value.as_float() {
+        Ok(x)
+    } else if let Ok(x) = value.as_int() {
+        Ok(x as f64)
+    } else if let Ok(x) = value.as_bool() {
+        Ok(f64::from(u8::from(x)))
+    } else {
+        Err(format!("{} is not a number", value).into())
+    }
+}
+
+fn bit_array(bits: Vec<bool>, count: u16) -> Array {
+    bits.into_iter()
+        .take(usize::from(count))
+        .map(Dynamic::from)
+        .collect()
+}
+
+fn word_array(words: Vec<u16>) -> Array {
+    words.into_iter().map(|x| Dynamic::from(INT::from(x))).collect()
+}
+
+fn engine(ctx: Arc<ScriptContext>) -> Engine {
+    let mut engine = Engine::new();
+
+    let c = ctx.clone();
+    engine.on_print(move |s| c.log(s.to_string()));
+    let c = ctx.clone();
+    engine.on_progress(move |_ops| {
+        if c.stop.load(Ordering::Relaxed) {
+            Some("Stopped".into())
+        } else {
+            None
+        }
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("log", move |s: &str| c.log(s.to_string()));
+    let c = ctx.clone();
+    engine.register_fn("sleep", move |ms: INT| c.sleep(ms));
+    let c = ctx.clone();
+    engine.register_fn("set_unit", move |unit: INT| -> ScriptResult<()> {
+        let unit = u8::try_from(unit).map_err(|_e| format!("{} is not a valid unit", unit))?;
+        *c.unit.lock().unwrap() = Some(unit);
+        Ok(())
+    });
+    let c = ctx.clone();
+    engine.register_fn("default_unit", move || {
+        *c.unit.lock().unwrap() = None;
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("read_coils", move |addr: INT, qty: INT| -> ScriptResult<Array> {
+        let qty = to_u16(qty)?;
+        match c.call(Request::ReadCoils(to_u16(addr)?, qty), None)? {
+            Response::ReadCoils(bits) => Ok(bit_array(bits, qty)),
+            other => Err(format!("Unexpected response {:?}", other).into()),
+        }
+    });
+    let c = ctx.clone();
+    engine.register_fn("read_discrete", move |addr: INT, qty: INT| -> ScriptResult<Array> {
+        let qty = to_u16(qty)?;
+        match c.call(Request::ReadDiscreteInputs(to_u16(addr)?, qty), None)? {
+            Response::ReadDiscreteInputs(bits) => Ok(bit_array(bits, qty)),
+            other => Err(format!("Unexpected response {:?}", other).into()),
+        }
+    });
+    let c = ctx.clone();
+    engine.register_fn("read_input", move |addr: INT, qty: INT| -> ScriptResult<Array> {
+        match c.call(Request::ReadInputRegisters(to_u16(addr)?, to_u16(qty)?), None)? {
+            Response::ReadInputRegisters(words) => Ok(word_array(words)),
+            other => Err(format!("Unexpected response {:?}", other).into()),
+        }
+    });
+    let c = ctx.clone();
+    engine.register_fn("read_holding", move |addr: INT, qty: INT| -> ScriptResult<Array> {
+        match c.call(Request::ReadHoldingRegisters(to_u16(addr)?, to_u16(qty)?), None)? {
+            Response::ReadHoldingRegisters(words) => Ok(word_array(words)),
+            other => Err(format!("Unexpected response {:?}", other).into()),
+        }
+    });
+    let c = ctx.clone();
+    engine.register_fn("write_coil", move |addr: INT, value: bool| -> ScriptResult<()> {
+        c.call(Request::WriteSingleCoil(to_u16(addr)?, value), None)?;
+        Ok(())
+    });
+    let c = ctx.clone();
+    engine.register_fn("write_coils", move |addr: INT, values: Array| -> ScriptResult<()> {
+        c.call(Request::WriteMultipleCoils(to_u16(addr)?, bits(values)?), None)?;
+        Ok(())
+    });
+    let c = ctx.clone();
+    engine.register_fn("write_register", move |addr: INT, value: INT| -> ScriptResult<()> {
+        c.call(Request::WriteSingleRegister(to_u16(addr)?, to_word(value)?), None)?;
+        Ok(())
+    });
+    let c = ctx.clone();
+    engine.register_fn("write_registers", move |addr: INT, values: Array| -> ScriptResult<()> {
+        c.call(Request::WriteMultipleRegisters(to_u16(addr)?, words(values)?), None)?;
+        Ok(())
+    });
+
+    // Named registers use the table's type, scaling and byte order.
+    let c = ctx.clone();
+    engine.register_fn("get", move |name: &str| -> ScriptResult<Dynamic> {
+        let reg = c.register(name)?;
+        let resp = c.call(reg.read_request(), reg.unit())?;
+        let reg = reg.with_response(resp)?;
+        Ok(match reg.engineering_value(c.order) {
+            Some(value) => Dynamic::from(value as FLOAT),
+            None => Dynamic::from(reg.value_text(c.order, DisplayFormat::Decimal)),
+        })
+    });
+    let c = ctx;
+    engine.register_fn("set", move |name: &str, value: Dynamic| -> ScriptResult<()> {
+        let reg = c.register(name)?;
+        let req = reg.write_request(to_number(&value)?, c.order)?;
+        c.call(req, reg.unit())?;
+        Ok(())
+    });
+
+    engine
+}
+
+// Runs a script to the end on the calling thread, which must not be one of
+// the async runtime's. The outcome is the last of its events.
+pub fn run(
+    source: String,
+    tx: UnboundedSender<Commands>,
+    events: UnboundedSender<ScriptEvent>,
+    registers: Vec<Register>,
+    order: ByteOrder,
+    stop: Arc<AtomicBool>,
+) {
+    let ctx = Arc::new(ScriptContext {
+        tx,
+        events: events.clone(),
+        unit: Mutex::new(None),
+        registers,
+        order,
+        stop,
+    });
+    let result = engine(ctx).run(&source).map_err(|e| e.to_string());
+    let _ = events.send(ScriptEvent::Finished(result));
+}
@@ -0,0 +1,426 @@
+use crate::gui::components::connection::serial::SerialPortParams;
+use crate::gui::components::table::{RegisterType, Table};
+use iced::{subscription, Subscription};
+use rustbus_core::file_record::{self, FileRecord};
+use rustbus_core::{device_id, diagnostics};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio_modbus::prelude::{Request, Response};
+
+pub mod rtu;
+pub mod tcp;
+
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+const ILLEGAL_DATA_VALUE: u8 = 0x03;
+
+// Fault injection for the RTU slave, percentages are per request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Faults {
+    pub delay: Duration,
+    pub crc_errors: u8,
+    pub dropped: u8,
+}
+
+// Serial line counters reported through diagnostics (FC 0x08).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Counters {
+    pub bus_messages: u16,
+    pub bus_errors: u16,
+    pub exceptions: u16,
+    pub server_messages: u16,
+    pub no_response: u16,
+}
+
+#[derive(Debug, Clone)]
+pub enum SimulatorConfig {
+    Tcp(u16),
+    Rtu(SerialPortParams),
+}
+
+// Values served to masters, reloaded from the register table whenever it
+// changes. Addresses not in the table answer with an exception.
+#[derive(Debug, Default)]
+pub struct SimulatorData {
+    coils: BTreeMap<u16, bool>,
+    discrete_inputs: BTreeMap<u16, bool>,
+    input_registers: BTreeMap<u16, u16>,
+    holding_registers: BTreeMap<u16, u16>,
+    // File records by (file, record), unwritten records read as 0.
+    files: BTreeMap<(u16, u16), u16>,
+    pub faults: Faults,
+    pub counters: Counters,
+}
+
+#[derive(Debug, Clone)]
+pub enum SimulatorEvent {
+    Listening(String),
+    // Unit the write was served as, then the request and the response.
+    Written(u8, Request, Response),
+    Error(String),
+}
+
+fn read_bits(map: &BTreeMap<u16, bool>, start: u16, qty: u16) -> Result<Vec<bool>, u8> {
+    if qty == 0 || qty > 2000 {
+        return Err(ILLEGAL_DATA_VALUE);
+    }
+    (u32::from(start)..u32::from(start) + u32::from(qty))
+        .map(|x| u16::try_from(x).ok().and_then(|x| map.get(&x).copied()))
+        .collect::<Option<Vec<bool>>>()
+        .ok_or(ILLEGAL_DATA_ADDRESS)
+}
+
+fn read_words(map: &BTreeMap<u16, u16>, start: u16, qty: u16) -> Result<Vec<u16>, u8> {
+    if qty == 0 || qty > 125 {
+        return Err(ILLEGAL_DATA_VALUE);
+    }
+    (u32::from(start)..u32::from(start) + u32::from(qty))
+        .map(|x| u16::try_from(x).ok().and_then(|x| map.get(&x).copied()))
+        .collect::<Option<Vec<u16>>>()
+        .ok_or(ILLEGAL_DATA_ADDRESS)
+}
+
+// Checks every address first so a rejected write changes nothing.
+fn write_values<T: Copy>(map: &mut BTreeMap<u16, T>, start: u16, values: &[T]) -> Result<(), u8> {
+    let end = u32::from(start) + values.len() as u32;
+    if values.is_empty()
+        || end > 0x10000
+        || (start..=(end - 1) as u16).any(|x| !map.contains_key(&x))
+    {
+        return Err(ILLEGAL_DATA_ADDRESS);
+    }
+    for (address, value) in (start..).zip(values) {
+        map.insert(address, *value);
+    }
+    Ok(())
+}
+
+impl SimulatorData {
+    // Faults, counters and files are kept, they do not come from the table.
+    pub fn load_table(&mut self, table: &Table) {
+        *self = SimulatorData {
+            files: std::mem::take(&mut self.files),
+            faults: self.faults,
+            counters: self.counters,
+            ..SimulatorData::default()
+        };
+        for reg in table.registers() {
+            let words = reg.words();
+            let addresses = reg.address()..;
+            match reg.register_type() {
+                RegisterType::Coil => {
+                    self.coils.insert(reg.address(), words[0] != 0);
+                }
+                RegisterType::DiscreteInputs => {
+                    self.discrete_inputs.insert(reg.address(), words[0] != 0);
+                }
+                RegisterType::InputRegister => self
+                    .input_registers
+                    .extend(addresses.zip(words.iter().copied())),
+                RegisterType::HoldingRegister => self
+                    .holding_registers
+                    .extend(addresses.zip(words.iter().copied())),
+            }
+        }
+    }
+
+    fn handle(&mut self, req: Request) -> Result<Response, u8> {
+        match req {
+            Request::ReadCoils(start, qty) => {
+                read_bits(&self.coils, start, qty).map(Response::ReadCoils)
+            }
+            Request::ReadDiscreteInputs(start, qty) => {
+                read_bits(&self.discrete_inputs, start, qty).map(Response::ReadDiscreteInputs)
+            }
+            Request::ReadInputRegisters(start, qty) => {
+                read_words(&self.input_registers, start, qty).map(Response::ReadInputRegisters)
+            }
+            Request::ReadHoldingRegisters(start, qty) => {
+                read_words(&self.holding_registers, start, qty).map(Response::ReadHoldingRegisters)
+            }
+            Request::WriteSingleCoil(address, value) => {
+                write_values(&mut self.coils, address, &[value])
+                    .map(|_| Response::WriteSingleCoil(address, value))
+            }
+            Request::WriteMultipleCoils(start, values) => {
+                write_values(&mut self.coils, start, &values)
+                    .map(|_| Response::WriteMultipleCoils(start, values.len() as u16))
+            }
+            Request::WriteSingleRegister(address, value) => {
+                write_values(&mut self.holding_registers, address, &[value])
+                    .map(|_| Response::WriteSingleRegister(address, value))
+            }
+            Request::WriteMultipleRegisters(start, values) => {
+                write_values(&mut self.holding_registers, start, &values)
+                    .map(|_| Response::WriteMultipleRegisters(start, values.len() as u16))
+            }
+            Request::MaskWriteRegister(address, and_mask, or_mask) => {
+                let word = *self
+                    .holding_registers
+                    .get(&address)
+                    .ok_or(ILLEGAL_DATA_ADDRESS)?;
+                self.holding_registers
+                    .insert(address, (word & and_mask) | (or_mask & !and_mask));
+                Ok(Response::MaskWriteRegister(address, and_mask, or_mask))
+            }
+            Request::ReadWriteMultipleRegisters(read_start, qty, write_start, values) => {
+                // Both ranges are checked before anything is written.
+                read_words(&self.holding_registers, read_start, qty)?;
+                write_values(&mut self.holding_registers, write_start, &values)?;
+                read_words(&self.holding_registers, read_start, qty)
+                    .map(Response::ReadWriteMultipleRegisters)
+            }
+            Request::Custom(device_id::FUNCTION, data) => self.identify(&data),
+            Request::Custom(diagnostics::READ_EXCEPTION_STATUS, data) if data.is_empty() => Ok(
+                Response::Custom(diagnostics::READ_EXCEPTION_STATUS, vec![0x00]),
+            ),
+            Request::Custom(diagnostics::DIAGNOSTICS, data) => self.diagnose(&data),
+            Request::Custom(file_record::READ_FILE_RECORD, _) => self.read_file(&req),
+            Request::Custom(file_record::WRITE_FILE_RECORD, _) => self.write_file(&req),
+            Request::Custom(file_record::READ_FIFO_QUEUE, _) => self.read_fifo(&req),
+            _ => Err(ILLEGAL_FUNCTION),
+        }
+    }
+
+    fn file_range(rec: &FileRecord, length: u16) -> Result<(), u8> {
+        let last = u32::from(rec.record) + u32::from(length);
+        if rec.file == 0 || length == 0 || last > u32::from(file_record::MAX_RECORD) + 1 {
+            return Err(ILLEGAL_DATA_ADDRESS);
+        }
+        Ok(())
+    }
+
+    // Only single group requests, which is what the request builder sends.
+    fn read_file(&self, req: &Request) -> Result<Response, u8> {
+        let rec = FileRecord::from_request(req).ok_or(ILLEGAL_DATA_VALUE)?;
+        if rec.length > 124 {
+            return Err(ILLEGAL_DATA_VALUE);
+        }
+        Self::file_range(&rec, rec.length)?;
+        let mut resp = vec![0, 0, 0x06];
+        for record in rec.record..rec.record + rec.length {
+            let word = self.files.get(&(rec.file, record)).copied().unwrap_or(0);
+            resp.extend_from_slice(&word.to_be_bytes());
+        }
+        resp[1] = (resp.len() - 2) as u8;
+        resp[0] = (resp.len() - 1) as u8;
+        Ok(Response::Custom(file_record::READ_FILE_RECORD, resp))
+    }
+
+    fn write_file(&mut self, req: &Request) -> Result<Response, u8> {
+        let rec = FileRecord::from_request(req).ok_or(ILLEGAL_DATA_VALUE)?;
+        Self::file_range(&rec, rec.length)?;
+        for (record, value) in (rec.record..).zip(rec.values.iter()) {
+            self.files.insert((rec.file, record), *value);
+        }
+        // The answer echoes the request.
+        let pdu = bytes::Bytes::from(req.clone());
+        Ok(Response::Custom(file_record::WRITE_FILE_RECORD, pdu[1..].to_vec()))
+    }
+
+    // The holding register at the pointer address holds the count, the
+    // queued values follow it.
+    fn read_fifo(&self, req: &Request) -> Result<Response, u8> {
+        let address = file_record::fifo_address(req).ok_or(ILLEGAL_DATA_VALUE)?;
+        let count = *self
+            .holding_registers
+            .get(&address)
+            .ok_or(ILLEGAL_DATA_ADDRESS)?;
+        if count > file_record::MAX_FIFO_COUNT {
+            return Err(ILLEGAL_DATA_VALUE);
+        }
+        let values = match count {
+            0 => Vec::new(),
+            _ => read_words(&self.holding_registers, address.wrapping_add(1), count)?,
+        };
+        let mut resp = (2 + 2 * count).to_be_bytes().to_vec();
+        resp.extend_from_slice(&count.to_be_bytes());
+        for x in values {
+            resp.extend_from_slice(&x.to_be_bytes());
+        }
+        Ok(Response::Custom(file_record::READ_FIFO_QUEUE, resp))
+    }
+
+    // Serial line diagnostics, the sub-function is echoed with its data.
+    fn diagnose(&mut self, data: &[u8]) -> Result<Response, u8> {
+        let sub_function = match data {
+            [hi, lo, ..] => u16::from_be_bytes([*hi, *lo]),
+            _ => return Err(ILLEGAL_DATA_VALUE),
+        };
+        let value = match sub_function {
+            0x00 => return Ok(Response::Custom(diagnostics::DIAGNOSTICS, data.to_vec())),
+            0x01 => {
+                self.counters = Counters::default();
+                return Ok(Response::Custom(diagnostics::DIAGNOSTICS, data.to_vec()));
+            }
+            0x02 => 0,
+            0x0A => {
+                self.counters = Counters::default();
+                0
+            }
+            0x0B => self.counters.bus_messages,
+            0x0C => self.counters.bus_errors,
+            0x0D => self.counters.exceptions,
+            0x0E => self.counters.server_messages,
+            0x0F => self.counters.no_response,
+            0x10..=0x12 => 0,
+            _ => return Err(ILLEGAL_FUNCTION),
+        };
+        let mut resp = data[..2].to_vec();
+        resp.extend_from_slice(&value.to_be_bytes());
+        Ok(Response::Custom(diagnostics::DIAGNOSTICS, resp))
+    }
+
+    // Read Device Identification, stream access only. Every object fits in a
+    // single answer so more never follows.
+    fn identify(&self, data: &[u8]) -> Result<Response, u8> {
+        let (code, start) = match data {
+            [device_id::MEI_TYPE, code @ 0x01..=0x03, start] => (*code, *start),
+            [device_id::MEI_TYPE, ..] => return Err(ILLEGAL_DATA_VALUE),
+            _ => return Err(ILLEGAL_FUNCTION),
+        };
+        let addresses = self.coils.len()
+            + self.discrete_inputs.len()
+            + self.input_registers.len()
+            + self.holding_registers.len();
+        let objects = [
+            (0x00, "Rust Bus".to_string()),
+            (0x01, "RB-SIM".to_string()),
+            (0x02, env!("CARGO_PKG_VERSION").to_string()),
+            (0x04, "Rust Bus Simulator".to_string()),
+            (0x05, "Simulator".to_string()),
+            (0x80, format!("{} addresses", addresses)),
+        ];
+        let last = match code {
+            0x01 => 0x02,
+            0x02 => 0x7F,
+            _ => 0xFF,
+        };
+        // An unknown object id restarts the stream from the beginning.
+        let start = if objects.iter().any(|(id, _)| *id == start && *id <= last) {
+            start
+        } else {
+            0x00
+        };
+        let mut resp = vec![device_id::MEI_TYPE, code, 0x03, 0x00, 0x00, 0x00];
+        for (id, value) in objects.iter().filter(|(id, _)| *id >= start && *id <= last) {
+            resp.push(*id);
+            resp.push(value.len() as u8);
+            resp.extend_from_slice(value.as_bytes());
+            resp[5] += 1;
+        }
+        Ok(Response::Custom(device_id::FUNCTION, resp))
+    }
+}
+
+fn is_write(req: &Request) -> bool {
+    matches!(
+        req,
+        Request::WriteSingleCoil(..)
+            | Request::WriteMultipleCoils(..)
+            | Request::WriteSingleRegister(..)
+            | Request::WriteMultipleRegisters(..)
+            | Request::MaskWriteRegister(..)
+            | Request::ReadWriteMultipleRegisters(..)
+    )
+}
+
+// tokio-modbus 0.7 keeps its exception type private, an exception goes out
+// as a custom response with the error bit set on the function code.
+fn exception(req: &Request, code: u8) -> Response {
+    let pdu = bytes::Bytes::from(req.clone());
+    Response::Custom(pdu[0] | 0x80, vec![code])
+}
+
+// Answers a request from a master for `unit`, writes are passed on so the
+// table can follow them.
+fn respond(
+    data: &Mutex<SimulatorData>,
+    unit: u8,
+    req: Request,
+    events: &UnboundedSender<SimulatorEvent>,
+) -> Response {
+    let res = {
+        let mut data = data.lock().unwrap();
+        data.counters.bus_messages = data.counters.bus_messages.wrapping_add(1);
+        data.counters.server_messages = data.counters.server_messages.wrapping_add(1);
+        let res = data.handle(req.clone());
+        if res.is_err() {
+            data.counters.exceptions = data.counters.exceptions.wrapping_add(1);
+        }
+        res
+    };
+    match res {
+        Ok(resp) => {
+            if is_write(&req) {
+                let _ = events.send(SimulatorEvent::Written(unit, req, resp.clone()));
+            }
+            resp
+        }
+        Err(code) => exception(&req, code),
+    }
+}
+
+// Stops the slave once the subscription is dropped.
+struct ServerGuard {
+    handle: JoinHandle<()>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.handle.abort();
+    }
+}
+
+enum SimulatorState {
+    Starting(SimulatorConfig, Arc<Mutex<SimulatorData>>),
+    Running(UnboundedReceiver<SimulatorEvent>, ServerGuard),
+    Stopped,
+}
+
+pub fn serve(
+    config: SimulatorConfig,
+    data: Arc<Mutex<SimulatorData>>,
+) -> Subscription<SimulatorEvent> {
+    struct Simulator;
+    subscription::unfold(
+        // Serial parameters are not hashable, their debug text identifies them.
+        (std::any::TypeId::of::<Simulator>(), format!("{:?}", config)),
+        SimulatorState::Starting(config, data),
+        |state| async move {
+            match state {
+                SimulatorState::Starting(config, data) => {
+                    let (events, rx) = unbounded_channel();
+                    let stopped = Arc::new(AtomicBool::new(false));
+                    let handle = match config {
+                        SimulatorConfig::Tcp(port) => {
+                            tokio::spawn(tcp::run(port, data, events, stopped.clone()))
+                        }
+                        SimulatorConfig::Rtu(params) => {
+                            tokio::spawn(rtu::run(params, data, events))
+                        }
+                    };
+                    (
+                        None,
+                        SimulatorState::Running(rx, ServerGuard { handle, stopped }),
+                    )
+                }
+                SimulatorState::Running(mut rx, guard) => match rx.recv().await {
+                    Some(SimulatorEvent::Error(e)) => {
+                        (Some(SimulatorEvent::Error(e)), SimulatorState::Stopped)
+                    }
+                    Some(ev) => (Some(ev), SimulatorState::Running(rx, guard)),
+                    None => (None, SimulatorState::Stopped),
+                },
+                SimulatorState::Stopped => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
@@ -0,0 +1,189 @@
+use super::{respond, SimulatorData, SimulatorEvent, ILLEGAL_FUNCTION};
+use crate::gui::components::connection::serial::SerialPortParams;
+use crate::transport::rtu::{decode_adu, encode_adu, frame_gap};
+use bytes::Bytes;
+use log::warn;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_modbus::prelude::{Request, Response};
+use tokio_serial::{SerialPort, SerialPortBuilderExt, SerialStream};
+
+// Port name that asks for a pseudo terminal instead of a real port.
+pub const PTY: &str = "pty";
+
+// Length of the request ADU in `buf` if it can be told from the bytes received
+// so far, `None` if the frame has to be delimited by silence instead.
+fn expected_request_len(buf: &[u8]) -> Option<usize> {
+    let function = *buf.get(1)?;
+    let pdu_len = match function {
+        // Return Query Data echoes however much data the master sent.
+        0x08 if buf.get(2..4)? == [0, 0] => return None,
+        0x01..=0x06 | 0x08 => 5,
+        0x07 | 0x0B | 0x0C | 0x11 => 1,
+        0x0F | 0x10 => 6 + usize::from(*buf.get(6)?),
+        0x14 | 0x15 => 2 + usize::from(*buf.get(2)?),
+        0x16 => 7,
+        0x17 => 10 + usize::from(*buf.get(10)?),
+        0x18 => 3,
+        _ => return None,
+    };
+    Some(pdu_len + 3)
+}
+
+async fn read_frame<T: AsyncRead + Unpin>(port: &mut T, gap: Duration) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 256];
+    loop {
+        let read = if buf.is_empty() {
+            port.read(&mut chunk).await?
+        } else {
+            match tokio::time::timeout(gap, port.read(&mut chunk)).await {
+                Ok(read) => read?,
+                Err(_silence) => return Ok(buf),
+            }
+        };
+        if read == 0 {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+        buf.extend_from_slice(&chunk[..read]);
+        if let Some(len) = expected_request_len(&buf) {
+            if buf.len() >= len {
+                buf.truncate(len);
+                return Ok(buf);
+            }
+        }
+    }
+}
+
+// Good enough to pick which frames to drop or corrupt.
+struct Dice(u64);
+
+impl Dice {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_nanos() as u64)
+            .unwrap_or(0);
+        Dice(seed | 1)
+    }
+
+    fn roll(&mut self, percent: u8) -> bool {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % 100 < u64::from(percent)
+    }
+}
+
+// The second half of a pty pair is what masters open, it is returned so it
+// stays open for as long as the simulator runs.
+#[cfg(unix)]
+fn open_pty() -> Result<(SerialStream, SerialStream, String), Error> {
+    let (master, mut slave) = SerialStream::pair()?;
+    slave.set_exclusive(false)?;
+    let name = slave.name().unwrap_or_default();
+    Ok((master, slave, name))
+}
+
+#[cfg(not(unix))]
+fn open_pty() -> Result<(SerialStream, SerialStream, String), Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "Virtual ports are only available on Unix",
+    ))
+}
+
+pub async fn run(
+    params: SerialPortParams,
+    data: Arc<Mutex<SimulatorData>>,
+    events: UnboundedSender<SimulatorEvent>,
+) {
+    let opened = if params.port.is_empty() || params.port == PTY {
+        open_pty().map(|(port, pty, name)| (port, Some(pty), name))
+    } else {
+        tokio_serial::new(params.port.clone(), params.baudrate)
+            .data_bits(params.data_bits)
+            .flow_control(tokio_serial::FlowControl::None)
+            .stop_bits(params.stop_bits)
+            .parity(params.parity)
+            .timeout(params.timeout)
+            .open_native_async()
+            .map(|port| (port, None, params.port.clone()))
+            .map_err(Error::from)
+    };
+    let (mut port, _pty, name) = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            let _ = events.send(SimulatorEvent::Error(e.to_string()));
+            return;
+        }
+    };
+    let _ = events.send(SimulatorEvent::Listening(format!(
+        "RTU {} slave {}",
+        name, params.address
+    )));
+
+    let gap = frame_gap(params.baudrate);
+    let mut dice = Dice::new();
+    loop {
+        let frame = match read_frame(&mut port, gap).await {
+            Ok(frame) => frame,
+            Err(e) => {
+                let _ = events.send(SimulatorEvent::Error(e.to_string()));
+                return;
+            }
+        };
+        // A real slave stays silent on frames it can not make sense of.
+        let (slave_id, pdu) = match decode_adu(&frame) {
+            Ok(adu) => adu,
+            Err(e) => {
+                warn!("Simulator: {}", e);
+                let mut data = data.lock().unwrap();
+                data.counters.bus_errors = data.counters.bus_errors.wrapping_add(1);
+                continue;
+            }
+        };
+        if slave_id != params.address && slave_id != 0 {
+            let mut data = data.lock().unwrap();
+            data.counters.bus_messages = data.counters.bus_messages.wrapping_add(1);
+            continue;
+        }
+        let pdu = Bytes::from(pdu);
+        let function = pdu[0];
+        let resp = match Request::try_from(pdu) {
+            // Broadcasts are carried out as this slave.
+            Ok(req) => respond(&data, params.address, req, &events),
+            Err(_e) => Response::Custom(function | 0x80, vec![ILLEGAL_FUNCTION]),
+        };
+        // Broadcasts are carried out without a reply.
+        if slave_id == 0 {
+            let mut data = data.lock().unwrap();
+            data.counters.no_response = data.counters.no_response.wrapping_add(1);
+            continue;
+        }
+
+        let faults = data.lock().unwrap().faults;
+        if dice.roll(faults.dropped) {
+            continue;
+        }
+        if !faults.delay.is_zero() {
+            tokio::time::sleep(faults.delay).await;
+        }
+        let mut adu = encode_adu(slave_id, &Bytes::from(resp));
+        if dice.roll(faults.crc_errors) {
+            let last = adu.len() - 1;
+            adu[last] = !adu[last];
+        }
+        let sent = match port.write_all(&adu).await {
+            Ok(()) => port.flush().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            let _ = events.send(SimulatorEvent::Error(e.to_string()));
+            return;
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use super::{respond, SimulatorData, SimulatorEvent};
+use std::future::{ready, Ready};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_modbus::prelude::{Response, SlaveRequest};
+use tokio_modbus::server::tcp::Server;
+use tokio_modbus::server::Service;
+
+struct SimulatorService {
+    data: Arc<Mutex<SimulatorData>>,
+    events: UnboundedSender<SimulatorEvent>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Service for SimulatorService {
+    type Request = SlaveRequest;
+    type Response = Response;
+    type Error = std::io::Error;
+    type Future = Ready<Result<Response, std::io::Error>>;
+
+    fn call(&self, req: SlaveRequest) -> Self::Future {
+        // Clients still connected when the simulator stops are dropped here.
+        if self.stopped.load(Ordering::Relaxed) {
+            return ready(Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                "Simulator stopped",
+            )));
+        }
+        // Every unit id is answered, each as itself.
+        ready(Ok(respond(&self.data, req.slave, req.request, &self.events)))
+    }
+}
+
+pub async fn run(
+    port: u16,
+    data: Arc<Mutex<SimulatorData>>,
+    events: UnboundedSender<SimulatorEvent>,
+    stopped: Arc<AtomicBool>,
+) {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port));
+    let _ = events.send(SimulatorEvent::Listening(format!("TCP {}", addr)));
+    let server = Server::new(addr);
+    let errors = events.clone();
+    let new_service = move || {
+        Ok(SimulatorService {
+            data: data.clone(),
+            events: events.clone(),
+            stopped: stopped.clone(),
+        })
+    };
+    if let Err(e) = server.serve(new_service).await {
+        let _ = errors.send(SimulatorEvent::Error(e.to_string()));
+    }
+}